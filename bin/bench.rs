@@ -13,49 +13,100 @@ pub struct Opts {
 	#[structopt(short = "t", long, default_value = "5")]
 	pub time: f32,
 
-	/// Engines to benchmark
+	/// Benchmark on these positions (FEN) instead of random boards. May be repeated.
+	#[structopt(long, number_of_values = 1)]
+	pub fen: Vec<String>,
+
+	/// List the algorithms and evaluations engines can be made of, and exit.
+	#[structopt(long)]
+	pub list_engines: bool,
+
+	/// Engines to benchmark, e.g. `alphabeta(depth=3,qsearch=on)` (see --list-engines).
 	#[structopt()]
 	pub engines: Vec<String>,
 }
 
 fn main() -> Result<()> {
 	let opts = Opts::from_args();
-	let boards = random_boards(512);
+	if opts.list_engines {
+		print!("{}", list_engines());
+		return Ok(());
+	}
+	let positions = match opts.fen.len() {
+		0 => random_positions(512),
+		_ => opts.fen.iter().map(|fen| Position::from_fen(fen)).collect::<Result<Vec<_>>>()?,
+	};
 	//let mut rng = StdRng::seed_from_u64(opts.seed);
 
 	let engines = opts.engines.iter().map(|name| parse_engine(name)).collect::<Result<Vec<_>>>()?;
 
+	// nodes per search of the first engine, to compare the others to (e.g. the same search with and without a feature).
+	let mut baseline = None;
 	for (i, engine) in engines.into_iter().enumerate() {
 		let name = &opts.engines[i];
 		let bench_time = Duration::from_secs_f32(opts.time);
 
 		let start = Instant::now();
+		let mut search_time = Duration::ZERO;
 		let mut evals = 0;
-
-		while start.elapsed() < bench_time {
-			for board in &boards {
-				engine.eval_moves(board, White);
-				engine.eval_moves(board, Black);
-				evals += 2;
+		let mut nodes = 0;
+		let mut stats = SearchStats::default();
+
+		// only whole passes over the positions (at least one). Every search starts from an empty transposition table,
+		// so that nodes/eval does not depend on the number of passes (i.e. on `--time`), and different engines compare.
+		// Clearing the table is not part of the search time.
+		loop {
+			for position in &positions {
+				engine.new_game();
+				let search_start = Instant::now();
+				let result = engine.search(position, &SearchLimits::none(), None, &mut |_| ());
+				search_time += search_start.elapsed();
+				nodes += result.nodes;
+				stats.fail_highs += result.stats.fail_highs;
+				stats.fail_lows += result.stats.fail_lows;
+				stats.researches += result.stats.researches;
+				evals += 1;
+			}
+			if start.elapsed() >= bench_time {
+				break;
 			}
 		}
 
-		let secs = start.elapsed().as_secs_f32();
+		let secs = search_time.as_secs_f32();
+		let nodes_per_eval = nodes as f64 / evals as f64;
+		let relative = match baseline {
+			Some(base) if base > 0.0 => format!(" ({:>+6.1}%)", (nodes_per_eval / base - 1.0) * 100.0),
+			_ => String::new(),
+		};
+		baseline.get_or_insert(nodes_per_eval);
 		println!(
-			"{:<8}:  {:>2.3} s/eval, {:>8.1} evals/s",
+			"{:<8}:  {:>2.3} s/eval, {:>8.1} evals/s, {:>10.0} nodes/eval{}, {:>10.0} nodes/s",
 			name,
 			(secs / evals as f32),
-			(evals as f32 / secs)
+			(evals as f32 / secs),
+			nodes_per_eval,
+			relative,
+			(nodes as f32 / secs)
 		);
+		if stats != SearchStats::default() {
+			println!(
+				"{:<8}   {:>2.2} fail-highs/eval, {:>2.2} fail-lows/eval, {:>2.2} re-searches/eval",
+				"",
+				stats.fail_highs as f64 / evals as f64,
+				stats.fail_lows as f64 / evals as f64,
+				stats.researches as f64 / evals as f64
+			);
+		}
 	}
 
 	Ok(())
 }
 
-fn random_boards(n: usize) -> Vec<Board> {
+/// `n` random boards, each with White and with Black to move.
+fn random_positions(n: usize) -> Vec<Position> {
 	let seed = 12345;
 	let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
-	let mut boards = Vec::with_capacity(n);
+	let mut positions = Vec::with_capacity(2 * n);
 
 	for _ in 0..n {
 		let mut board = Board::starting_position();
@@ -73,7 +124,7 @@ fn random_boards(n: usize) -> Vec<Board> {
 		for mv in 0..rng.gen_range(0..100) {
 			let player = if mv % 2 == 0 { Color::White } else { Color::Black };
 			let moves = board.collect_moves(player);
-			if moves.len() != 0 {
+			if !moves.is_empty() {
 				let mv = moves[rng.gen_range(0..moves.len())];
 				if !board.at(mv.to).is_king() {
 					board = board.with_move(mv)
@@ -81,8 +132,9 @@ fn random_boards(n: usize) -> Vec<Board> {
 			}
 		}
 
-		boards.push(board);
+		positions.push(Position::new(board.clone(), White));
+		positions.push(Position::new(board, Black));
 	}
 
-	boards
+	positions
 }
@@ -1,7 +1,8 @@
+use std::fs;
 use std::io;
 use std::io::prelude::*;
-use std::io::BufRead;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use bitboard::*;
 use structopt::*;
@@ -19,6 +20,23 @@ pub struct Opts {
 	/// Search depth
 	#[structopt(short, long, default_value = "4")]
 	pub depth: u32,
+
+	/// Think this many seconds per move (at most `depth` deep).
+	#[structopt(short, long)]
+	pub time: Option<f32>,
+
+	/// Visit at most this many positions per move (at most `depth` deep).
+	#[structopt(long)]
+	pub nodes: Option<u64>,
+
+	/// Start from this position (FEN) instead of the starting position.
+	/// The human plays White.
+	#[structopt(long)]
+	pub fen: Option<String>,
+
+	/// Save the game to this PGN file (after every move).
+	#[structopt(long)]
+	pub pgn: Option<PathBuf>,
 }
 
 impl Opts {
@@ -28,101 +46,113 @@ impl Opts {
 	}
 }
 fn main() {
+	if let Err(e) = main_result() {
+		eprintln!("Error {}", e);
+		std::process::exit(1);
+	}
+}
+
+fn main_result() -> Result<()> {
 	let opts = Opts::from_args();
 
-	let mut board = Board::starting_position();
+	let mut position = match &opts.fen {
+		None => Position::starting_position(),
+		Some(fen) => Position::from_fen(fen)?,
+	};
 
 	let mut rng = StdRng::seed_from_u64(opts.seed);
 
-	let engine = ParAlphaBeta::new(opts.depth, heuristic1);
+	let limits = SearchLimits {
+		depth: Some(opts.depth + 1),
+		time: opts.time.map(Duration::from_secs_f32),
+		nodes: opts.nodes,
+	};
+	let engine = ParAlphaBeta::new(opts.depth, heuristic1).with_limits(limits);
 
-	print_ansi(&board, &Set::default());
-	loop {
-		let mv = play_human(&board, White).expect("White resigns");
-		board = board.with_move(mv);
-		print_ansi(&board, &[mv.from, mv.to].into_iter().collect());
+	let mut game = Game::new(position.clone());
+	game.set_tag("Event", "cli")?;
+	game.set_tag("White", "human")?;
+	game.set_tag("Black", &format!("ParAlphaBeta depth {} heuristic1", opts.depth))?;
+	game.set_tag("Seed", &opts.seed.to_string())?;
 
-		if let Some(winner) = winner(&board) {
-			println!("{} wins", winner);
-			break;
+	print_ansi(position.board(), &Set::default());
+	loop {
+		let mv = match position.player() {
+			White => play_human(&position).expect("White resigns"),
+			Black => {
+				let (mv, result) = play_machine(&mut rng, &engine, &position);
+				let mv = mv.expect("Black resigns");
+				let ms = result.time.as_secs_f32() * 1e3;
+				println!(
+					"{}> {} (depth {}, {}, {} nodes, {:.1}ms)",
+					position.player(),
+					mv.to_san(position.board()),
+					result.depth,
+					result.score,
+					result.nodes,
+					ms
+				);
+				mv
+			}
+		};
+		game.push(mv);
+		position.play(mv);
+		print_ansi(position.board(), &[mv.from, mv.to].into_iter().collect());
+		if opts.v(1) {
+			println!("{}", position.to_fen());
 		}
 
-		let start = Instant::now();
-		let mv = play_machine(&mut rng, &engine, &board, Black).expect("Black resigns");
-		let ms = start.elapsed().as_secs_f32() * 1e3;
-		println!("Black> {} ({:.1}ms)", annotate_move(&board, mv), ms);
-		board = board.with_move(mv);
-		print_ansi(&board, &[mv.from, mv.to].into_iter().collect());
-
-		if let Some(winner) = winner(&board) {
-			println!("{} wins", winner);
-			break;
+		let outcome = position.outcome();
+		if let Some(outcome) = outcome {
+			game.result = GameResult::from_winner(outcome.winner());
+		}
+		if let Some(path) = &opts.pgn {
+			save_pgn(path, &game)?;
+		}
+		if let Some(outcome) = outcome {
+			println!("{}", outcome);
+			return Ok(());
 		}
 	}
 }
 
-fn play_human(board: &Board, color: Color) -> Option<Move> {
+fn save_pgn(path: &Path, game: &Game) -> Result<()> {
+	fs::write(path, game.to_string()).map_err(|e| format_err!("{}: {}", path.display(), e))
+}
+
+fn play_human(position: &Position) -> Option<Move> {
 	loop {
-		print!("{}> ", color);
+		print!("{}> ", position.player());
 		io::stdout().flush().expect("stdio error");
-		let allowed = board //
-			.iter_moves(color)
-			.filter(|&mv| !board.with_move(mv).is_check(color))
-			.collect::<Vec<_>>();
+		let allowed = position.legal_moves();
 
 		let mut line = String::new();
-		io::stdin().read_line(&mut line).expect("read from stdin");
+		if io::stdin().read_line(&mut line).expect("read from stdin") == 0 {
+			return None; // end of input
+		}
 		let line = line.trim();
 
+		// SAN (Nf3), or else (part of) coordinates (g1f3).
+		if let Ok(mv) = position.parse_san(line) {
+			return Some(mv);
+		}
+
 		let have = allowed //
 			.iter()
 			.copied()
 			.filter(|mv| mv.to_string().contains(line))
 			.collect::<Vec<_>>();
 
+		let san = |moves: &[Move]| moves.iter().map(|mv| mv.to_san(position.board())).collect::<Vec<_>>().join(" ");
 		match &have[..] {
-			&[] => println!("invalid move: {}, options: {:?}", &line, &allowed),
+			&[] => println!("invalid move: {}, options: {}", &line, san(&allowed)),
 			&[mv] => return Some(mv),
-			ambigous => println!("ambiguous move: {}, options: {:?}", &line, ambigous),
+			ambigous => println!("ambiguous move: {}, options: {}", &line, san(ambigous)),
 		}
 	}
 }
 
-fn play_machine(rng: &mut StdRng, engine: &dyn Engine, board: &Board, color: Color) -> Option<Move> {
-	pick_best_with_tiebreak(rng, &engine.eval_moves(board, color))
-}
-/// Full chess notation of move `mv`. E.g.:
-///   p b2c3 xn +
-pub fn annotate_move(board: &Board, mv: Move) -> String {
-	// piece...
-	let mut str = board.at(mv.from).to_string().to_ascii_uppercase();
-
-	// ...moves to
-	str += &mv.to_string();
-
-	// ... captures?
-	if !board.at(mv.to).is_empty() {
-		str += "x";
-		str += &board.at(mv.to).to_string().to_ascii_uppercase();
-	}
-
-	if let Some(player) = board.at(mv.from).color() {
-		// ... checkmate?
-		if is_mate(&board.with_move(mv), player.opposite()) {
-			str += "#";
-		// ...or just check?
-		} else if board.with_move(mv).is_check(player.opposite()) {
-			str += "+";
-		}
-	}
-	str
-}
-
-fn winner(board: &Board) -> Option<Color> {
-	for player in [White, Black] {
-		if is_mate(board, player) {
-			return Some(player.opposite());
-		}
-	}
-	None
+fn play_machine(rng: &mut StdRng, engine: &dyn Engine, position: &Position) -> (Option<Move>, SearchResult) {
+	let result = engine.search(position, &SearchLimits::none(), None, &mut |_| ());
+	(pick_best_with_tiebreak(rng, &result.exact_root_moves()), result)
 }
@@ -1,3 +1,7 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
 use bitboard::*;
 use structopt::*;
 
@@ -19,6 +23,16 @@ pub struct Opts {
 	#[structopt(short, long, default_value = "0")]
 	pub verbosity: u32,
 
+	/// Save all games to this PGN file, with the engines' evaluation of each move as a comment
+	/// (in pawns, or #moves to mate, from White's point of view).
+	#[structopt(long)]
+	pub pgn: Option<PathBuf>,
+
+	/// List the algorithms and evaluations engines can be made of, and exit.
+	#[structopt(long)]
+	pub list_engines: bool,
+
+	/// The two engines to compare, e.g. `alphabeta(depth=3,qsearch=on)` `greedy` (see --list-engines).
 	#[structopt()]
 	pub engines: Vec<String>,
 }
@@ -38,11 +52,21 @@ fn main() {
 
 fn main_result() -> Result<()> {
 	let opts = Opts::from_args();
+	if opts.list_engines {
+		print!("{}", list_engines());
+		return Ok(());
+	}
+	if opts.engines.len() != 2 {
+		return Err(format_err!(
+			"need two engines to compare, got {} (see --list-engines)",
+			opts.engines.len()
+		));
+	}
 
 	let a = parse_engine(&opts.engines[0])?;
 	let b = parse_engine(&opts.engines[1])?;
 
-	let stats = play_match(&opts, &[a.as_ref(), b.as_ref()], [&opts.engines[0], &opts.engines[1]]);
+	let stats = play_match(&opts, &[a.as_ref(), b.as_ref()], [&opts.engines[0], &opts.engines[1]])?;
 
 	println!("{}", stats);
 
@@ -120,9 +144,10 @@ impl fmt::Display for PlayerStats {
 
 impl fmt::Display for MatchStats {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		Ok(for player_stats in &self.wins {
-			write!(f, "{}\n", player_stats)?;
-		})
+		for player_stats in &self.wins {
+			writeln!(f, "{}", player_stats)?;
+		}
+		Ok(())
 	}
 }
 
@@ -130,15 +155,32 @@ struct GameStats {
 	winner: Option<Color>,
 	plies: u32,
 	board: Board,
+	game: Game,
 }
 
-fn play_match(opts: &Opts, engines: &[&dyn Engine; 2], names: [&str; 2]) -> MatchStats {
+fn play_match(opts: &Opts, engines: &[&dyn Engine; 2], names: [&str; 2]) -> Result<MatchStats> {
 	let mut match_stats = MatchStats::new(names);
+	let mut pgn = match &opts.pgn {
+		None => None,
+		Some(path) => Some(BufWriter::new(File::create(path).map_err(|e| format_err!("{}: {}", path.display(), e))?)),
+	};
 	for i in 0..opts.num_games {
 		let seed = opts.seed * 10000 + i as u64;
-		let game_stats = play_game(opts, seed, engines);
+		let mut game_stats = play_game(opts, seed, engines);
 		match_stats.add(&game_stats);
 
+		if let Some(pgn) = &mut pgn {
+			let game = &mut game_stats.game;
+			game.set_tag("Event", "cmp")?;
+			game.set_tag("Round", &(i + 1).to_string())?;
+			game.set_tag("White", names[0])?;
+			game.set_tag("Black", names[1])?;
+			game.set_tag("Seed", &seed.to_string())?;
+			game.set_tag("PlyCount", &game.moves.len().to_string())?;
+			writeln!(pgn, "{}", game)?;
+			pgn.flush()?;
+		}
+
 		if opts.verbosity == 0 {
 			println!("{}\x1b[4A", &match_stats);
 		}
@@ -156,56 +198,64 @@ fn play_match(opts: &Opts, engines: &[&dyn Engine; 2], names: [&str; 2]) -> Matc
 			print_ansi(&game_stats.board, &Set::default())
 		}
 	}
-	match_stats
+	Ok(match_stats)
 }
 
+/// Play one game. Each move is annotated with the score its engine gave it.
 // TODO: alternate engines between White and Black
 fn play_game(opts: &Opts, seed: u64, engines: &[&dyn Engine; 2]) -> GameStats {
-	let mut board = Board::starting_position();
+	let mut position = Position::starting_position();
+	let mut game = Game::new(position.clone());
 
 	let mut rng = StdRng::seed_from_u64(seed);
-	let mut player = White;
 
 	let max_plies = 2 * opts.max_turns;
 	for ply in 0..=max_plies {
-		let mv = match pick_move(&mut rng, &engines[player.index()].eval_moves(&board, player)) {
+		let player = position.player();
+		let result = engines[player.index()].search(&position, &SearchLimits::none(), None, &mut |_| ());
+		let mv = match pick_move(&mut rng, &result.exact_root_moves()) {
 			None => {
 				// player has not valid moves or resigns.
+				game.result = GameResult::from_winner(Some(player.opposite()));
 				return GameStats {
 					winner: Some(player.opposite()),
 					plies: ply,
-					board,
+					board: position.board().clone(),
+					game,
 				};
 			}
 			Some(mv) => mv,
 		};
 
-		board = board.with_move(mv);
+		let score = result.score_of(mv).expect("picked a root move");
+		game.push(mv).comment = Some(eval_comment(score, player));
+		position.play(mv);
 
 		if opts.v(3) {
-			print_ansi(&board, &[mv.from, mv.to].into_iter().collect())
+			print_ansi(position.board(), &[mv.from, mv.to].into_iter().collect())
 		}
 
-		if let Some(winner) = winner(&board) {
+		if let Some(outcome) = position.outcome() {
+			game.result = GameResult::from_winner(outcome.winner());
 			return GameStats {
-				winner: Some(winner),
-				board,
+				winner: outcome.winner(),
+				board: position.board().clone(),
 				plies: ply,
+				game,
 			};
 		}
 
-		if board.is_check(player) {
+		if position.board().is_check(player) {
 			panic!("{} checked their self", player);
 		}
-
-		player = player.opposite();
 	}
 
 	// too many moves
 	GameStats {
 		winner: None,
-		board,
+		board: position.board().clone(),
 		plies: max_plies,
+		game,
 	}
 }
 
@@ -213,11 +263,14 @@ fn pick_move(rng: &mut StdRng, options: &[(Move, i32)]) -> Option<Move> {
 	pick_randomized_within(rng, options, 3, 500 /*half a pawn*/)
 }
 
-fn winner(board: &Board) -> Option<Color> {
-	for player in [White, Black] {
-		if is_mate(board, player) {
-			return Some(player.opposite());
-		}
+/// `score` for `player` as a PGN comment: pawns (`+0.35`) or moves to mate (`#-3`), from White's point of view.
+fn eval_comment(score: Score, player: Color) -> String {
+	let sign = match player {
+		White => 1,
+		Black => -1,
+	};
+	match score {
+		Score::Cp(cp) => format!("{:+.2}", (sign * cp) as f32 / 100.0),
+		Score::Mate(moves) => format!("#{}", sign * moves),
 	}
-	None
 }
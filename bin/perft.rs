@@ -0,0 +1,108 @@
+use bitboard::*;
+use std::time::Instant;
+use structopt::*;
+
+#[derive(StructOpt)]
+pub struct Opts {
+	/// Maximum search depth
+	#[structopt(short, long, default_value = "4")]
+	pub depth: u32,
+
+	/// Run on this position (FEN) instead of the standard suite.
+	#[structopt(long)]
+	pub fen: Option<String>,
+
+	/// Print node counts per root move (at exactly `depth`).
+	#[structopt(long)]
+	pub divide: bool,
+
+	/// Only run suite positions with these names (start, kiwipete, position3, ...).
+	#[structopt()]
+	pub names: Vec<String>,
+}
+
+fn main() {
+	match main_result() {
+		Err(e) => {
+			eprintln!("Error {}", e);
+			std::process::exit(1);
+		}
+		Ok(false) => std::process::exit(1),
+		Ok(true) => (),
+	}
+}
+
+/// Returns false if any node count differs from the published value.
+fn main_result() -> Result<bool> {
+	let opts = Opts::from_args();
+	if opts.divide && opts.depth == 0 {
+		return Err(format_err!("--divide needs a depth of at least 1"));
+	}
+
+	if let Some(fen) = &opts.fen {
+		let position = Position::from_fen(fen)?;
+		if opts.divide {
+			divide(&position, opts.depth);
+		} else {
+			for depth in 1..=opts.depth {
+				run(&position, depth);
+			}
+		}
+		return Ok(true);
+	}
+
+	for name in &opts.names {
+		if !PERFT_SUITE.iter().any(|(n, _, _)| n == name) {
+			return Err(format_err!("unknown position '{}'", name));
+		}
+	}
+
+	let mut ok = true;
+	for (name, fen, want) in PERFT_SUITE {
+		if !opts.names.is_empty() && !opts.names.iter().any(|n| n == name) {
+			continue;
+		}
+		println!("{}: {}", name, fen);
+		let position = Position::from_fen(fen)?;
+
+		if opts.divide {
+			divide(&position, opts.depth);
+			continue;
+		}
+
+		for (depth, &want) in (1..=opts.depth).zip(want.iter()) {
+			let got = run(&position, depth);
+			if got != want {
+				println!("  MISMATCH: want {}", want);
+				ok = false;
+			}
+		}
+	}
+
+	println!("{}", if ok { "OK" } else { "FAIL" });
+	Ok(ok)
+}
+
+/// Perft with timing output, returns the node count.
+fn run(position: &Position, depth: u32) -> u64 {
+	let start = Instant::now();
+	let nodes = position.perft(depth);
+	let secs = start.elapsed().as_secs_f64();
+	println!(
+		"  depth {}: {:>12} nodes {:>8.3} s {:>12.0} nodes/s",
+		depth,
+		nodes,
+		secs,
+		nodes as f64 / secs
+	);
+	nodes
+}
+
+fn divide(position: &Position, depth: u32) {
+	let mut total = 0;
+	for (mv, nodes) in position.divide(depth) {
+		println!("{}: {}", mv, nodes);
+		total += nodes;
+	}
+	println!("\nmoves: {}\nnodes: {}", position.legal_moves().len(), total);
+}
@@ -0,0 +1,259 @@
+//! Universal Chess Interface over stdin/stdout,
+//! for GUIs and tournament runners (https://www.shredderchess.com/download/div/uci.zip).
+
+use std::io;
+use std::io::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use bitboard::*;
+
+type BoxedEngine = Box<dyn Engine + Send>;
+
+/// Engine spec (see `parse_engine`) used until `setoption name Engine value ...` picks another.
+/// Engines search as deep as their spec allows (by default: until the time, nodes or depth given by `go` run out),
+/// with a transposition table of `Hash` megabytes unless their spec gives a size.
+const DEFAULT_ENGINE: &str = "palphabeta(eval=pst,qsearch=on)";
+const MAX_HASH_MB: usize = MAX_MEGABYTES as usize;
+
+/// Time kept in reserve when playing on the clock, for communication overhead.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+fn main() {
+	if let Err(e) = main_result() {
+		eprintln!("Error {}", e);
+		std::process::exit(1);
+	}
+}
+
+fn main_result() -> Result<()> {
+	let mut uci = Uci::new();
+	for line in io::stdin().lock().lines() {
+		let line = line?;
+		let words = line.split_whitespace().collect::<Vec<_>>();
+		let Some((&command, args)) = words.split_first() else {
+			continue;
+		};
+		if command == "quit" {
+			uci.stop();
+			return Ok(());
+		}
+		if let Err(e) = uci.handle(command, args) {
+			println!("info string error: {}", e);
+		}
+	}
+	// end of input: let a running search finish, as when a script is piped in.
+	uci.finish();
+	Ok(())
+}
+
+struct Uci {
+	position: Position,
+	engine_spec: String,
+	hash_mb: usize,
+	/// Built from `engine_spec` and `hash_mb`; locked by the running search.
+	engine: Arc<Mutex<BoxedEngine>>,
+	/// Signal the running search (if any) to stop.
+	stop: Arc<AtomicBool>,
+	search: Option<JoinHandle<()>>,
+	/// Does the running search wait for `stop`?
+	infinite: bool,
+}
+
+impl Uci {
+	fn new() -> Self {
+		Self {
+			position: Position::starting_position(),
+			engine_spec: DEFAULT_ENGINE.to_owned(),
+			hash_mb: DEFAULT_TT_MB,
+			engine: Arc::new(Mutex::new(build_engine(DEFAULT_ENGINE, DEFAULT_TT_MB).expect("valid default engine"))),
+			stop: Arc::new(AtomicBool::new(false)),
+			search: None,
+			infinite: false,
+		}
+	}
+
+	/// Handle one command. Unknown commands are ignored, as the protocol asks.
+	fn handle(&mut self, command: &str, args: &[&str]) -> Result<()> {
+		match command {
+			"uci" => self.uci(),
+			"isready" => println!("readyok"),
+			"ucinewgame" => {
+				self.stop();
+				self.engine.lock().unwrap().new_game();
+				self.position = Position::starting_position();
+			}
+			"setoption" => {
+				self.stop();
+				self.set_option(args)?;
+			}
+			"position" => {
+				self.stop();
+				self.position = parse_position(args)?;
+			}
+			"go" => {
+				self.stop();
+				self.go(args)?;
+			}
+			"stop" => self.stop(),
+			_ => (),
+		}
+		Ok(())
+	}
+
+	fn uci(&self) {
+		println!("id name bitboard_chess {}", env!("CARGO_PKG_VERSION"));
+		println!("id author the bitboard_chess authors");
+		println!("option name Hash type spin default {} min 1 max {}", DEFAULT_TT_MB, MAX_HASH_MB);
+		println!("option name Engine type string default {}", DEFAULT_ENGINE);
+		println!("uciok");
+	}
+
+	/// `setoption name <id> [value <x>]`.
+	fn set_option(&mut self, args: &[&str]) -> Result<()> {
+		let value_at = args.iter().position(|&w| w == "value").unwrap_or(args.len());
+		let name = args.get(1..value_at).unwrap_or_default().join(" ");
+		let value = args.get(value_at + 1..).unwrap_or_default().join(" ");
+		match name.to_lowercase().as_str() {
+			"hash" => {
+				let mb = value.parse::<usize>().map_err(|_| format_err!("invalid hash size: {}", value))?;
+				self.hash_mb = mb.clamp(1, MAX_HASH_MB);
+				self.engine = Arc::new(Mutex::new(build_engine(&self.engine_spec, self.hash_mb)?));
+			}
+			"engine" => {
+				self.engine = Arc::new(Mutex::new(build_engine(&value, self.hash_mb)?));
+				self.engine_spec = value;
+			}
+			_ => return Err(format_err!("unknown option: {}", name)),
+		}
+		Ok(())
+	}
+
+	/// Start searching in the background. The search prints `info` lines and finally `bestmove`.
+	fn go(&mut self, args: &[&str]) -> Result<()> {
+		let (limits, infinite) = parse_go(args, self.position.player())?;
+		let position = self.position.clone();
+		let engine = self.engine.clone();
+		self.stop = Arc::new(AtomicBool::new(false));
+		let stop = self.stop.clone();
+		self.infinite = infinite;
+		self.search = Some(thread::spawn(move || {
+			search(engine.lock().unwrap().as_ref(), &position, &stop, &limits, infinite)
+		}));
+		Ok(())
+	}
+
+	/// Stop the running search (if any), and wait for its `bestmove`.
+	fn stop(&mut self) {
+		self.stop.store(true, Relaxed);
+		self.finish();
+	}
+
+	/// Wait for the running search (if any) to finish. Infinite searches are stopped.
+	fn finish(&mut self) {
+		if self.infinite {
+			self.stop.store(true, Relaxed);
+		}
+		if let Some(search) = self.search.take() {
+			search.join().expect("search panicked");
+		}
+		self.infinite = false;
+	}
+}
+
+/// The engine `spec` describes, by default searching as deep as `go` allows, with a transposition table of `hash_mb`.
+fn build_engine(spec: &str, hash_mb: usize) -> Result<BoxedEngine> {
+	let depth = (MAX_DEPTH - 1).to_string();
+	let tt = format!("{}mb", hash_mb);
+	parse_engine_with_defaults(spec, &[("depth", &depth), ("tt", &tt)])
+}
+
+/// `position startpos|fen <fen> [moves <move>...]`, with moves in coordinate notation (`e2e4`, `e7e8q`).
+fn parse_position(args: &[&str]) -> Result<Position> {
+	let moves_at = args.iter().position(|&w| w == "moves").unwrap_or(args.len());
+	let mut position = match args.first() {
+		Some(&"startpos") => Position::starting_position(),
+		Some(&"fen") => Position::from_fen(&args[1..moves_at].join(" "))?,
+		_ => return Err(format_err!("expected 'startpos' or 'fen': {}", args.join(" "))),
+	};
+
+	for &word in args.get(moves_at + 1..).unwrap_or_default() {
+		let mv = position
+			.legal_moves()
+			.into_iter()
+			.find(|mv| mv.to_string() == word)
+			.ok_or_else(|| format_err!("illegal move: {}", word))?;
+		position.play(mv);
+	}
+	Ok(position)
+}
+
+/// `go [depth <plies>] [nodes <n>] [movetime <ms>] [wtime <ms>] [btime <ms>] [winc <ms>] [binc <ms>] [movestogo <n>] [infinite]`.
+/// Returns the limits, and whether to keep searching until `stop`.
+fn parse_go(args: &[&str], player: Color) -> Result<(SearchLimits, bool)> {
+	let mut limits = SearchLimits::none();
+	let mut infinite = false;
+	let mut clock = [None, None];
+	let mut inc = [Duration::ZERO, Duration::ZERO];
+	let mut moves_to_go = None;
+
+	let mut args = args.iter();
+	while let Some(&word) = args.next() {
+		let mut number = || {
+			let value = args.next().ok_or_else(|| format_err!("missing value for {}", word))?;
+			value.parse::<u64>().map_err(|_| format_err!("invalid value for {}: {}", word, value))
+		};
+		match word {
+			"depth" => limits.depth = Some(number()? as u32),
+			"nodes" => limits.nodes = Some(number()?),
+			"movetime" => limits.time = Some(Duration::from_millis(number()?)),
+			"wtime" => clock[White.index()] = Some(Duration::from_millis(number()?)),
+			"btime" => clock[Black.index()] = Some(Duration::from_millis(number()?)),
+			"winc" => inc[White.index()] = Duration::from_millis(number()?),
+			"binc" => inc[Black.index()] = Duration::from_millis(number()?),
+			"movestogo" => moves_to_go = Some(number()? as u32),
+			"infinite" => infinite = true,
+			_ => (), // e.g. ponder, searchmoves
+		}
+	}
+
+	if let (None, Some(remaining)) = (limits.time, clock[player.index()]) {
+		limits.time = Some(time_for_move(remaining, inc[player.index()], moves_to_go));
+	}
+	Ok((limits, infinite))
+}
+
+/// An even share of the remaining time (for `moves_to_go`, or else 30 more moves) plus most of the increment,
+/// but never more than what is left on the clock.
+fn time_for_move(remaining: Duration, inc: Duration, moves_to_go: Option<u32>) -> Duration {
+	let share = remaining / moves_to_go.unwrap_or(30).max(1) + inc * 3 / 4;
+	Duration::min(share, remaining.saturating_sub(MOVE_OVERHEAD)).max(Duration::from_millis(1))
+}
+
+/// Run `engine`, printing `info` after each iteration and `bestmove` at the end.
+fn search(engine: &dyn Engine, position: &Position, stop: &AtomicBool, limits: &SearchLimits, infinite: bool) {
+	let result = engine.search(position, limits, Some(stop), &mut |info| {
+		let ms = info.time.as_millis() as u64;
+		println!(
+			"info depth {} score {} nodes {} nps {} time {} pv {}",
+			info.depth,
+			info.score,
+			info.nodes,
+			info.nodes * 1000 / u64::max(ms, 1),
+			ms,
+			info.pv.iter().map(|mv| mv.to_string()).collect::<Vec<_>>().join(" ")
+		);
+	});
+
+	// the protocol wants no bestmove before the stop command.
+	while infinite && !stop.load(Relaxed) {
+		thread::sleep(Duration::from_millis(1));
+	}
+
+	match result.best_move {
+		Some(mv) => println!("bestmove {}", mv),
+		None => println!("bestmove 0000"),
+	}
+}
@@ -7,10 +7,7 @@ use std::ops::Sub;
 #[test]
 fn random_all_moves() {
 	for mb in &random_boards(1000) {
-		let mut bb = Board::new();
-		for (pos, sq) in mb.iter() {
-			bb.set(pos, sq);
-		}
+		let bb = to_bitboard(mb);
 
 		for player in [White, Black] {
 			let have: Set<Move> = bb.collect_moves(player).iter().copied().collect();
@@ -31,6 +28,102 @@ fn random_all_moves() {
 	}
 }
 
+// Compare BitBoard with_move to Mailbox do_move for all moves on a large number of random boards.
+#[test]
+fn random_with_move() {
+	for mb in &random_boards(300) {
+		let bb = to_bitboard(mb);
+		for player in [White, Black] {
+			for mv in mb.collect_moves(player) {
+				let mut want = mb.clone();
+				want.do_move(mv);
+				let have = bb.with_move(mv);
+				let same = want.iter().all(|(pos, sq)| have.at(pos) == sq)
+					&& have.castling() == want.castling()
+					&& have.en_passant() == want.en_passant()
+					&& have.zobrist() == to_bitboard(&want).zobrist();
+				if !same {
+					println!("move: {}", mv);
+					println!("have: {} {:?} {:?}", &have, have.castling(), have.en_passant());
+					println!("want: {} {:?} {:?}", &want, want.castling(), want.en_passant());
+					panic!("test failed");
+				}
+			}
+		}
+	}
+}
+
+// Compare legal move generation (pins and check masks)
+// to playing each pseudo-legal move and testing for check, for a large number of random boards.
+#[test]
+fn random_legal_moves() {
+	for mb in &random_boards(1000) {
+		let bb = to_bitboard(mb);
+
+		for player in [White, Black] {
+			let have: Set<Move> = bb.legal_moves(player).iter().copied().collect();
+			let want: Set<Move> = bb.iter_moves(player).filter(|&mv| !bb.with_move(mv).is_check(player)).collect();
+
+			if have != want {
+				println!("player: {}", player);
+				println!("{}", &bb);
+				println!("diff: +{:?}, -{:?}", have.sub(&want), want.sub(&have));
+				panic!("test failed");
+			}
+		}
+	}
+}
+
+// Compare `is_legal` to `legal_moves` for the moves of both players, and those of another board,
+// as if they came from a transposition table that confused positions.
+#[test]
+fn random_is_legal() {
+	let boards = random_boards(1000).iter().map(to_bitboard).collect::<Vec<_>>();
+	for (bb, other) in boards.iter().zip(boards.iter().cycle().skip(1)) {
+		let candidates = [bb, other]
+			.iter()
+			.flat_map(|b| [White, Black].map(|player| b.collect_moves(player)))
+			.flatten()
+			.collect::<Vec<_>>();
+
+		for player in [White, Black] {
+			let legal = bb.legal_moves(player);
+			for &mv in &candidates {
+				if bb.is_legal(player, mv) != legal.contains(&mv) {
+					println!("player: {}, move: {}", player, mv);
+					println!("{}", &bb);
+					panic!("test failed");
+				}
+			}
+		}
+	}
+}
+
+// Compare magic bitboard attacks to the sliding reference implementation on a large number of random boards.
+#[test]
+fn random_sliding_attacks() {
+	for mb in &random_boards(1000) {
+		let bb = to_bitboard(mb);
+		for piece in [WRook, WBisshop, WQueen, BRook, BBisshop, BQueen] {
+			let bits = bb.bits(piece);
+			assert_eq!(bb.rook_vector(bits), bb.rook_slide_vector(bits), "{}", &bb);
+			assert_eq!(bb.bisshop_vector(bits), bb.bisshop_slide_vector(bits), "{}", &bb);
+		}
+	}
+}
+
+fn to_bitboard(mb: &Mailbox) -> Board {
+	let mut bb = Board::new();
+	for (pos, sq) in mb.iter() {
+		bb.set(pos, sq);
+	}
+	bb.set_castling(mb.castling());
+	assert_eq!(bb.castling(), mb.castling());
+	bb.set_en_passant(mb.en_passant());
+	assert_eq!(bb.en_passant(), mb.en_passant());
+	bb
+}
+
 fn random_boards(n: usize) -> Vec<Mailbox> {
 	let seed = 12345;
 	let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
@@ -51,13 +144,21 @@ fn random_boards(n: usize) -> Vec<Mailbox> {
 
 		for mv in 0..rng.gen_range(0..100) {
 			let player = if mv % 2 == 0 { Color::White } else { Color::Black };
-			let moves = board.collect_moves(player);
-			if moves.len() != 0 {
+			let mut moves = board.collect_moves(player);
+			// favour pawn moves, so that pawns meet and en-passant captures arise.
+			if rng.gen_bool(0.5) && moves.iter().any(|mv| mv.piece.is_pawn()) {
+				moves.retain(|mv| mv.piece.is_pawn());
+			}
+			if !moves.is_empty() {
 				let mv = moves[rng.gen_range(0..moves.len())];
 				if !board.at(mv.to).is_king() {
 					board.do_move(mv)
 				}
 			}
+			// often stop right after a double push, while en passant is possible.
+			if board.en_passant().is_some() && rng.gen_bool(0.3) {
+				break;
+			}
 		}
 
 		boards.push(board);
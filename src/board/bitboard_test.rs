@@ -100,10 +100,10 @@ fn test_check() {
 	)
 	.unwrap();
 
-	assert_eq!(b1.is_check(White), true);
-	assert_eq!(b1.is_check(Black), false);
-	assert_eq!(is_mate(&b1, White), true);
-	assert_eq!(is_mate(&b1, Black), false);
+	assert!(b1.is_check(White));
+	assert!(!b1.is_check(Black));
+	assert!(is_mate(&b1, White));
+	assert!(!is_mate(&b1, Black));
 }
 
 #[test]
@@ -144,12 +144,12 @@ fn vector() {
 		. . . . . . . .
 		",
 		r"
-		. . . . x x x .
+		. . . . x . x .
 		. . . . . P . .
 		. . . . . . . .
 		. . . . . . . .
-		. . x x x . . p
-		p x x x x x x x
+		. . x . x . . p
+		p x . x . x x .
 		P . P . P . . P
 		. . . . . . . .
 		",
@@ -875,6 +875,358 @@ fn white_black() {
 	);
 }
 
+#[test]
+fn castling_moves() {
+	let castles = |player: Color, board: &str| {
+		let mut board = Board::from_str(board).unwrap();
+		board.set_castling(Castling::ALL);
+		board
+			.collect_moves(player)
+			.into_iter()
+			.filter(|mv| mv.piece.is_king() && mv.from.col() == 4 && (mv.to.col() == 2 || mv.to.col() == 6))
+			.map(|mv| mv.to_string())
+			.collect::<Vec<_>>()
+	};
+
+	// both sides free
+	let board = r"
+		r . . . k . . r
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		R . . . K . . R
+		";
+	assert_eq!(castles(White, board), vec!["e1g1", "e1c1"]);
+	assert_eq!(castles(Black, board), vec!["e8g8", "e8c8"]);
+
+	// blocked by pieces between king and rook (b1 may be attacked, but not occupied)
+	let board = r"
+		r n . . k . b r
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		R N . . K . . R
+		";
+	assert_eq!(castles(White, board), vec!["e1g1"]);
+	assert_eq!(castles(Black, board), Vec::<String>::new());
+
+	// not out of, through or into check
+	let board = r"
+		r . . . k . . r
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . b
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		R . . . K . . R
+		";
+	assert_eq!(castles(White, board), vec!["e1g1"]); // bisshop covers d1
+	let board = r"
+		r . . . k . . r
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . p .
+		R . . . K . . R
+		";
+	assert_eq!(castles(White, board), vec!["e1c1"]); // pawn covers f1, h1
+	let board = r"
+		r . . . k . . r
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		R . . . K . r R
+		";
+	assert_eq!(castles(White, board), Vec::<String>::new()); // in check
+	assert_eq!(castles(Black, board), vec!["e8g8", "e8c8"]); // an attacked rook does not matter
+}
+
+#[test]
+fn castling_with_move() {
+	let mut board = Board::from_str(
+		r"
+		r . . . k . . r
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		R . . . K . . R
+		",
+	)
+	.unwrap();
+	board.set_castling(Castling::ALL);
+
+	let b = board.with_move(Move::from_str("Ke1g1").unwrap());
+	assert_eq!(
+		(b.at(pos(0, 4)), b.at(pos(0, 5)), b.at(pos(0, 6)), b.at(pos(0, 7))),
+		(Empty, WRook, WKing, Empty)
+	);
+	assert_eq!(b.castling().to_string(), "kq");
+
+	let b = board.with_move(Move::from_str("ke8c8").unwrap());
+	assert_eq!(
+		(b.at(pos(7, 0)), b.at(pos(7, 2)), b.at(pos(7, 3)), b.at(pos(7, 4))),
+		(Empty, BKing, BRook, Empty)
+	);
+	assert_eq!(b.castling().to_string(), "KQ");
+
+	// moving a rook, or losing it, loses the right to castle with it.
+	let b = board.with_move(Move::from_str("Ra1a8").unwrap());
+	assert_eq!(b.castling().to_string(), "Kk");
+	let b = board.with_move(Move::from_str("rh8h7").unwrap());
+	assert_eq!(b.castling().to_string(), "KQq");
+}
+
+#[test]
+fn promotion() {
+	test_moves(
+		White,
+		r"
+		. n . . . . . .
+		P . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		",
+		&["Pa7a8q", "Pa7a8r", "Pa7a8b", "Pa7a8n", "Pa7b8q", "Pa7b8r", "Pa7b8b", "Pa7b8n"],
+	);
+	test_moves(
+		Black,
+		r"
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . p
+		. . . . . . . R
+		",
+		&[],
+	);
+	test_moves(
+		Black,
+		r"
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . p .
+		. . . . . . . R
+		",
+		&["pg2g1q", "pg2g1r", "pg2g1b", "pg2g1n", "pg2h1q", "pg2h1r", "pg2h1b", "pg2h1n"],
+	);
+
+	let board = Board::from_str(
+		r"
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . p .
+		. . . . . . . R
+		",
+	)
+	.unwrap();
+	let b = board.with_move(Move::from_str("pg2h1n").unwrap());
+	assert_eq!(b.at(pos(0, 7)), BKnight);
+	assert_eq!(b.at(pos(1, 6)), Empty);
+	assert_eq!(b.piece_count(BPawn), 0);
+	assert_eq!(b.piece_count(WRook), 0);
+	let b = board.with_move(Move::from_str("pg2g1q").unwrap());
+	assert_eq!(b.at(pos(0, 6)), BQueen);
+	assert_eq!(b.at(pos(0, 7)), WRook);
+}
+
+#[test]
+fn en_passant() {
+	let board = Board::from_str(
+		r"
+		. . . . k . . .
+		. . . p . . . .
+		. . . . . . . .
+		. . P . P . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . K . . .
+		",
+	)
+	.unwrap();
+	assert_eq!(board.en_passant(), None);
+
+	let board = board.with_move(Move::from_str("pd7d5").unwrap());
+	assert_eq!(board.en_passant(), Some(pos(5, 3)));
+	let ep = |board: &Board, player| {
+		board
+			.collect_moves(player)
+			.into_iter()
+			.filter(|mv| Some(mv.to) == board.en_passant())
+			.collect::<Set<_>>()
+	};
+	assert_eq!(ep(&board, White), ["Pc5d6", "Pe5d6"].iter().map(|s| Move::from_str(s).unwrap()).collect());
+	assert_eq!(ep(&board, Black), Set::default());
+
+	let after = board.with_move(Move::from_str("Pe5d6").unwrap());
+	assert_eq!(after.at(pos(5, 3)), WPawn);
+	assert_eq!(after.at(pos(4, 3)), Empty);
+	assert_eq!(after.at(pos(4, 4)), Empty);
+	assert_eq!(after.en_passant(), None);
+
+	// the opportunity is gone after any other move
+	let after = board.with_move(Move::from_str("Ke1e2").unwrap());
+	assert_eq!(after.en_passant(), None);
+	assert_eq!(ep(&after, White), Set::default());
+
+	// single pushes do not set a target
+	let board = Board::from_str(
+		r"
+		. . . . k . . .
+		. . . p . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . K . . .
+		",
+	)
+	.unwrap();
+	assert_eq!(board.with_move(Move::from_str("pd7d6").unwrap()).en_passant(), None);
+}
+
+#[test]
+fn en_passant_rank_pin() {
+	// Capturing en passant would remove both pawns from row 4, exposing the king to the rook.
+	let mut board = Board::from_str(
+		r"
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. k . P p . . R
+		. . . . . . . .
+		. . . . . . . .
+		. . . . K . . .
+		",
+	)
+	.unwrap();
+	board.set_en_passant(Some(pos(2, 3)));
+	assert_eq!(board.en_passant(), Some(pos(2, 3)));
+
+	let mv = Move::from_str("pe4d3").unwrap();
+	assert!(board.collect_moves(Black).contains(&mv));
+	assert!(!board.with_move(Move::from_str("pe4e3").unwrap()).is_check(Black));
+	assert!(board.with_move(mv).is_check(Black));
+	assert!(!board
+		.iter_moves(Black)
+		.filter(|&mv| !board.with_move(mv).is_check(Black))
+		.any(|m| m == mv));
+	assert!(!board.legal_moves(Black).contains(&mv));
+
+	// the same capture with the rook on another row is fine.
+	board.set(pos(3, 7), Empty);
+	board.set(pos(4, 7), WRook);
+	assert!(!board.with_move(mv).is_check(Black));
+}
+
+#[test]
+fn legal_moves() {
+	// The knight on d2 is pinned by the bisshop on a5,
+	// the rook on e4 checks and can be captured by the queen on a4 or blocked by the bisshop on f1.
+	let board = Board::from_str(
+		r"
+		. . . . k . . .
+		. . . . . . . .
+		. . . . . . . .
+		b . . . . . . .
+		Q . . . r . . .
+		. . . . . . . .
+		. . . N . . . .
+		. . . . K B . .
+		",
+	)
+	.unwrap();
+	let have = board.legal_moves(White).iter().map(|mv| mv.to_string()).collect::<Set<_>>();
+	let want = ["a4e4", "f1e2", "e1f2", "e1d1"].iter().map(|s| s.to_string()).collect::<Set<_>>();
+	assert_eq!(have, want);
+
+	// double check: only the king may move.
+	let board = Board::from_str(
+		r"
+		. . . . k . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. Q . . r . . .
+		. . . . . n . .
+		. . . . . . . .
+		. . . . K B . .
+		",
+	)
+	.unwrap();
+	assert!(board.legal_moves(White).iter().all(|mv| mv.piece == WKing));
+	assert_eq!(board.legal_moves(White).len(), 2);
+}
+
+#[test]
+fn set_en_passant() {
+	let mut board = Board::from_str(
+		r"
+		. . . . k . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . P p . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . K . . .
+		",
+	)
+	.unwrap();
+	board.set_en_passant(Some(pos(2, 3)));
+	assert_eq!(board.en_passant(), Some(pos(2, 3)));
+	board.set_en_passant(Some(pos(2, 4))); // no white pawn in front
+	assert_eq!(board.en_passant(), None);
+	board.set_en_passant(Some(pos(5, 4))); // black pawn is not on its double push row
+	assert_eq!(board.en_passant(), None);
+}
+
+#[test]
+fn with_null_move() {
+	let position = Position::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+	let board = position.board().with_null_move();
+	assert_eq!(board.en_passant(), None);
+	assert_eq!(board.to_string(), position.board().to_string());
+	assert_ne!(board.zobrist(), position.board().zobrist());
+
+	let mut want = position.board().clone();
+	want.set_en_passant(None);
+	assert_eq!(board.zobrist(), want.zobrist());
+	assert_eq!(board.with_null_move().zobrist(), board.zobrist());
+}
+
 fn test_moves(player: Color, board: &str, want: &[&str]) {
 	let board = Board::from_str(board).unwrap();
 	let have = board.collect_moves(player).iter().copied().collect::<Set<_>>();
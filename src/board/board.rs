@@ -2,9 +2,18 @@ use super::internal::*;
 use Color::*;
 use Square::*;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Board {
 	bitfields: [u64; 13],
+	castling: Castling,
+	/// Square skipped over by the last pawn double push (if any), as a bitmask.
+	en_passant: u64,
+	/// Zobrist key of all of the above, updated incrementally.
+	zobrist: u64,
+	/// Zobrist key of just the pawns, updated incrementally.
+	pawn_zobrist: u64,
+	/// Sum of the piece-square values of all pieces, updated incrementally.
+	pst: Tapered,
 }
 
 impl Board {
@@ -12,7 +21,14 @@ impl Board {
 	pub fn new() -> Self {
 		let mut pieces = [0; 13];
 		pieces[Empty.index()] = !0;
-		Self { bitfields: pieces }
+		Self {
+			bitfields: pieces,
+			castling: Castling::NONE,
+			en_passant: 0,
+			zobrist: 0,
+			pawn_zobrist: 0,
+			pst: Tapered::default(),
+		}
 	}
 
 	pub fn starting_position() -> Self {
@@ -21,8 +37,8 @@ impl Board {
 		b.set(pos(0, 0), WRook);
 		b.set(pos(0, 1), WKnight);
 		b.set(pos(0, 2), WBisshop);
-		b.set(pos(0, 3), WKing);
-		b.set(pos(0, 4), WQueen);
+		b.set(pos(0, 3), WQueen);
+		b.set(pos(0, 4), WKing);
 		b.set(pos(0, 5), WBisshop);
 		b.set(pos(0, 6), WKnight);
 		b.set(pos(0, 7), WRook);
@@ -35,12 +51,13 @@ impl Board {
 		b.set(pos(7, 0), BRook);
 		b.set(pos(7, 1), BKnight);
 		b.set(pos(7, 2), BBisshop);
-		b.set(pos(7, 3), BKing);
-		b.set(pos(7, 4), BQueen);
+		b.set(pos(7, 3), BQueen);
+		b.set(pos(7, 4), BKing);
 		b.set(pos(7, 5), BBisshop);
 		b.set(pos(7, 6), BKnight);
 		b.set(pos(7, 7), BRook);
 
+		b.set_castling(Castling::ALL);
 		b
 	}
 
@@ -64,11 +81,135 @@ impl Board {
 	}
 
 	/// Set position to piece.
+	/// Overwriting a king or rook on its original square loses the corresponding castling rights.
 	pub fn set(&mut self, pos: Pos, piece: Square) {
 		debug_assert!(pos.is_valid());
+		self.castling = self.castling.after_touching(pos.index());
 		let pos = pos.index() as u8;
 		self.clear(pos);
 		self.bitfields[piece.index()] |= 1 << pos;
+		self.zobrist = self.compute_zobrist();
+		self.pawn_zobrist = self.compute_pawn_zobrist();
+		self.pst = self.compute_pst();
+	}
+
+	/// Castling rights (not whether castling is currently possible).
+	#[inline]
+	pub fn castling(&self) -> Castling {
+		self.castling
+	}
+
+	/// Set castling rights.
+	/// Rights for which king and rook are not on their original squares are dropped.
+	pub fn set_castling(&mut self, rights: Castling) {
+		let mut castling = Castling::NONE;
+		for (side, king, rook) in [
+			(Castling::W_KINGSIDE, WKing, WRook),
+			(Castling::W_QUEENSIDE, WKing, WRook),
+			(Castling::B_KINGSIDE, BKing, BRook),
+			(Castling::B_QUEENSIDE, BKing, BRook),
+		] {
+			let (king_from, _, rook_from, _) = Self::castling_squares(side);
+			if rights.has(side) && self.bits(king) & king_from != 0 && self.bits(rook) & rook_from != 0 {
+				castling = castling.with(side);
+			}
+		}
+		self.castling = castling;
+		self.zobrist = self.compute_zobrist();
+	}
+
+	/// En-passant target square: the square skipped over by a pawn double push on the previous move,
+	/// if an opposing pawn can capture en passant.
+	#[inline]
+	pub fn en_passant(&self) -> Option<Pos> {
+		match self.en_passant {
+			0 => None,
+			bits => Some(Pos::from_index(bits.trailing_zeros() as usize)),
+		}
+	}
+
+	/// Set the en-passant target square.
+	/// The target is dropped unless it lies right behind a pawn that could just have made a double push,
+	/// and an opposing pawn can capture en passant: otherwise the same positions would hash differently.
+	pub fn set_en_passant(&mut self, target: Option<Pos>) {
+		self.en_passant = match target {
+			None => 0,
+			Some(target) => {
+				let (w_target, b_target) = self.double_push_targets();
+				(1 << target.index()) & ((w_target & self.b_pawn_cover()) | (b_target & self.w_pawn_cover()))
+			}
+		};
+		self.zobrist = self.compute_zobrist();
+	}
+
+	/// Could `target` be the en-passant target square, capture or not?
+	pub(super) fn is_double_push_target(&self, target: Pos) -> bool {
+		let (w_target, b_target) = self.double_push_targets();
+		(1 << target.index()) & (w_target | b_target) != 0
+	}
+
+	/// Squares right behind a white and black pawn that could just have made a double push.
+	fn double_push_targets(&self) -> (u64, u64) {
+		let w_target = sh_s(self.bits(WPawn) & ROW3) & self.empty() & sh_n(self.empty());
+		let b_target = sh_n(self.bits(BPawn) & ROW4) & self.empty() & sh_s(self.empty());
+		(w_target, b_target)
+	}
+
+	/// Zobrist key of the pieces, castling rights and en-passant square,
+	/// for use in hash tables and repetition detection.
+	/// The board does not know whose move it is: `zobrist_for` (or `Position::zobrist`) includes the side to move.
+	#[inline]
+	pub fn zobrist(&self) -> u64 {
+		self.zobrist
+	}
+
+	/// Zobrist key including the side to move.
+	#[inline]
+	pub fn zobrist_for(&self, player: Color) -> u64 {
+		self.zobrist ^ side_key(player)
+	}
+
+	/// Zobrist key of the pawns (of both colors) alone,
+	/// for caching evaluations that only depend on the pawn structure.
+	#[inline]
+	pub fn pawn_zobrist(&self) -> u64 {
+		self.pawn_zobrist
+	}
+
+	/// Zobrist key computed from scratch
+	/// (`with_move` updates it incrementally instead).
+	fn compute_zobrist(&self) -> u64 {
+		let mut key = castling_key(self.castling) ^ en_passant_key(self.en_passant);
+		for piece in Square::ALL_PIECES {
+			for i in iter_bitfield(self.bits(piece)) {
+				key ^= piece_key(piece, i as usize);
+			}
+		}
+		key
+	}
+
+	/// Piece-square values of all pieces (including material), in centipawns from White's point of view.
+	/// Blend with `Tapered::taper(self.phase())`.
+	#[inline]
+	pub fn pst(&self) -> Tapered {
+		self.pst
+	}
+
+	fn compute_pawn_zobrist(&self) -> u64 {
+		iter_bitfield(self.bits(WPawn)).fold(0, |key, i| key ^ piece_key(WPawn, i as usize))
+			^ iter_bitfield(self.bits(BPawn)).fold(0, |key, i| key ^ piece_key(BPawn, i as usize))
+	}
+
+	/// Piece-square values computed from scratch
+	/// (`with_move` updates them incrementally instead).
+	fn compute_pst(&self) -> Tapered {
+		let mut pst = Tapered::default();
+		for piece in Square::ALL_PIECES {
+			for i in iter_bitfield(self.bits(piece)) {
+				pst += pst_value(piece, i as usize);
+			}
+		}
+		pst
 	}
 
 	fn clear(&mut self, pos: u8) {
@@ -78,6 +219,15 @@ impl Board {
 		}
 	}
 
+	/// The board after passing instead of moving (a null move): the same, except that en passant is no longer possible.
+	pub fn with_null_move(&self) -> Self {
+		let mut board = self.clone();
+		board.zobrist ^= en_passant_key(board.en_passant);
+		board.en_passant = 0;
+		debug_assert_eq!(board.zobrist, board.compute_zobrist());
+		board
+	}
+
 	#[inline]
 	pub fn with_move(&self, mv: Move) -> Self {
 		let mut b = self.clone();
@@ -88,17 +238,101 @@ impl Board {
 		let to = 1 << mv.to.index();
 		let clear = !(from | to);
 
+		// the captured piece, if any, leaves the hash.
+		let captured = self.at_bit(to);
+		b.zobrist ^= piece_key(mv.piece, mv.from.index()) ^ piece_key(captured, mv.to.index());
+		b.pawn_zobrist ^= pawn_key(mv.piece, mv.from.index()) ^ pawn_key(captured, mv.to.index());
+		b.pst -= pst_value(mv.piece, mv.from.index()) + pst_value(captured, mv.to.index());
+
 		for sq in Square::ALL_SQUARES {
 			b.bitfields[sq.index()] &= clear;
 		}
 
 		// set `from` and `to` squares.
+		let placed = mv.promotion.unwrap_or(mv.piece);
 		b.bitfields[Empty.index()] |= from;
-		b.bitfields[mv.piece.index()] |= to;
+		b.bitfields[placed.index()] |= to;
+		b.zobrist ^= piece_key(placed, mv.to.index());
+		b.pawn_zobrist ^= pawn_key(placed, mv.to.index());
+		b.pst += pst_value(placed, mv.to.index());
+
+		// en passant: a pawn landing on the target square captures the pawn that just passed it.
+		// A double push only sets the target if an opposing pawn can capture.
+		b.en_passant = 0;
+		match mv.piece {
+			WPawn if to & self.en_passant & ROW5 != 0 => b.capture_en_passant(BPawn, sh_s(to)),
+			BPawn if to & self.en_passant & ROW2 != 0 => b.capture_en_passant(WPawn, sh_n(to)),
+			WPawn if mv.to.row() == mv.from.row() + 2 => b.en_passant = sh_s(to) & b.b_pawn_cover(),
+			BPawn if mv.from.row() == mv.to.row() + 2 => b.en_passant = sh_n(to) & b.w_pawn_cover(),
+			_ => (),
+		}
+
+		// castling: the king moves two columns, the rook jumps over it.
+		if mv.piece.is_king() && (mv.from.col() as i8 - mv.to.col() as i8).abs() == 2 {
+			let (_, _, rook_from, rook_to) = Self::castling_squares(Self::castling_side(mv));
+			let rook = if mv.piece == WKing { WRook } else { BRook };
+			b.bitfields[rook.index()] ^= rook_from | rook_to;
+			b.bitfields[Empty.index()] ^= rook_from | rook_to;
+			b.zobrist ^= piece_key(rook, rook_from.trailing_zeros() as usize) ^ piece_key(rook, rook_to.trailing_zeros() as usize);
+			b.pst += pst_value(rook, rook_to.trailing_zeros() as usize) - pst_value(rook, rook_from.trailing_zeros() as usize);
+		}
+
+		b.castling = b.castling.after_touching(mv.from.index()).after_touching(mv.to.index());
+
+		b.zobrist ^= castling_key(self.castling) ^ castling_key(b.castling);
+		b.zobrist ^= en_passant_key(self.en_passant) ^ en_passant_key(b.en_passant);
+		debug_assert_eq!(b.zobrist, b.compute_zobrist());
+		debug_assert_eq!(b.pawn_zobrist, b.compute_pawn_zobrist());
+		debug_assert_eq!(b.pst, b.compute_pst());
 
 		b
 	}
 
+	#[inline]
+	fn capture_en_passant(&mut self, pawn: Square, captured: u64) {
+		debug_assert!(self.bits(pawn) & captured != 0);
+		self.bitfields[pawn.index()] &= !captured;
+		self.bitfields[Empty.index()] |= captured;
+		self.zobrist ^= piece_key(pawn, captured.trailing_zeros() as usize);
+		self.pawn_zobrist ^= piece_key(pawn, captured.trailing_zeros() as usize);
+		self.pst -= pst_value(pawn, captured.trailing_zeros() as usize);
+	}
+
+	/// Piece on the square given as a bitmask (fast enough for making moves, unlike `at`).
+	#[inline]
+	fn at_bit(&self, bit: u64) -> Square {
+		for sq in Square::ALL_SQUARES {
+			if self.bitfields[sq.index()] & bit != 0 {
+				return sq;
+			}
+		}
+		unreachable!()
+	}
+
+	/// Castling side of a king move that moves two columns.
+	#[inline]
+	fn castling_side(mv: Move) -> Castling {
+		let player = if mv.piece == WKing { White } else { Black };
+		if mv.to.col() > mv.from.col() {
+			Castling::kingside(player)
+		} else {
+			Castling::queenside(player)
+		}
+	}
+
+	/// Bitmasks for castling on `side` (which must be a single side):
+	/// (king from, king to, rook from, rook to).
+	#[inline]
+	fn castling_squares(side: Castling) -> (u64, u64, u64, u64) {
+		match side {
+			Castling::W_KINGSIDE => (1 << 4, 1 << 6, 1 << 7, 1 << 5),
+			Castling::W_QUEENSIDE => (1 << 4, 1 << 2, 1 << 0, 1 << 3),
+			Castling::B_KINGSIDE => (1 << 60, 1 << 62, 1 << 63, 1 << 61),
+			Castling::B_QUEENSIDE => (1 << 60, 1 << 58, 1 << 56, 1 << 59),
+			_ => unreachable!(),
+		}
+	}
+
 	/// All moves for `player`.
 	pub fn collect_moves(&self, player: Color) -> SmVec<Move> {
 		let mut moves = SmVec::new();
@@ -121,12 +355,16 @@ impl Board {
 		Self::unpack_pawn(WPawn, self.w_pawn_push2(), delta(-2, 0), buf);
 		Self::unpack_pawn(WPawn, self.w_pawn_attack_ne(), delta(-1, -1), buf);
 		Self::unpack_pawn(WPawn, self.w_pawn_attack_nw(), delta(-1, 1), buf);
+		Self::unpack_pawn(WPawn, self.w_pawn_en_passant_ne(), delta(-1, -1), buf);
+		Self::unpack_pawn(WPawn, self.w_pawn_en_passant_nw(), delta(-1, 1), buf);
 
 		self.unpack(WKing, |s, b| s.king_moves(b, white), buf);
 		self.unpack(WKnight, |s, b| s.knight_moves(b, white), buf);
 		self.unpack(WRook, |s, b| s.rook_moves(b, white), buf);
 		self.unpack(WBisshop, |s, b| s.bisshop_moves(b, white), buf);
 		self.unpack(WQueen, |s, b| s.queen_moves(b, white), buf);
+
+		self.castling_moves(White, buf);
 	}
 
 	/// All moves for black.
@@ -138,12 +376,49 @@ impl Board {
 		Self::unpack_pawn(BPawn, self.b_pawn_push2(), delta(2, 0), buf);
 		Self::unpack_pawn(BPawn, self.b_pawn_attack_se(), delta(1, -1), buf);
 		Self::unpack_pawn(BPawn, self.b_pawn_attack_sw(), delta(1, 1), buf);
+		Self::unpack_pawn(BPawn, self.b_pawn_en_passant_se(), delta(1, -1), buf);
+		Self::unpack_pawn(BPawn, self.b_pawn_en_passant_sw(), delta(1, 1), buf);
 
 		self.unpack(BKing, |s, b| s.king_moves(b, black), buf);
 		self.unpack(BKnight, |s, b| s.knight_moves(b, black), buf);
 		self.unpack(BRook, |s, b| s.rook_moves(b, black), buf);
 		self.unpack(BBisshop, |s, b| s.bisshop_moves(b, black), buf);
 		self.unpack(BQueen, |s, b| s.queen_moves(b, black), buf);
+
+		self.castling_moves(Black, buf);
+	}
+
+	/// King-side and queen-side castling, if `player` still has the rights,
+	/// the squares between king and rook are empty,
+	/// and the king does not start in, pass through or land in check.
+	fn castling_moves(&self, player: Color, buf: &mut SmVec<Move>) {
+		let king = player.king();
+		let mut attacked = None;
+		for side in [Castling::kingside(player), Castling::queenside(player)] {
+			if !self.castling.has(side) {
+				continue;
+			}
+			let (king_from, king_to, rook_from, _) = Self::castling_squares(side);
+			let between = Self::squares_between(king_from, rook_from);
+			if between & self.empty() != between {
+				continue;
+			}
+			let king_path = king_from | Self::squares_between(king_from, king_to) | king_to;
+			let attacked = *attacked.get_or_insert_with(|| self.attack_vector(player.opposite()));
+			if king_path & attacked != 0 {
+				continue;
+			}
+			let from = Pos::from_index(king_from.trailing_zeros() as usize);
+			let to = Pos::from_index(king_to.trailing_zeros() as usize);
+			buf.push(Move::new(king, from, to));
+		}
+	}
+
+	/// Squares strictly between two squares on the same row.
+	#[inline]
+	fn squares_between(a: u64, b: u64) -> u64 {
+		let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+		(hi - 1) & !(lo | (lo - 1))
 	}
 
 	pub fn attack_vectors(&self) -> AttacVector {
@@ -193,7 +468,7 @@ impl Board {
 			| self.bisshop_vector(self.bits(WQueen) | self.bits(WBisshop))
 			| self.rook_vector(self.bits(WQueen) | self.bits(WRook))
 			| self.knight_vector(self.bits(WKnight))
-			| self.w_pawn_reach()
+			| self.w_pawn_cover()
 	}
 
 	#[inline]
@@ -202,7 +477,14 @@ impl Board {
 			| self.bisshop_vector(self.bits(BQueen) | self.bits(BBisshop))
 			| self.rook_vector(self.bits(BQueen) | self.bits(BRook))
 			| self.knight_vector(self.bits(BKnight))
-			| self.b_pawn_reach()
+			| self.b_pawn_cover()
+	}
+
+	/// Like `attack_vector`, but treating the squares in `transparent` as empty.
+	pub(super) fn attack_vector_through(&self, player: Color, transparent: u64) -> u64 {
+		let mut b = self.clone();
+		b.bitfields[Empty.index()] |= transparent;
+		b.attack_vector(player)
 	}
 
 	fn unpack<F>(&self, piece: Square, f: F, buf: &mut SmVec<Move>)
@@ -213,7 +495,7 @@ impl Board {
 		for i in iter_bitfield(bits) {
 			let from = Pos::from_index(i as usize);
 			let bits = 1 << i;
-			let moves = (&f)(self, bits);
+			let moves = f(self, bits);
 
 			for j in iter_bitfield(moves) {
 				let to = Pos::from_index(j as usize);
@@ -224,12 +506,24 @@ impl Board {
 
 	#[inline]
 	fn unpack_pawn(piece: Square, bits: u64, delta: u8, moves: &mut SmVec<Move>) {
-		// TODO: use count trailing zeros
-		for i in iter_bitfield(bits) {
+		for i in iter_bitfield(bits & !(ROW0 | ROW7)) {
 			let pos = Pos::from_index(i as usize);
 			let from = pos + delta;
 			moves.push(Move::new(piece, from, pos));
 		}
+
+		// reaching the last row: promote to any of these.
+		let promotions = match piece {
+			WPawn => [WQueen, WRook, WBisshop, WKnight],
+			_ => [BQueen, BRook, BBisshop, BKnight],
+		};
+		for i in iter_bitfield(bits & (ROW0 | ROW7)) {
+			let pos = Pos::from_index(i as usize);
+			let from = pos + delta;
+			for promotion in promotions {
+				moves.push(Move::with_promotion(piece, from, pos, promotion));
+			}
+		}
 	}
 
 	#[inline]
@@ -243,7 +537,7 @@ impl Board {
 	}
 
 	#[inline]
-	fn king_moves(&self, king: u64, player: u64) -> u64 {
+	pub(super) fn king_moves(&self, king: u64, player: u64) -> u64 {
 		self.king_vector(king) & !player
 	}
 
@@ -254,7 +548,7 @@ impl Board {
 
 	// NOTE: includes self.
 	#[inline]
-	fn king_vector(&self, king: u64) -> u64 {
+	pub(super) fn king_vector(&self, king: u64) -> u64 {
 		let mut acc = king;
 		acc |= sh_n(acc);
 		acc |= sh_s(acc);
@@ -274,12 +568,12 @@ impl Board {
 	}
 
 	#[inline]
-	fn knight_moves(&self, knights: u64, player: u64) -> u64 {
+	pub(super) fn knight_moves(&self, knights: u64, player: u64) -> u64 {
 		self.knight_vector(knights) & !player
 	}
 
 	#[inline]
-	fn knight_vector(&self, knights: u64) -> u64 {
+	pub(super) fn knight_vector(&self, knights: u64) -> u64 {
 		let e = sh_e(knights);
 		let w = sh_w(knights);
 		let ee = sh_e(e);
@@ -307,8 +601,15 @@ impl Board {
 		self.bisshop_vector(bisshops) & !player
 	}
 
+	/// Squares attacked by bisshops on `bits`, using magic bitboards.
 	#[inline]
 	pub fn bisshop_vector(&self, bits: u64) -> u64 {
+		let occupied = !self.empty();
+		iter_bitfield(bits).fold(0, |acc, i| acc | bisshop_attacks(i, occupied))
+	}
+
+	/// Reference implementation of `bisshop_vector`, by sliding in each direction.
+	pub fn bisshop_slide_vector(&self, bits: u64) -> u64 {
 		self.slide(bits, sh_ne) | self.slide(bits, sh_se) | self.slide(bits, sh_sw) | self.slide(bits, sh_nw)
 	}
 
@@ -328,12 +629,19 @@ impl Board {
 	}
 
 	#[inline]
-	fn rook_moves(&self, rooks: u64, player: u64) -> u64 {
+	pub(super) fn rook_moves(&self, rooks: u64, player: u64) -> u64 {
 		self.rook_vector(rooks) & !player
 	}
 
+	/// Squares attacked by rooks on `bits`, using magic bitboards.
 	#[inline]
 	pub fn rook_vector(&self, bits: u64) -> u64 {
+		let occupied = !self.empty();
+		iter_bitfield(bits).fold(0, |acc, i| acc | rook_attacks(i, occupied))
+	}
+
+	/// Reference implementation of `rook_vector`, by sliding in each direction.
+	pub fn rook_slide_vector(&self, bits: u64) -> u64 {
 		self.slide(bits, sh_n) | self.slide(bits, sh_e) | self.slide(bits, sh_s) | self.slide(bits, sh_w)
 	}
 
@@ -407,33 +715,50 @@ impl Board {
 		sh_sw(self.bits(BPawn)) & self.white()
 	}
 
+	// En-passant captures.
+	// Like all other moves, these may still leave the own king in check,
+	// including the special case where removing both pawns from a row exposes the king to a rook or queen.
+	// `is_check` on the resulting board detects this.
+
 	#[inline]
-	fn w_pawn_reach(&self) -> u64 {
-		let pawns = self.bits(WPawn);
-		self.w_pawn_push() | sh_ne(pawns) | sh_nw(pawns)
+	pub fn w_pawn_en_passant_ne(&self) -> u64 {
+		sh_ne(self.bits(WPawn)) & self.en_passant & ROW5
 	}
 
 	#[inline]
-	fn b_pawn_reach(&self) -> u64 {
-		let pawns = self.bits(BPawn);
-		self.b_pawn_push() | sh_se(pawns) | sh_sw(pawns)
+	pub fn w_pawn_en_passant_nw(&self) -> u64 {
+		sh_nw(self.bits(WPawn)) & self.en_passant & ROW5
 	}
 
 	#[inline]
-	pub fn slide<F: Fn(u64) -> u64>(&self, bits: u64, sh: F) -> u64 {
-		let empty = self.empty();
+	pub fn b_pawn_en_passant_se(&self) -> u64 {
+		sh_se(self.bits(BPawn)) & self.en_passant & ROW2
+	}
 
-		let mut cursor = sh(bits);
-		let mut acc = cursor;
+	#[inline]
+	pub fn b_pawn_en_passant_sw(&self) -> u64 {
+		sh_sw(self.bits(BPawn)) & self.en_passant & ROW2
+	}
 
-		// TODO: check if unrolled, unroll manually if needed.
-		for _ in 0..6 {
-			cursor &= empty;
-			cursor = sh(cursor);
-			acc |= cursor;
-		}
+	/// Squares attacked by white pawns, occupied or not.
+	/// (Pushes do not attack.)
+	#[inline]
+	fn w_pawn_cover(&self) -> u64 {
+		let pawns = self.bits(WPawn);
+		sh_ne(pawns) | sh_nw(pawns)
+	}
 
-		acc
+	/// Squares attacked by black pawns, occupied or not.
+	#[inline]
+	fn b_pawn_cover(&self) -> u64 {
+		let pawns = self.bits(BPawn);
+		sh_se(pawns) | sh_sw(pawns)
+	}
+
+	/// Slide `bits` in direction `sh`, up to and including the first occupied square.
+	#[inline]
+	pub fn slide<F: Fn(u64) -> u64>(&self, bits: u64, sh: F) -> u64 {
+		slide_through(bits, self.empty(), sh)
 	}
 
 	/// All white pieces.
@@ -470,7 +795,7 @@ impl Board {
 	/// Iterate over non-empty positions
 	#[inline]
 	pub fn iter(bits: u64) -> impl Iterator<Item = Pos> {
-		(0..64).filter(move |i| bits & (1 << i) != 0).map(|i| Pos::from_index(i))
+		(0..64).filter(move |i| bits & (1 << i) != 0).map(Pos::from_index)
 	}
 
 	pub fn is_check(&self, player: Color) -> bool {
@@ -485,14 +810,19 @@ impl Board {
 // ___________________________________________________________ bit fiddling
 
 const ROW0: u64 = 0b_11111111;
-const ROW1: u64 = ROW0 << (1 * 8);
+const ROW1: u64 = ROW0 << 8;
+const ROW2: u64 = ROW0 << (2 * 8);
+const ROW3: u64 = ROW0 << (3 * 8);
+const ROW4: u64 = ROW0 << (4 * 8);
+const ROW5: u64 = ROW0 << (5 * 8);
 const ROW6: u64 = ROW0 << (6 * 8);
+const ROW7: u64 = ROW0 << (7 * 8);
 const COL0: u64 = 0x_01_01_01_01_01_01_01_01;
 const COL7: u64 = COL0 << 7;
 
 /// Shift one row north.
 #[inline]
-const fn sh_n(set: u64) -> u64 {
+pub const fn sh_n(set: u64) -> u64 {
 	set << 8
 }
 
@@ -538,6 +868,38 @@ pub const fn sh_nw(set: u64) -> u64 {
 	(set & !COL0) << 7
 }
 
+/// All squares north of `bits`, and `bits` itself.
+#[inline]
+pub const fn fill_n(mut bits: u64) -> u64 {
+	bits |= bits << 8;
+	bits |= bits << 16;
+	bits | bits << 32
+}
+
+/// All squares south of `bits`, and `bits` itself.
+#[inline]
+pub const fn fill_s(mut bits: u64) -> u64 {
+	bits |= bits >> 8;
+	bits |= bits >> 16;
+	bits | bits >> 32
+}
+
+/// Slide `bits` in direction `sh` through `empty` squares, up to and including the first non-empty square.
+/// (Reference implementation for sliding pieces, see `rook_attacks`, `bisshop_attacks`.)
+#[inline]
+pub fn slide_through<F: Fn(u64) -> u64>(bits: u64, empty: u64, sh: F) -> u64 {
+	let mut cursor = sh(bits);
+	let mut acc = cursor;
+
+	for _ in 0..6 {
+		cursor &= empty;
+		cursor = sh(cursor);
+		acc |= cursor;
+	}
+
+	acc
+}
+
 #[inline]
 pub fn bit_at(set: u64, pos: Pos) -> bool {
 	let mask = 1 << pos.index();
@@ -566,11 +928,29 @@ impl Iterator for BitfieldIter {
 
 // ___________________________________________________________ trait implementations
 
+/// Hashes the Zobrist key.
+impl std::hash::Hash for Board {
+	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+		state.write_u64(self.zobrist)
+	}
+}
+
+impl Default for Board {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Parse either FEN or the 8-line grid notation accepted by `parse_board`.
 impl FromStr for Board {
 	type Err = anyhow::Error;
 
 	fn from_str(s: &str) -> Result<Self> {
-		parse_board(s)
+		if s.contains('/') {
+			Board::from_fen(s.trim())
+		} else {
+			parse_board(s)
+		}
 	}
 }
 
@@ -613,12 +993,7 @@ impl fmt::Debug for Board {
 /// intended to determine the winner of a game,
 /// not to be used in a value computation.
 pub fn is_mate(board: &Board, player: Color) -> bool {
-	for mv in board.iter_moves(player) {
-		if !board.with_move(mv).is_check(player) {
-			return false;
-		}
-	}
-	true
+	board.legal_moves(player).is_empty()
 }
 
 // impl Board for BitBoard {
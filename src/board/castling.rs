@@ -0,0 +1,149 @@
+use super::internal::*;
+use std::fmt::Write;
+use Color::*;
+
+/// Castling rights, one bit per king and side.
+/// Rights only record that king and rook have not moved (yet),
+/// whether castling is actually possible depends on the rest of the board.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Castling(u8);
+
+impl Castling {
+	pub const NONE: Self = Self(0);
+	pub const W_KINGSIDE: Self = Self(1);
+	pub const W_QUEENSIDE: Self = Self(2);
+	pub const B_KINGSIDE: Self = Self(4);
+	pub const B_QUEENSIDE: Self = Self(8);
+	pub const ALL: Self = Self(15);
+
+	/// Rights in the order used by FEN: `KQkq`.
+	const FLAGS: [(Self, char); 4] = [
+		(Self::W_KINGSIDE, 'K'), //
+		(Self::W_QUEENSIDE, 'Q'),
+		(Self::B_KINGSIDE, 'k'),
+		(Self::B_QUEENSIDE, 'q'),
+	];
+
+	#[inline]
+	pub const fn bits(self) -> u8 {
+		self.0
+	}
+
+	#[inline]
+	pub fn has(self, rights: Self) -> bool {
+		self.0 & rights.0 == rights.0
+	}
+
+	#[inline]
+	pub fn with(self, rights: Self) -> Self {
+		Self(self.0 | rights.0)
+	}
+
+	#[inline]
+	pub fn without(self, rights: Self) -> Self {
+		Self(self.0 & !rights.0)
+	}
+
+	pub fn kingside(player: Color) -> Self {
+		match player {
+			White => Self::W_KINGSIDE,
+			Black => Self::B_KINGSIDE,
+		}
+	}
+
+	pub fn queenside(player: Color) -> Self {
+		match player {
+			White => Self::W_QUEENSIDE,
+			Black => Self::B_QUEENSIDE,
+		}
+	}
+
+	/// Rights that remain after a piece moves from, or to, the square with index `i`.
+	/// Moving the king or a rook, or capturing a rook on its original square, loses the corresponding rights.
+	#[inline]
+	pub(super) fn after_touching(self, i: usize) -> Self {
+		Self(self.0 & KEEP_RIGHTS[i])
+	}
+}
+
+/// Indexed by Pos::index(): rights kept after a piece leaves or lands on that square.
+const KEEP_RIGHTS: [u8; 64] = {
+	let mut keep = [Castling::ALL.0; 64];
+	keep[0] = !Castling::W_QUEENSIDE.0; // a1
+	keep[4] = !(Castling::W_KINGSIDE.0 | Castling::W_QUEENSIDE.0); // e1
+	keep[7] = !Castling::W_KINGSIDE.0; // h1
+	keep[56] = !Castling::B_QUEENSIDE.0; // a8
+	keep[60] = !(Castling::B_KINGSIDE.0 | Castling::B_QUEENSIDE.0); // e8
+	keep[63] = !Castling::B_KINGSIDE.0; // h8
+	keep
+};
+
+/// FEN notation, e.g. `KQkq`, `Kq` or `-`.
+impl fmt::Display for Castling {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if *self == Self::NONE {
+			return f.write_char('-');
+		}
+		for (rights, chr) in Self::FLAGS {
+			if self.has(rights) {
+				f.write_char(chr)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl fmt::Debug for Castling {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self)
+	}
+}
+
+impl FromStr for Castling {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		if s == "-" {
+			return Ok(Self::NONE);
+		}
+		let mut castling = Self::NONE;
+		for chr in s.chars() {
+			let rights = match Self::FLAGS.iter().find(|(_, c)| *c == chr) {
+				Some((rights, _)) => *rights,
+				None => return Err(format_err!("castling '{}': invalid character '{}', want one of 'KQkq' or '-'", s, chr)),
+			};
+			if castling.has(rights) {
+				return Err(format_err!("castling '{}': duplicate character '{}'", s, chr));
+			}
+			castling = castling.with(rights);
+		}
+		if castling == Self::NONE {
+			return Err(format_err!("castling: empty, want one of 'KQkq' or '-'"));
+		}
+		Ok(castling)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn display_from_str() {
+		for s in ["-", "K", "Qk", "KQkq", "kq"] {
+			assert_eq!(Castling::from_str(s).unwrap().to_string(), s);
+		}
+		assert!(Castling::from_str("").is_err());
+		assert!(Castling::from_str("KK").is_err());
+		assert!(Castling::from_str("KX").is_err());
+	}
+
+	#[test]
+	fn after_touching() {
+		let all = Castling::ALL;
+		assert_eq!(all.after_touching(pos(0, 0).index()), all.without(Castling::W_QUEENSIDE));
+		assert_eq!(all.after_touching(pos(0, 4).index()), Castling::B_KINGSIDE.with(Castling::B_QUEENSIDE));
+		assert_eq!(all.after_touching(pos(7, 7).index()), all.without(Castling::B_KINGSIDE));
+		assert_eq!(all.after_touching(pos(3, 3).index()), all);
+	}
+}
@@ -33,6 +33,13 @@ impl Color {
 			Black => Square::BKing,
 		}
 	}
+
+	pub fn pawn(self) -> Square {
+		match self {
+			White => Square::WPawn,
+			Black => Square::BPawn,
+		}
+	}
 }
 
 impl fmt::Display for Color {
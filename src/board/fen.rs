@@ -0,0 +1,293 @@
+use super::internal::*;
+use Color::*;
+use Square::*;
+
+/// FEN of the standard starting position.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Parse Forsyth-Edwards Notation (https://en.wikipedia.org/wiki/Forsyth%E2%80%93Edwards_Notation). E.g.:
+///
+///   rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1
+///
+/// The half-move clock and full-move number may be omitted, they default to `0 1`.
+pub fn parse_fen(fen: &str) -> Result<Position> {
+	parse_fen_fields(fen).map_err(|e| format_err!("FEN '{}': {}", fen, e))
+}
+
+fn parse_fen_fields(fen: &str) -> Result<Position> {
+	let fields = fen.split_whitespace().collect::<Vec<_>>();
+	if fields.len() < 4 {
+		return Err(format_err!(
+			"need at least 4 fields (placement, side to move, castling, en passant), got {}",
+			fields.len()
+		));
+	}
+	if fields.len() > 6 {
+		return Err(format_err!("need at most 6 fields, got {}", fields.len()));
+	}
+
+	let mut board = parse_placement(fields[0])?;
+	for (king, color) in [(WKing, "white"), (BKing, "black")] {
+		let kings = board.bits(king).count_ones();
+		if kings != 1 {
+			return Err(format_err!("piece placement: need exactly one {} king, got {}", color, kings));
+		}
+	}
+
+	let player = match fields[1] {
+		"w" => White,
+		"b" => Black,
+		other => return Err(format_err!("side to move: want 'w' or 'b', got '{}'", other)),
+	};
+
+	let castling = Castling::from_str(fields[2]).map_err(|e| format_err!("castling: {}", e))?;
+	board.set_castling(castling);
+	if board.castling() != castling {
+		let missing = castling.without(board.castling());
+		return Err(format_err!("castling: '{}' requires king and rook on their original squares", missing));
+	}
+
+	let en_passant = match fields[3] {
+		"-" => None,
+		square => Some(Pos::try_from(square.as_bytes()).map_err(|e| format_err!("en passant: {}", e))?),
+	};
+	if let Some(target) = en_passant {
+		let want_row = if player == White { 5 } else { 2 };
+		if target.row() != want_row {
+			return Err(format_err!("en passant: {} cannot be captured by {}", target, player));
+		}
+		if !board.is_double_push_target(target) {
+			return Err(format_err!("en passant: no pawn can just have passed {}", target));
+		}
+		// dropped if no pawn can capture.
+		board.set_en_passant(en_passant);
+	}
+
+	let halfmove_clock = match fields.get(4) {
+		None => 0,
+		Some(s) => s.parse().map_err(|_| format_err!("half-move clock: want a number, got '{}'", s))?,
+	};
+	let fullmove_number = match fields.get(5) {
+		None => 1,
+		Some(s) => match s.parse() {
+			Ok(n) if n > 0 => n,
+			_ => return Err(format_err!("full-move number: want a positive number, got '{}'", s)),
+		},
+	};
+
+	Ok(Position::with_clocks(board, player, halfmove_clock, fullmove_number))
+}
+
+/// Parse the piece placement field, e.g. `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR`.
+fn parse_placement(placement: &str) -> Result<Board> {
+	let ranks = placement.split('/').collect::<Vec<_>>();
+	if ranks.len() != 8 {
+		return Err(format_err!("piece placement: need 8 ranks separated by '/', got {}", ranks.len()));
+	}
+
+	let mut board = Board::new();
+	for (i, rank) in ranks.iter().enumerate() {
+		let row = 7 - i as u8;
+		let mut col = 0;
+		for chr in rank.chars() {
+			if col >= 8 {
+				return Err(format_err!("piece placement: rank {}: more than 8 squares", row + 1));
+			}
+			match chr {
+				'1'..='8' => col += chr as u8 - b'0',
+				chr => {
+					let piece = match Square::try_from(chr) {
+						Ok(piece) if !piece.is_empty() => piece,
+						_ => return Err(format_err!("piece placement: rank {}: invalid character '{}'", row + 1, chr)),
+					};
+					board.set(pos(row, col), piece);
+					col += 1;
+				}
+			}
+		}
+		if col != 8 {
+			return Err(format_err!("piece placement: rank {}: need 8 squares, got {}", row + 1, col));
+		}
+	}
+	Ok(board)
+}
+
+/// Format the piece placement field, e.g. `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR`.
+fn format_placement(board: &Board) -> String {
+	let mut str = String::with_capacity(64 + 8);
+	for row in (0..8).rev() {
+		let mut empty = 0;
+		for col in 0..8 {
+			match board.at(pos(row, col)) {
+				Empty => empty += 1,
+				piece => {
+					if empty != 0 {
+						str.push((b'0' + empty) as char);
+						empty = 0;
+					}
+					str.push(piece.to_char());
+				}
+			}
+		}
+		if empty != 0 {
+			str.push((b'0' + empty) as char);
+		}
+		if row != 0 {
+			str.push('/');
+		}
+	}
+	str
+}
+
+impl Position {
+	pub fn from_fen(fen: &str) -> Result<Self> {
+		parse_fen(fen)
+	}
+
+	pub fn to_fen(&self) -> String {
+		let side = match self.player() {
+			White => 'w',
+			Black => 'b',
+		};
+		let en_passant = self.en_passant().map_or("-".to_string(), |p| p.to_string());
+		format!(
+			"{} {} {} {} {} {}",
+			format_placement(self.board()),
+			side,
+			self.castling(),
+			en_passant,
+			self.halfmove_clock(),
+			self.fullmove_number()
+		)
+	}
+}
+
+impl Board {
+	/// Board part of a FEN string (side to move and clocks are checked, but otherwise ignored).
+	pub fn from_fen(fen: &str) -> Result<Self> {
+		Ok(parse_fen(fen)?.board().clone())
+	}
+
+	/// FEN for this board with `player` to move (and clocks `0 1`).
+	pub fn to_fen(&self, player: Color) -> String {
+		Position::new(self.clone(), player).to_fen()
+	}
+}
+
+impl FromStr for Position {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		parse_fen(s.trim())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// FENs with castling rights, en-passant squares, promotions, and odd clocks.
+	const TRICKY_FENS: &[&str] = &[
+		STARTING_FEN,
+		"rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+		"rnbqkbnr/pp1p1ppp/8/2pPp3/8/8/PPP1PPPP/RNBQKBNR w KQkq c6 0 3",
+		"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+		"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+		"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+		"rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+		"r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+		"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+		"4k3/8/8/8/3Pp3/8/8/4K3 b - d3 0 1",
+		"r3k3/8/8/8/8/8/8/4K2R w Kq - 99 150",
+		"7k/P7/8/8/8/8/7p/K7 b - - 12 61",
+	];
+
+	#[test]
+	fn round_trip() {
+		for &fen in TRICKY_FENS {
+			let position = Position::from_fen(fen).unwrap();
+			assert_eq!(position.to_fen(), fen);
+			assert_eq!(Position::from_str(&position.to_fen()).unwrap().to_fen(), fen);
+		}
+	}
+
+	#[test]
+	fn starting_position() {
+		assert_eq!(Position::starting_position().to_fen(), STARTING_FEN);
+		let board = Board::from_fen(STARTING_FEN).unwrap();
+		assert_eq!(board.to_string(), Board::starting_position().to_string());
+		assert_eq!(board.castling(), Castling::ALL);
+		assert_eq!(Board::from_str(STARTING_FEN).unwrap().to_fen(White), STARTING_FEN);
+	}
+
+	#[test]
+	fn after_moves() {
+		let mut position = Position::starting_position();
+		for mv in ["Pe2e4", "pc7c5", "Ng1f3"] {
+			position.play(Move::from_str(mv).unwrap());
+		}
+		assert_eq!(position.to_fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+	}
+
+	#[test]
+	fn en_passant_only_if_capturable() {
+		// no pawn can capture: the target is dropped, so that the position hashes like any other without it.
+		for (fen, want) in [
+			(
+				"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+				"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+			),
+			(
+				"rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+				"rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2",
+			),
+		] {
+			let position = Position::from_fen(fen).unwrap();
+			assert_eq!(position.en_passant(), None);
+			assert_eq!(position.to_fen(), want);
+			assert_eq!(position.zobrist(), Position::from_fen(want).unwrap().zobrist());
+		}
+
+		// the same after the double push.
+		let mut position = Position::starting_position();
+		position.play(Move::from_str("Pe2e4").unwrap());
+		assert_eq!(position.en_passant(), None);
+		for mv in ["pd7d5", "Pe4e5", "pf7f5"] {
+			position.play(Move::from_str(mv).unwrap());
+		}
+		assert_eq!(position.en_passant(), Some(pos(5, 5)));
+	}
+
+	#[test]
+	fn optional_clocks() {
+		let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+		assert_eq!(position.player(), Black);
+		assert_eq!((position.halfmove_clock(), position.fullmove_number()), (0, 1));
+	}
+
+	#[test]
+	fn errors() {
+		let err = |fen: &str| Position::from_fen(fen).unwrap_err().to_string();
+		let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+
+		assert!(err(fen).contains("need at least 4 fields"));
+		assert!(err(&format!("{} w KQkq - 0 1 x", fen)).contains("need at most 6 fields"));
+		assert!(err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1").contains("need 8 ranks"));
+		assert!(err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1").contains("rank 1: more than 8 squares"));
+		assert!(err("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").contains("rank 7: need 8 squares, got 7"));
+		assert!(err("rnbqkbnr/pppppppp/8/8/3X4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").contains("rank 4: invalid character 'X'"));
+		assert!(err("rnbqkbnr/pppppppp/8/8/3.4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").contains("rank 4: invalid character '.'"));
+		assert!(err(&format!("{} x KQkq - 0 1", fen)).contains("side to move: want 'w' or 'b', got 'x'"));
+		assert!(err(&format!("{} w KQxq - 0 1", fen)).contains("castling"));
+		assert!(err("4k3/8/8/8/8/8/8/4K3 w K - 0 1").contains("castling: 'K' requires king and rook"));
+		assert!(err(&format!("{} w KQkq e9 0 1", fen)).contains("en passant: invalid pos"));
+		assert!(err(&format!("{} w KQkq e3 0 1", fen)).contains("en passant: e3 cannot be captured by White"));
+		assert!(err(&format!("{} w KQkq e6 0 1", fen)).contains("en passant: no pawn can just have passed e6"));
+		assert!(err("8/8/8/8/8/8/8/8 w - - 0 1").contains("piece placement: need exactly one white king, got 0"));
+		assert!(err("4k3/8/8/8/8/8/8/3KK3 w - - 0 1").contains("piece placement: need exactly one white king, got 2"));
+		assert!(err("8/8/8/8/8/8/8/4K3 w - - 0 1").contains("piece placement: need exactly one black king, got 0"));
+		assert!(err(&format!("{} w KQkq - x 1", fen)).contains("half-move clock"));
+		assert!(err(&format!("{} w KQkq - 0 0", fen)).contains("full-move number"));
+		assert!(err(fen).starts_with(&format!("FEN '{}': ", fen)));
+	}
+}
@@ -20,7 +20,7 @@ where
 }
 
 pub fn print_ansi(board: &Board, mark: &Set<Pos>) {
-	let is_light = |p: Pos| (p.row() + p.col()) % 2 == 0;
+	let is_light = |p: Pos| (p.row() + p.col()).is_multiple_of(2);
 	let color_of = |p: Pos| match (is_light(p), mark.contains(&p)) {
 		(false, false) => DARK,
 		(false, true) => MARK_DARK,
@@ -28,7 +28,7 @@ pub fn print_ansi(board: &Board, mark: &Set<Pos>) {
 		(true, true) => MARK_LIGHT,
 	};
 
-	println!("{}{}{}{}", RESET, FG_DARK, "  a b c d e f g h", RESET);
+	println!("{}{}  a b c d e f g h{}", RESET, FG_DARK, RESET);
 	for r in (0..8).rev() {
 		// row number
 		print!("{}{}{}{}", RESET, FG_DARK, r + 1, RESET);
@@ -63,7 +63,7 @@ pub fn print_ansi(board: &Board, mark: &Set<Pos>) {
 		println!("{}{}{}{}", RESET, FG_DARK, r + 1, RESET);
 	}
 	// print column numbers
-	println!("{}{}{}{}", RESET, FG_DARK, "  a b c d e f g h", RESET);
+	println!("{}{}  a b c d e f g h{}", RESET, FG_DARK, RESET);
 }
 
 const RESET: &str = "\x1b[39;49m";
@@ -2,9 +2,14 @@ pub use super::super::types::*;
 
 pub use super::attack_vector::*;
 pub use super::board::*;
+pub use super::castling::*;
 pub use super::color::*;
 pub use super::formatter::*;
+pub use super::magic::*;
 pub use super::moves::*;
 pub use super::parser::*;
 pub use super::pos::*;
+pub use super::position::*;
+pub use super::pst::*;
 pub use super::square::*;
+pub use super::zobrist::*;
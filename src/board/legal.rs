@@ -0,0 +1,158 @@
+use super::internal::*;
+use smallvec::SmallVec;
+use Color::*;
+use Square::*;
+
+impl Board {
+	/// All legal moves for `player`: moves that do not leave the own king in check.
+	///
+	/// Equivalent to filtering `collect_moves` with `!with_move(mv).is_check(player)`,
+	/// but moves are checked against pins and check evasion masks instead of being played.
+	/// (If `player` has no king, all moves are legal.)
+	pub fn legal_moves(&self, player: Color) -> SmVec<Move> {
+		let mut moves = self.collect_moves(player);
+		if !self.has_king(player) {
+			return moves;
+		}
+		let legality = Legality::new(self, player);
+		moves.retain(|mv| legality.allows(self, *mv));
+		moves
+	}
+
+	/// Is `mv` one of `legal_moves(player)`? Without generating all moves first, except for pawns and castling.
+	/// Meant for moves from elsewhere, like the transposition table, that may not fit the board.
+	pub fn is_legal(&self, player: Color, mv: Move) -> bool {
+		let from = 1 << mv.from.index();
+		let to = 1 << mv.to.index();
+		if !mv.piece.is_color(player) || self.bits(mv.piece) & from == 0 {
+			return false;
+		}
+		let own = self.all_pieces(player);
+		let targets = match mv.piece {
+			WKnight | BKnight => self.knight_moves(from, own),
+			WRook | BRook => self.rook_moves(from, own),
+			WBisshop | BBisshop => self.bisshop_moves(from, own),
+			WQueen | BQueen => self.queen_moves(from, own),
+			WKing | BKing if (mv.from.col() as i8 - mv.to.col() as i8).abs() != 2 => self.king_moves(from, own),
+			_ => return self.legal_moves(player).contains(&mv),
+		};
+		if mv.promotion.is_some() || targets & to == 0 {
+			return false;
+		}
+		!self.has_king(player) || Legality::new(self, player).allows(self, mv)
+	}
+
+	/// Iterate over all legal moves for `player`.
+	pub fn iter_legal_moves(&self, player: Color) -> impl Iterator<Item = Move> {
+		self.legal_moves(player).into_iter()
+	}
+}
+
+/// What `player` may do without leaving their king in check.
+struct Legality {
+	player: Color,
+	/// Squares attacked by the opponent, seen through our own king
+	/// (so that the king cannot step back along the line of a checking slider).
+	attacked: u64,
+	/// Opponent pieces giving check.
+	checkers: u64,
+	/// When in single check: squares that capture the checker or block its line.
+	/// Not in check: all squares.
+	evasion: u64,
+	/// Our pieces pinned to our king.
+	pinned: u64,
+	/// Each pinned piece with the line it may still move along (including the capture of the pinner).
+	pins: SmallVec<[(u64, u64); 8]>,
+}
+
+impl Legality {
+	fn new(board: &Board, player: Color) -> Self {
+		let opponent = player.opposite();
+		let king = board.bits(player.king());
+		let own = board.all_pieces(player);
+		let occupied = !board.empty();
+
+		let (pawn, knight, rook, bisshop, queen) = match opponent {
+			White => (WPawn, WKnight, WRook, WBisshop, WQueen),
+			Black => (BPawn, BKnight, BRook, BBisshop, BQueen),
+		};
+		let pawn_checkers = match player {
+			White => sh_ne(king) | sh_nw(king),
+			Black => sh_se(king) | sh_sw(king),
+		};
+
+		let mut checkers = (board.knight_vector(king) & board.bits(knight))
+			| (pawn_checkers & board.bits(pawn))
+			| (board.king_vector(king) & board.bits(opponent.king())); // only in positions that cannot arise in a game
+		let mut evasion = checkers;
+		let mut pinned = 0;
+		let mut pins = SmallVec::new();
+
+		let k = king.trailing_zeros() as u8;
+		let rooks = board.bits(rook) | board.bits(queen);
+		let bisshops = board.bits(bisshop) | board.bits(queen);
+		for (attacks, sliders) in [(rook_attacks as fn(u8, u64) -> u64, rooks), (bisshop_attacks, bisshops)] {
+			// sliders that see the king give check.
+			let seen = attacks(k, occupied);
+			for i in iter_bitfield(seen & sliders) {
+				checkers |= 1 << i;
+				evasion |= (1 << i) | squares_between(k, i);
+			}
+			// sliders that see the king through one of our pieces pin it.
+			let xray = attacks(k, occupied & !(seen & own));
+			for i in iter_bitfield(xray & !seen & sliders) {
+				let line = (1 << i) | squares_between(k, i);
+				let piece = line & own;
+				pinned |= piece;
+				pins.push((piece, line));
+			}
+		}
+
+		Self {
+			player,
+			attacked: board.attack_vector_through(opponent, king),
+			checkers,
+			evasion: if checkers == 0 { !0 } else { evasion },
+			pinned,
+			pins,
+		}
+	}
+
+	fn allows(&self, board: &Board, mv: Move) -> bool {
+		let from = 1 << mv.from.index();
+		let to = 1 << mv.to.index();
+
+		// capturing the king, only in positions that cannot arise in a game.
+		if to & board.bits(self.player.opposite().king()) != 0 {
+			return !board.with_move(mv).is_check(self.player);
+		}
+
+		if mv.piece.is_king() {
+			// castling moves have already been checked for attacks on the king's path.
+			let is_castling = (mv.from.col() as i8 - mv.to.col() as i8).abs() == 2;
+			return is_castling || to & self.attacked == 0;
+		}
+
+		// double check: only the king can move.
+		if self.checkers.count_ones() > 1 {
+			return false;
+		}
+
+		// en passant removes two pawns from a row, which may expose the king sideways,
+		// and may capture a checking pawn that is not on the target square.
+		// Rare enough to simply play the move.
+		if mv.piece.is_pawn() && Some(mv.to) == board.en_passant() && mv.from.col() != mv.to.col() {
+			return !board.with_move(mv).is_check(self.player);
+		}
+
+		if to & self.evasion == 0 {
+			return false;
+		}
+
+		if from & self.pinned != 0 {
+			return self.pins.iter().any(|&(piece, line)| piece == from && to & line != 0);
+		}
+
+		true
+	}
+}
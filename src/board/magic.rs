@@ -0,0 +1,380 @@
+use super::internal::*;
+use std::sync::LazyLock;
+
+/// Sliding piece attacks by table lookup: magic bitboards (https://www.chessprogramming.org/Magic_Bitboards).
+///
+/// For each square, the occupancy that matters to a rook or bisshop there
+/// (the squares its rays pass over, not counting the board edge)
+/// is hashed into an index into a table of precomputed attacks: `(occupied & mask) * magic >> shift`.
+/// The magic numbers below are chosen so that different attacks never share an index.
+///
+/// On CPUs with BMI2, PEXT (parallel bit extract) is used instead,
+/// which turns the occupancy into an index directly.
+/// It is selected at run time.
+static SLIDERS: LazyLock<Sliders> = LazyLock::new(|| Sliders::new(has_pext()));
+
+/// Squares attacked by a rook on square index `i`, stopping at (and including) the first occupied square in each direction.
+#[inline]
+pub fn rook_attacks(i: u8, occupied: u64) -> u64 {
+	let sliders = &*SLIDERS;
+	sliders.lookup(&sliders.rook[i as usize], occupied)
+}
+
+/// Squares attacked by a bisshop on square index `i`, stopping at (and including) the first occupied square in each direction.
+#[inline]
+pub fn bisshop_attacks(i: u8, occupied: u64) -> u64 {
+	let sliders = &*SLIDERS;
+	sliders.lookup(&sliders.bisshop[i as usize], occupied)
+}
+
+/// Squares strictly between square indices `a` and `b`
+/// if they share a row, column or diagonal, otherwise none.
+#[inline]
+pub fn squares_between(a: u8, b: u8) -> u64 {
+	let (bit_a, bit_b) = (1 << a, 1 << b);
+	if rook_attacks(a, 0) & bit_b != 0 {
+		rook_attacks(a, bit_b) & rook_attacks(b, bit_a)
+	} else if bisshop_attacks(a, 0) & bit_b != 0 {
+		bisshop_attacks(a, bit_b) & bisshop_attacks(b, bit_a)
+	} else {
+		0
+	}
+}
+
+/// Are sliding attacks computed with PEXT (rather than magic multiplication)?
+pub fn uses_pext() -> bool {
+	SLIDERS.pext
+}
+
+const ROOK_DIRECTIONS: [fn(u64) -> u64; 4] = [sh_n, sh_e, sh_s, sh_w];
+const BISSHOP_DIRECTIONS: [fn(u64) -> u64; 4] = [sh_ne, sh_se, sh_sw, sh_nw];
+
+struct Sliders {
+	pext: bool,
+	rook: [Entry; 64],
+	bisshop: [Entry; 64],
+	/// Attacks for all squares and occupancies, rooks and bisshops.
+	attacks: Vec<u64>,
+}
+
+/// Where to find the attacks for one piece type on one square.
+#[derive(Clone, Copy, Default)]
+struct Entry {
+	mask: u64,
+	magic: u64,
+	shift: u32,
+	offset: usize,
+}
+
+impl Sliders {
+	/// With `pext` only if the CPU has it (see `has_pext`).
+	fn new(pext: bool) -> Self {
+		assert!(!pext || has_pext(), "PEXT needs BMI2, which this CPU does not have");
+		let mut sliders = Self {
+			pext,
+			rook: [Entry::default(); 64],
+			bisshop: [Entry::default(); 64],
+			attacks: Vec::new(),
+		};
+		for i in 0..64 {
+			sliders.rook[i] = sliders.add_square(i, &ROOK_DIRECTIONS, ROOK_MAGICS[i]);
+			sliders.bisshop[i] = sliders.add_square(i, &BISSHOP_DIRECTIONS, BISSHOP_MAGICS[i]);
+		}
+		sliders
+	}
+
+	/// Fill in the attacks for all occupancies around square `i`,
+	/// computed by the reference implementation `slide_through`.
+	fn add_square(&mut self, i: usize, directions: &[fn(u64) -> u64], magic: u64) -> Entry {
+		let bit = 1 << i;
+		let attacks = |occupied: u64| directions.iter().fold(0, |acc, &sh| acc | slide_through(bit, !occupied, sh));
+
+		// Occupancy of the last square in each direction does not matter: it is attacked either way.
+		let mask = directions.iter().fold(0, |acc, &sh| acc | (slide_through(bit, !0, sh) & !Self::edge(sh)));
+		let bits = mask.count_ones();
+		let entry = Entry {
+			mask,
+			magic,
+			shift: 64 - bits,
+			offset: self.attacks.len(),
+		};
+		self.attacks.resize(self.attacks.len() + (1 << bits), 0);
+
+		// enumerate all subsets of mask (Carry-Rippler).
+		let mut occupied = 0u64;
+		loop {
+			let index = entry.offset + self.index(&entry, occupied);
+			// a magic that maps occupancies with different attacks to the same slot is wrong. Checked once, at startup.
+			assert!(
+				self.attacks[index] == 0 || self.attacks[index] == attacks(occupied),
+				"magic {:#x} for square {} collides",
+				magic,
+				i
+			);
+			self.attacks[index] = attacks(occupied);
+			occupied = occupied.wrapping_sub(mask) & mask;
+			if occupied == 0 {
+				break;
+			}
+		}
+		entry
+	}
+
+	/// Squares from which shifting by `sh` falls off the board.
+	fn edge(sh: fn(u64) -> u64) -> u64 {
+		(0..64).map(|i| 1u64 << i).filter(|&bit| sh(bit) == 0).fold(0, |acc, bit| acc | bit)
+	}
+
+	#[inline]
+	fn index(&self, entry: &Entry, occupied: u64) -> usize {
+		if self.pext {
+			// SAFETY: `self.pext` is only set if the CPU has BMI2 (see `Sliders::new`).
+			unsafe { pext(occupied, entry.mask) as usize }
+		} else {
+			((occupied & entry.mask).wrapping_mul(entry.magic) >> entry.shift) as usize
+		}
+	}
+
+	#[inline]
+	fn lookup(&self, entry: &Entry, occupied: u64) -> u64 {
+		self.attacks[entry.offset + self.index(entry, occupied)]
+	}
+}
+
+#[cfg(target_arch = "x86_64")]
+fn has_pext() -> bool {
+	is_x86_feature_detected!("bmi2")
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn has_pext() -> bool {
+	false
+}
+
+/// # Safety
+/// Only if `has_pext`.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+#[inline]
+unsafe fn pext(bits: u64, mask: u64) -> u64 {
+	std::arch::x86_64::_pext_u64(bits, mask)
+}
+
+/// # Safety
+/// Never: there is no PEXT.
+#[cfg(not(target_arch = "x86_64"))]
+unsafe fn pext(_bits: u64, _mask: u64) -> u64 {
+	unreachable!()
+}
+
+/// Magic numbers, by square index.
+/// Found by trying sparse random numbers (`a & b & c`) until one maps all occupancies to distinct (or equal-attack) indices.
+const ROOK_MAGICS: [u64; 64] = [
+	0x_2080_0020_8040_0010,
+	0x_00C0_0020_0140_1000,
+	0x_2100_1100_0840_2002,
+	0x_0880_0800_8104_1000,
+	0x_0200_0200_2004_1008,
+	0x_2300_0400_0801_0012,
+	0x_0C00_2830_0400_8201,
+	0x_0180_0100_0040_7A80,
+	0x_0168_8000_8040_0020,
+	0x_0010_4000_4020_1000,
+	0x_1001_0020_0100_1048,
+	0x_1001_0024_0810_0100,
+	0x_0801_0004_0801_0012,
+	0x_4001_0002_0900_0400,
+	0x_08A2_0004_C802_0001,
+	0x_2002_8011_4500_2280,
+	0x_0080_8600_2100_4200,
+	0x_0010_00C0_0940_2002,
+	0x_00B0_0020_0400_2800,
+	0x_100A_8080_1002_0800,
+	0x_8101_0100_0800_0410,
+	0x_0244_0080_0200_0480,
+	0x_0000_0400_1081_0208,
+	0x_2000_0200_0044_8534,
+	0x_4104_4004_8000_8033,
+	0x_0000_8101_0020_4000,
+	0x_0440_4309_0020_0010,
+	0x_4600_2409_0010_0100,
+	0x_0060_0800_8004_0080,
+	0x_0001_0003_0008_0400,
+	0x_0004_0844_0001_1002,
+	0x_0023_0402_0000_8041,
+	0x_0580_0500_4300_2080,
+	0x_0400_8040_0280_2008,
+	0x_0001_0020_0100_4010,
+	0x_1000_2009_0100_1000,
+	0x_4410_8008_0180_0C00,
+	0x_A012_0038_0600_1004,
+	0x_0020_1001_0400_8802,
+	0x_0004_8084_0200_0041,
+	0x_0010_4001_7089_8000,
+	0x_0080_5000_2000_4004,
+	0x_1040_4080_1202_0020,
+	0x_8010_0400_0800_4040,
+	0x_2001_0801_0011_0004,
+	0x_0000_0200_0400_8080,
+	0x_0021_0108_1004_0002,
+	0x_0800_008C_4302_0024,
+	0x_0000_8000_2100_5100,
+	0x_0070_2010_4000_8080,
+	0x_0000_D042_8200_6A00,
+	0x_0010_0144_0008_0240,
+	0x_0001_0801_1005_0100,
+	0x_0012_0008_1024_0600,
+	0x_0402_0008_0104_0200,
+	0x_0281_0010_8A00_4100,
+	0x_0050_8003_0010_2045,
+	0x_8208_2100_4012_0882,
+	0x_8010_6001_0118_3441,
+	0x_020B_0009_1000_6045,
+	0x_0241_0010_0248_0005,
+	0x_0081_0004_0088_0241,
+	0x_0000_0090_0802_4124,
+	0x_0048_1229_8041_0402,
+];
+const BISSHOP_MAGICS: [u64; 64] = [
+	0x_0848_0208_2204_0013,
+	0x_8010_A400_8582_1200,
+	0x_0008_0084_3084_0822,
+	0x_0808_0481_0804_0000,
+	0x_1304_0421_0000_8104,
+	0x_5001_0120_1020_4023,
+	0x_8104_8801_B820_0420,
+	0x_200A_0080_8401_2000,
+	0x_0040_1020_0104_2084,
+	0x_840A_5050_4242_8020,
+	0x_0000_7001_0220_2920,
+	0x_4410_1C0C_1080_0002,
+	0x_0040_0404_2200_0000,
+	0x_0180_0208_0209_0202,
+	0x_4020_0208_1104_1202,
+	0x_0001_0430_8C04_2000,
+	0x_4140_6610_0242_4400,
+	0x_0028_0120_0801_0460,
+	0x_0188_0621_0200_2A00,
+	0x_0014_0048_4010_2008,
+	0x_0105_0002_9040_0002,
+	0x_8001_0222_0041_0400,
+	0x_104A_0419_1801_3446,
+	0x_008A_0000_8200_8238,
+	0x_04A0_0600_0810_0430,
+	0x_0008_2200_0882_0801,
+	0x_2508_0412_0800_5010,
+	0x_4008_0802_0020_2020,
+	0x_2441_0010_1300_4000,
+	0x_0030_0080_6040_7000,
+	0x_4008_1080_0042_0800,
+	0x_0012_0210_5029_0100,
+	0x_0210_0804_8220_0500,
+	0x_CC01_1120_4810_0480,
+	0x_0020_4028_0650_0440,
+	0x_0004_8E00_8058_0080,
+	0x_0040_1020_2002_0080,
+	0x_0028_0104_4008_0807,
+	0x_4601_0411_0800_8800,
+	0x_8040_810E_0410_4200,
+	0x_9012_1011_0400_088A,
+	0x_A003_0802_1208_1050,
+	0x_00C1_0040_4840_1004,
+	0x_9000_00A0_1440_0800,
+	0x_0008_0210_4040_5401,
+	0x_4020_0082_0600_2090,
+	0x_0004_1904_2403_0100,
+	0x_0424_008A_0202_6250,
+	0x_8004_0882_5090_0040,
+	0x_1C00_4300_88A0_4200,
+	0x_0001_0200_9404_0001,
+	0x_8040_2100_2088_0061,
+	0x_2010_0404_5044_2032,
+	0x_0800_8408_5004_4001,
+	0x_0004_0408_0214_0004,
+	0x_0004_080A_0422_2020,
+	0x_8088_8021_1002_2000,
+	0x_1081_A104_1611_4400,
+	0x_0205_010A_2406_0820,
+	0x_0000_0007_2041_1080,
+	0x_1008_0002_0843_0400,
+	0x_580C_0260_2881_0840,
+	0x_8020_2044_1020_A110,
+	0x_12C0_0224_0102_0018,
+];
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// Compare table lookups to the reference implementation for random occupancies.
+	#[test]
+	fn random_occupancies() {
+		let mut rng = rand::rngs::StdRng::seed_from_u64(12345);
+		let mut implementations = vec![Sliders::new(false)];
+		if has_pext() {
+			implementations.push(Sliders::new(true));
+		}
+
+		for sliders in &implementations {
+			for i in 0..64 {
+				for _ in 0..200 {
+					// sparse and dense boards
+					let occupied = match rng.gen_bool(0.5) {
+						true => rng.gen::<u64>() & rng.gen::<u64>(),
+						false => rng.gen::<u64>() | rng.gen::<u64>(),
+					};
+					let bit = 1 << i;
+					let rook = ROOK_DIRECTIONS.iter().fold(0, |acc, &sh| acc | slide_through(bit, !occupied, sh));
+					let bisshop = BISSHOP_DIRECTIONS.iter().fold(0, |acc, &sh| acc | slide_through(bit, !occupied, sh));
+					assert_eq!(sliders.lookup(&sliders.rook[i], occupied), rook, "rook on {}, pext: {}", i, sliders.pext);
+					assert_eq!(
+						sliders.lookup(&sliders.bisshop[i], occupied),
+						bisshop,
+						"bisshop on {}, pext: {}",
+						i,
+						sliders.pext
+					);
+				}
+			}
+		}
+	}
+
+	// Every occupancy of every square, not just random ones.
+	#[test]
+	fn all_occupancies() {
+		let mut implementations = vec![Sliders::new(false)];
+		if has_pext() {
+			implementations.push(Sliders::new(true));
+		}
+
+		for sliders in &implementations {
+			for i in 0..64 {
+				let bit = 1 << i;
+				for (entry, directions) in [(&sliders.rook[i], &ROOK_DIRECTIONS), (&sliders.bisshop[i], &BISSHOP_DIRECTIONS)] {
+					let mut occupied = 0u64;
+					loop {
+						let want = directions.iter().fold(0, |acc, &sh| acc | slide_through(bit, !occupied, sh));
+						assert_eq!(
+							sliders.lookup(entry, occupied),
+							want,
+							"square {}, occupied {:#x}, pext: {}",
+							i,
+							occupied,
+							sliders.pext
+						);
+						occupied = occupied.wrapping_sub(entry.mask) & entry.mask;
+						if occupied == 0 {
+							break;
+						}
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn table_size() {
+		let sliders = Sliders::new(false);
+		assert_eq!(sliders.attacks.len(), 102_400 + 5_248);
+	}
+}
@@ -8,11 +8,17 @@ use Square::*;
 #[derive(Eq, PartialEq, Clone)]
 pub struct Mailbox {
 	board: [Square; 64],
+	castling: Castling,
+	en_passant: Option<Pos>,
 }
 
 impl Mailbox {
 	pub fn new() -> Self {
-		Self { board: [Empty; 64] }
+		Self {
+			board: [Empty; 64],
+			castling: Castling::NONE,
+			en_passant: None,
+		}
 	}
 
 	pub fn starting_position() -> Self {
@@ -21,8 +27,8 @@ impl Mailbox {
 		b.set(pos(0, 0), WRook);
 		b.set(pos(0, 1), WKnight);
 		b.set(pos(0, 2), WBisshop);
-		b.set(pos(0, 3), WKing);
-		b.set(pos(0, 4), WQueen);
+		b.set(pos(0, 3), WQueen);
+		b.set(pos(0, 4), WKing);
 		b.set(pos(0, 5), WBisshop);
 		b.set(pos(0, 6), WKnight);
 		b.set(pos(0, 7), WRook);
@@ -35,12 +41,13 @@ impl Mailbox {
 		b.set(pos(7, 0), BRook);
 		b.set(pos(7, 1), BKnight);
 		b.set(pos(7, 2), BBisshop);
-		b.set(pos(7, 3), BKing);
-		b.set(pos(7, 4), BQueen);
+		b.set(pos(7, 3), BQueen);
+		b.set(pos(7, 4), BKing);
 		b.set(pos(7, 5), BBisshop);
 		b.set(pos(7, 6), BKnight);
 		b.set(pos(7, 7), BRook);
 
+		b.castling = Castling::ALL;
 		b
 	}
 
@@ -49,14 +56,70 @@ impl Mailbox {
 	}
 
 	pub fn set(&mut self, pos: Pos, sq: Square) {
+		self.lose_castling(pos);
 		self[pos] = sq
 	}
 
+	pub fn castling(&self) -> Castling {
+		self.castling
+	}
+
+	pub fn en_passant(&self) -> Option<Pos> {
+		self.en_passant
+	}
+
 	pub fn do_move(&mut self, mv: Move) {
-		self[mv.to] = self[mv.from];
+		let piece = self[mv.from];
+
+		// en passant: remove the pawn that was passed
+		if piece.is_pawn() && Some(mv.to) == self.en_passant && mv.from.col() != mv.to.col() {
+			self[pos(mv.from.row(), mv.to.col())] = Empty;
+		}
+		self.en_passant = None;
+		if piece.is_pawn() && (mv.to.row() as i8 - mv.from.row() as i8).abs() == 2 {
+			// only if an opposing pawn next to the pushed one can capture.
+			let opponent = if piece == WPawn { BPawn } else { WPawn };
+			let capturable = [-1, 1].into_iter().any(|dc| {
+				let next = mv.to + delta(0, dc);
+				next.is_valid() && self[next] == opponent
+			});
+			if capturable {
+				self.en_passant = Some(pos((mv.from.row() + mv.to.row()) / 2, mv.from.col()));
+			}
+		}
+
+		// castling: also move the rook
+		if self[mv.from].is_king() && (mv.to.col() as i8 - mv.from.col() as i8).abs() == 2 {
+			let row = mv.from.row();
+			let (rook_from, rook_to) = if mv.to.col() == 6 {
+				(pos(row, 7), pos(row, 5))
+			} else {
+				(pos(row, 0), pos(row, 3))
+			};
+			self[rook_to] = self[rook_from];
+			self[rook_from] = Empty;
+		}
+
+		self.lose_castling(mv.from);
+		self.lose_castling(mv.to);
+		self[mv.to] = mv.promotion.unwrap_or(self[mv.from]);
 		self[mv.from] = Empty;
 	}
 
+	// Moving from, or capturing on, a king or rook starting square loses the corresponding rights.
+	fn lose_castling(&mut self, p: Pos) {
+		let lost = match (p.row(), p.col()) {
+			(0, 0) => Castling::W_QUEENSIDE,
+			(0, 4) => Castling::W_QUEENSIDE.with(Castling::W_KINGSIDE),
+			(0, 7) => Castling::W_KINGSIDE,
+			(7, 0) => Castling::B_QUEENSIDE,
+			(7, 4) => Castling::B_QUEENSIDE.with(Castling::B_KINGSIDE),
+			(7, 7) => Castling::B_KINGSIDE,
+			_ => Castling::NONE,
+		};
+		self.castling = self.castling.without(lost);
+	}
+
 	pub fn iter<'s>(&'s self) -> impl Iterator<Item = (Pos, Square)> + 's {
 		self.board
 			.iter()
@@ -70,18 +133,76 @@ impl Mailbox {
 		for r in 0..8 {
 			for c in 0..8 {
 				let pos = pos(r, c);
-				if self[pos].is_color(player) {
-					moves.extend(
-						self.dests_for(pos) //
-							.iter()
-							.map(|&dst| Move::new(self.at(pos), pos, dst)),
-					)
+				let piece = self[pos];
+				if piece.is_color(player) {
+					for dst in self.dests_for(pos) {
+						if piece.is_pawn() && (dst.row() == 0 || dst.row() == 7) {
+							for promotion in Self::promotions(player) {
+								moves.push(Move::with_promotion(piece, pos, dst, promotion))
+							}
+						} else {
+							moves.push(Move::new(piece, pos, dst))
+						}
+					}
 				}
 			}
 		}
+		self.castling_moves(&mut moves, player);
 		moves
 	}
 
+	fn promotions(player: Color) -> [Square; 4] {
+		match player {
+			White => [WQueen, WRook, WBisshop, WKnight],
+			Black => [BQueen, BRook, BBisshop, BKnight],
+		}
+	}
+
+	fn castling_moves(&self, moves: &mut SmVec<Move>, player: Color) {
+		let (row, rook) = match player {
+			White => (0, WRook),
+			Black => (7, BRook),
+		};
+		let king = pos(row, 4);
+		let opponent = player.opposite();
+
+		// (rights, rook column, squares that must be empty, squares that must not be attacked)
+		let sides: [(Castling, u8, &[u8], &[u8]); 2] = [
+			(Castling::kingside(player), 7, &[5, 6], &[4, 5, 6]),
+			(Castling::queenside(player), 0, &[1, 2, 3], &[4, 3, 2]),
+		];
+
+		for (rights, rook_col, empty, safe) in sides {
+			if self.castling.has(rights)
+				&& self[king] == player.king()
+				&& self[pos(row, rook_col)] == rook
+				&& empty.iter().all(|&c| self[pos(row, c)].is_empty())
+				&& safe.iter().all(|&c| !self.is_attacked(pos(row, c), opponent))
+			{
+				let to = if rook_col == 7 { pos(row, 6) } else { pos(row, 2) };
+				moves.push(Move::new(player.king(), king, to));
+			}
+		}
+	}
+
+	/// Is `target` attacked by any piece of color `by`?
+	fn is_attacked(&self, target: Pos, by: Color) -> bool {
+		for (pos, piece) in self.iter() {
+			if !piece.is_color(by) {
+				continue;
+			}
+			let attacks = match piece {
+				WPawn => [pos + delta(1, -1), pos + delta(1, 1)].into_iter().filter(|p| p.is_valid()).collect(),
+				BPawn => [pos + delta(-1, -1), pos + delta(-1, 1)].into_iter().filter(|p| p.is_valid()).collect(),
+				_ => self.dests_for(pos),
+			};
+			if attacks.contains(&target) {
+				return true;
+			}
+		}
+		false
+	}
+
 	pub fn dests_for(&self, pos: Pos) -> SmVec<Pos> {
 		debug_assert!(pos.is_valid());
 
@@ -168,9 +289,16 @@ impl Mailbox {
 	}
 
 	fn pawn_captures(&self, dests: &mut SmVec<Pos>, player: Color, pos: Pos, left: u8, right: u8) {
+		// en passant target must be behind an opponent's pawn
+		let en_passant_row = match player {
+			White => 5,
+			Black => 2,
+		};
+		let en_passant = self.en_passant.filter(|p| p.row() == en_passant_row);
+
 		for delta in [left, right] {
 			let pos = pos + delta;
-			if pos.is_valid() && self[pos].is_color(player.opposite()) {
+			if pos.is_valid() && (self[pos].is_color(player.opposite()) || Some(pos) == en_passant) {
 				dests.push(pos)
 			}
 		}
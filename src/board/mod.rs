@@ -1,21 +1,43 @@
 mod internal;
 
 mod attack_vector;
+#[allow(clippy::module_inception)]
 mod board;
+mod castling;
 mod color;
+mod fen;
 mod formatter;
+mod legal;
+mod magic;
 mod moves;
+mod outcome;
 mod parser;
+mod perft;
+mod pgn;
 mod pos;
+mod position;
+mod pst;
+mod san;
+mod see;
 mod square;
+mod zobrist;
 
 pub use attack_vector::*;
 pub use board::*;
+pub use castling::*;
 pub use color::*;
+pub use fen::*;
 pub use formatter::*;
+pub use magic::*;
 pub use moves::*;
+pub use outcome::*;
+pub use perft::*;
+pub use pgn::*;
 pub use pos::*;
+pub use position::*;
+pub use pst::{Tapered, MAX_PHASE};
 pub use square::*;
+pub use zobrist::side_key;
 
 pub use Color::*;
 pub use Square::*;
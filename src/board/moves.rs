@@ -5,6 +5,8 @@ pub struct Move {
 	pub piece: Square,
 	pub from: Pos,
 	pub to: Pos,
+	/// Piece a pawn is promoted to when reaching the last row.
+	pub promotion: Option<Square>,
 }
 
 impl Move {
@@ -14,7 +16,24 @@ impl Move {
 		debug_assert!(to.is_valid());
 		debug_assert!(from != to);
 		debug_assert!(!piece.is_empty());
-		Self { piece, from, to }
+		Self {
+			piece,
+			from,
+			to,
+			promotion: None,
+		}
+	}
+
+	/// Pawn move to the last row, promoting to `promotion` (of the pawn's color).
+	#[inline]
+	pub fn with_promotion(piece: Square, from: Pos, to: Pos, promotion: Square) -> Self {
+		debug_assert!(piece.is_pawn());
+		debug_assert!(promotion.color() == piece.color());
+		debug_assert!(!promotion.is_pawn() && !promotion.is_king());
+		Self {
+			promotion: Some(promotion),
+			..Self::new(piece, from, to)
+		}
 	}
 
 	pub fn is_valid(self) -> bool {
@@ -22,9 +41,14 @@ impl Move {
 	}
 }
 
+/// Coordinate notation, e.g. `e2e4` or, for promotions, `e7e8q`.
 impl fmt::Display for Move {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "{}{}", self.from, self.to)
+		write!(f, "{}{}", self.from, self.to)?;
+		if let Some(promotion) = self.promotion {
+			write!(f, "{}", promotion.to_char().to_ascii_lowercase())?;
+		}
+		Ok(())
 	}
 }
 
@@ -37,15 +61,44 @@ impl fmt::Debug for Move {
 impl FromStr for Move {
 	type Err = anyhow::Error;
 
+	/// Parse a piece and coordinates, with optional promotion suffix. E.g.:
+	///   Pe2e4, pe7e8q
 	fn from_str(s: &str) -> Result<Self> {
 		let bytes = s.as_bytes();
-		if bytes.len() != 5 {
-			return Err(format_err!("move '{}': syntax error: need 5 characters", s));
+		if bytes.len() != 5 && bytes.len() != 6 {
+			return Err(format_err!("move '{}': syntax error: need 5 or 6 characters", s));
 		}
 		let piece = Square::try_from(bytes[0] as char)?;
 		let from = Pos::try_from(&bytes[1..3])?;
 		let to = Pos::try_from(&bytes[3..5])?;
-		Ok(Move::new(piece, from, to))
+		if from == to || piece.is_empty() {
+			return Err(format_err!("move '{}': invalid move", s));
+		}
+		let last_rank = match piece {
+			Square::WPawn => to.row() == 7,
+			Square::BPawn => to.row() == 0,
+			_ => false,
+		};
+		match bytes.get(5) {
+			None if last_rank => Err(format_err!("move '{}': a pawn reaching the last rank must promote", s)),
+			None => Ok(Move::new(piece, from, to)),
+			Some(&chr) => {
+				// promotion piece takes the pawn's color, regardless of case.
+				let chr = match piece {
+					Square::WPawn => chr.to_ascii_uppercase(),
+					Square::BPawn => chr.to_ascii_lowercase(),
+					_ => return Err(format_err!("move '{}': only pawns can promote", s)),
+				};
+				let promotion = Square::try_from(chr as char)?;
+				if promotion.is_empty() || promotion.is_pawn() || promotion.is_king() {
+					return Err(format_err!("move '{}': invalid promotion, want one of 'qrbn'", s));
+				}
+				if !last_rank {
+					return Err(format_err!("move '{}': pawns only promote on the last rank", s));
+				}
+				Ok(Move::with_promotion(piece, from, to, promotion))
+			}
+		}
 	}
 }
 
@@ -62,3 +115,32 @@ impl Moves {
 		self.inner.into_iter().collect()
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn display_from_str() {
+		let mv = Move::from_str("Pe2e4").unwrap();
+		assert_eq!(mv, Move::new(Square::WPawn, pos(1, 4), pos(3, 4)));
+		assert_eq!(mv.to_string(), "e2e4");
+
+		let mv = Move::from_str("pb2a1n").unwrap();
+		assert_eq!(mv, Move::with_promotion(Square::BPawn, pos(1, 1), pos(0, 0), Square::BKnight));
+		assert_eq!(mv.to_string(), "b2a1n");
+		assert_eq!(Move::from_str(&format!("p{}", mv)).unwrap(), mv);
+
+		assert_eq!(Move::from_str("Pe7e8Q").unwrap(), Move::from_str("Pe7e8q").unwrap());
+		assert_eq!(Move::from_str("Pe7e8q").unwrap().promotion, Some(Square::WQueen));
+
+		assert!(Move::from_str("Pe7e8").is_err());
+		assert!(Move::from_str("pe2e1").is_err());
+		assert!(Move::from_str("Pe2e4q").is_err());
+		assert!(Move::from_str("Pe7e8k").is_err());
+		assert!(Move::from_str("Pe7e8p").is_err());
+		assert!(Move::from_str("Pe7e8.").is_err());
+		assert!(Move::from_str("Re7e8q").is_err());
+		assert!(Move::from_str("Pe7e8qq").is_err());
+	}
+}
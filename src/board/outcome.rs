@@ -0,0 +1,184 @@
+use super::internal::*;
+use Square::*;
+
+/// How a game ended.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+	Checkmate { winner: Color },
+	Draw(DrawReason),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrawReason {
+	/// The side to move has no legal moves, but is not in check.
+	Stalemate,
+	/// The same position (including side to move, castling rights and en passant) for the third time.
+	ThreefoldRepetition,
+	/// Fifty moves by each side without capture or pawn move.
+	FiftyMoveRule,
+	/// Neither side can possibly mate.
+	InsufficientMaterial,
+}
+
+/// Squares a1, c1, ..., b2, d2, ...
+const DARK_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+
+impl Outcome {
+	/// The winner, `None` for a draw.
+	pub fn winner(self) -> Option<Color> {
+		match self {
+			Outcome::Checkmate { winner } => Some(winner),
+			Outcome::Draw(_) => None,
+		}
+	}
+}
+
+impl fmt::Display for Outcome {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Outcome::Checkmate { winner } => write!(f, "{} wins by checkmate", winner),
+			Outcome::Draw(reason) => write!(f, "draw by {}", reason),
+		}
+	}
+}
+
+impl fmt::Display for DrawReason {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			DrawReason::Stalemate => "stalemate",
+			DrawReason::ThreefoldRepetition => "threefold repetition",
+			DrawReason::FiftyMoveRule => "the fifty-move rule",
+			DrawReason::InsufficientMaterial => "insufficient material",
+		})
+	}
+}
+
+impl Position {
+	/// How the game has ended, `None` while it goes on.
+	/// Draws by repetition and the fifty-move rule are taken as soon as they can be claimed,
+	/// but mate on the fiftieth move still counts.
+	pub fn outcome(&self) -> Option<Outcome> {
+		if self.legal_moves().is_empty() {
+			return Some(match self.is_check() {
+				true => Outcome::Checkmate {
+					winner: self.player().opposite(),
+				},
+				false => Outcome::Draw(DrawReason::Stalemate),
+			});
+		}
+		if self.board().is_insufficient_material() {
+			return Some(Outcome::Draw(DrawReason::InsufficientMaterial));
+		}
+		if self.halfmove_clock() >= 100 {
+			return Some(Outcome::Draw(DrawReason::FiftyMoveRule));
+		}
+		if self.repetitions() >= 3 {
+			return Some(Outcome::Draw(DrawReason::ThreefoldRepetition));
+		}
+		None
+	}
+
+	/// How many times this position has occurred (at least once: now).
+	pub fn repetitions(&self) -> usize {
+		let key = self.zobrist();
+		1 + self.history().iter().filter(|&&k| k == key).count()
+	}
+}
+
+impl Board {
+	/// Can neither side ever mate? That is when only kings are left, plus
+	/// a single knight or bishop, or any number of bishops all on squares of the same color.
+	pub fn is_insufficient_material(&self) -> bool {
+		let heavy = [WPawn, WRook, WQueen, BPawn, BRook, BQueen].iter().any(|&piece| self.bits(piece) != 0);
+		if heavy {
+			return false;
+		}
+		let knights = self.bits(WKnight) | self.bits(BKnight);
+		let bishops = self.bits(WBisshop) | self.bits(BBisshop);
+		match knights {
+			0 => bishops & DARK_SQUARES == 0 || bishops & !DARK_SQUARES == 0,
+			_ => bishops == 0 && knights.count_ones() == 1,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use Color::*;
+
+	fn outcome(fen: &str) -> Option<Outcome> {
+		Position::from_fen(fen).unwrap().outcome()
+	}
+
+	/// Play `moves` (SAN) from the starting position.
+	fn play(moves: &str) -> Position {
+		let mut p = Position::starting_position();
+		for san in moves.split_whitespace() {
+			let mv = p.parse_san(san).unwrap();
+			p.play(mv);
+		}
+		p
+	}
+
+	#[test]
+	fn mate_and_stalemate() {
+		assert_eq!(outcome("R5k1/5ppp/8/8/8/8/8/4K3 b - - 1 1"), Some(Outcome::Checkmate { winner: White }));
+		assert_eq!(play("f3 e5 g4 Qh4").outcome(), Some(Outcome::Checkmate { winner: Black }));
+		assert_eq!(outcome("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), Some(Outcome::Draw(DrawReason::Stalemate)));
+		assert_eq!(outcome("7k/5Q2/6K1/8/8/8/8/8 w - - 0 1"), None);
+		assert_eq!(Outcome::Checkmate { winner: White }.winner(), Some(White));
+		assert_eq!(Outcome::Draw(DrawReason::Stalemate).winner(), None);
+	}
+
+	#[test]
+	fn repetition() {
+		let p = play("Nf3 Nf6 Ng1 Ng8");
+		assert_eq!(p.repetitions(), 2);
+		assert_eq!(p.outcome(), None);
+		let p = play("Nf3 Nf6 Ng1 Ng8 Nf3 Nf6 Ng1");
+		assert_eq!(p.repetitions(), 2);
+		assert_eq!(p.outcome(), None);
+		let p = play("Nf3 Nf6 Ng1 Ng8 Nf3 Nf6 Ng1 Ng8");
+		assert_eq!(p.repetitions(), 3);
+		assert_eq!(p.outcome(), Some(Outcome::Draw(DrawReason::ThreefoldRepetition)));
+
+		// a pawn move makes all earlier positions unreachable.
+		let p = play("Nf3 Nf6 Ng1 Ng8 e3 e6 Nf3 Nf6 Ng1 Ng8");
+		assert_eq!(p.repetitions(), 2);
+		assert_eq!(p.history().len(), 4);
+
+		// losing the right to castle makes a position different.
+		let p = play("e4 e5 Ke2 Ke7 Ke1 Ke8 Ke2 Ke7 Ke1 Ke8");
+		assert_eq!(p.repetitions(), 2);
+	}
+
+	#[test]
+	fn fifty_moves() {
+		let fen = "4k3/8/8/8/8/8/R7/4K3 w - - 99 80";
+		assert_eq!(outcome(fen), None);
+		let mut p = Position::from_fen(fen).unwrap();
+		p.play(p.parse_san("Ra3").unwrap());
+		assert_eq!(p.outcome(), Some(Outcome::Draw(DrawReason::FiftyMoveRule)));
+
+		// mate on the last move counts.
+		let mut p = Position::from_fen("4k3/R7/8/4K3/8/8/8/7R w - - 99 80").unwrap();
+		p.play(p.parse_san("Rh8").unwrap());
+		assert_eq!(p.outcome(), Some(Outcome::Checkmate { winner: White }));
+	}
+
+	#[test]
+	fn insufficient_material() {
+		let draw = Some(Outcome::Draw(DrawReason::InsufficientMaterial));
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), draw);
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/4KN2 w - - 0 1"), draw);
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/4KB2 b - - 0 1"), draw);
+		assert_eq!(outcome("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"), draw); // same colored bishops
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1"), None); // bishops on both colors
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/3BKB2 w - - 0 1"), draw);
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1"), None);
+		assert_eq!(outcome("4kn2/8/8/8/8/8/8/4KB2 w - - 0 1"), None);
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/4KP2 w - - 0 1"), None);
+		assert_eq!(outcome("4k3/8/8/8/8/8/8/4KR2 w - - 0 1"), None);
+	}
+}
@@ -0,0 +1,107 @@
+use super::internal::*;
+
+/// Standard perft test positions (https://www.chessprogramming.org/Perft_Results),
+/// with the published node counts for depth 1, 2, 3, ...
+pub const PERFT_SUITE: &[(&str, &str, &[u64])] = &[
+	(
+		"start",
+		"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+		&[20, 400, 8_902, 197_281, 4_865_609, 119_060_324],
+	),
+	(
+		"kiwipete",
+		"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+		&[48, 2_039, 97_862, 4_085_603, 193_690_690],
+	),
+	(
+		"position3",
+		"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+		&[14, 191, 2_812, 43_238, 674_624, 11_030_083],
+	),
+	(
+		"position4",
+		"r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+		&[6, 264, 9_467, 422_333, 15_833_292],
+	),
+	(
+		"position5",
+		"rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+		&[44, 1_486, 62_379, 2_103_487, 89_941_194],
+	),
+	(
+		"position6",
+		"r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+		&[46, 2_079, 89_890, 3_894_594, 164_075_551],
+	),
+];
+
+impl Position {
+	/// Number of leaf nodes of the legal move tree, `depth` plies deep.
+	/// Comparing against published numbers verifies move generation.
+	pub fn perft(&self, depth: u32) -> u64 {
+		perft(self.board(), self.player(), depth)
+	}
+
+	/// Perft, split per legal root move (sorted by move).
+	/// Comparing against another engine's divide output pinpoints where move generation goes wrong.
+	/// At depth 0 no moves are made, so there is nothing to split: empty.
+	pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+		if depth == 0 {
+			return vec![];
+		}
+		let mut result = self
+			.legal_moves()
+			.into_iter()
+			.map(|mv| (mv, perft(&self.board().with_move(mv), self.player().opposite(), depth - 1)))
+			.collect::<Vec<_>>();
+		result.sort_by_key(|(mv, _)| mv.to_string());
+		result
+	}
+}
+
+fn perft(board: &Board, player: Color, depth: u32) -> u64 {
+	if depth == 0 {
+		return 1;
+	}
+
+	let moves = board.legal_moves(player);
+	if depth == 1 {
+		return moves.len() as u64;
+	}
+	moves
+		.into_iter()
+		.map(|mv| perft(&board.with_move(mv), player.opposite(), depth - 1))
+		.sum()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Run the suite up to depths that stay fast in debug builds.
+	#[test]
+	fn perft_suite() {
+		for (name, fen, want) in PERFT_SUITE {
+			let position = Position::from_fen(fen).unwrap();
+			for (depth, &want) in want.iter().enumerate().map(|(i, n)| (i as u32 + 1, n)) {
+				if want > 100_000 {
+					break;
+				}
+				assert_eq!(position.perft(depth), want, "{} depth {}", name, depth);
+			}
+		}
+	}
+
+	#[test]
+	fn divide() {
+		let position = Position::from_fen(PERFT_SUITE[1].1).unwrap();
+		let divide = position.divide(2);
+		assert_eq!(divide.len(), 48);
+		assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), 2039);
+
+		let castle = divide.iter().find(|(mv, _)| mv.to_string() == "e1g1").unwrap();
+		assert_eq!(castle.1, 43);
+
+		assert_eq!(position.divide(0), vec![]);
+	}
+}
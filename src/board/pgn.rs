@@ -0,0 +1,624 @@
+use super::fen::STARTING_FEN;
+use super::internal::*;
+use std::io::BufRead;
+use Color::*;
+
+/// A game as recorded in Portable Game Notation
+/// (https://www.saremba.de/chessgml/standards/pgn/pgn-complete.htm):
+/// tags, starting position, moves with annotations, and result.
+#[derive(Clone, Debug)]
+pub struct Game {
+	/// In order of appearance, except Result, SetUp and FEN which are kept as `result` and `start`.
+	tags: Vec<(String, String)>,
+	start: Position,
+	pub moves: Vec<GameMove>,
+	pub result: GameResult,
+}
+
+/// A move in a game, with its annotations.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GameMove {
+	pub mv: Move,
+	/// Numeric Annotation Glyphs, e.g. 1 for `!`, 2 for `?`.
+	pub nags: Vec<u8>,
+	pub comment: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+	WhiteWins,
+	BlackWins,
+	Draw,
+	/// Still going, abandoned or unknown.
+	Unfinished,
+}
+
+/// The Seven Tag Roster: tags every game has, in this order.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// Longest lines written (the standard asks for at most 79 characters).
+const MAX_LINE: usize = 79;
+
+impl Game {
+	/// Game starting from `start`, without moves, with unknown ("?") Seven Tag Roster values.
+	pub fn new(start: Position) -> Self {
+		Self {
+			tags: vec![
+				("Event".into(), "?".into()),
+				("Site".into(), "?".into()),
+				("Date".into(), "????.??.??".into()),
+				("Round".into(), "?".into()),
+				("White".into(), "?".into()),
+				("Black".into(), "?".into()),
+			],
+			start,
+			moves: vec![],
+			result: GameResult::Unfinished,
+		}
+	}
+
+	pub fn tag(&self, name: &str) -> Option<&str> {
+		match name {
+			"Result" => Some(self.result.as_str()),
+			_ => self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str()),
+		}
+	}
+
+	/// Set (or add) a tag. Setting Result sets `result`.
+	/// (SetUp and FEN follow from the starting position, and cannot be set.)
+	pub fn set_tag(&mut self, name: &str, value: &str) -> Result<()> {
+		match name {
+			"Result" => self.result = GameResult::from_str(value)?,
+			"SetUp" | "FEN" => return Err(format_err!("tag {} follows from the starting position", name)),
+			_ => match self.tags.iter_mut().find(|(n, _)| n == name) {
+				Some((_, v)) => *v = value.into(),
+				None => self.tags.push((name.into(), value.into())),
+			},
+		}
+		Ok(())
+	}
+
+	pub fn start(&self) -> &Position {
+		&self.start
+	}
+
+	/// Append a move (which must be legal in the current position), returning it for annotation. E.g.:
+	///   game.push(mv).comment = Some("+0.35".into());
+	pub fn push(&mut self, mv: Move) -> &mut GameMove {
+		self.moves.push(GameMove {
+			mv,
+			nags: vec![],
+			comment: None,
+		});
+		self.moves.last_mut().unwrap()
+	}
+
+	/// The position after all moves.
+	pub fn position(&self) -> Position {
+		let mut position = self.start.clone();
+		for m in &self.moves {
+			position.play(m.mv);
+		}
+		position
+	}
+
+	/// All tags to write: Seven Tag Roster, setup, others.
+	fn all_tags(&self) -> Vec<(&str, String)> {
+		let mut tags = SEVEN_TAG_ROSTER
+			.iter()
+			.map(|&name| (name, self.tag(name).unwrap_or("?").to_owned()))
+			.collect::<Vec<_>>();
+		let fen = self.start.to_fen();
+		if fen != STARTING_FEN {
+			tags.push(("SetUp", "1".into()));
+			tags.push(("FEN", fen));
+		}
+		for (name, value) in &self.tags {
+			if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+				tags.push((name, value.clone()));
+			}
+		}
+		tags
+	}
+}
+
+/// Export format: tags, an empty line, movetext wrapped at 79 characters.
+impl fmt::Display for Game {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for (name, value) in self.all_tags() {
+			writeln!(f, "[{} \"{}\"]", name, value.replace('\\', "\\\\").replace('"', "\\\""))?;
+		}
+		writeln!(f)?;
+
+		let mut tokens = vec![];
+		let mut position = self.start.clone();
+		let mut need_number = true; // black moves are numbered at the start and after comments.
+		for m in &self.moves {
+			match position.player() {
+				White => tokens.push(format!("{}.", position.fullmove_number())),
+				Black if need_number => tokens.push(format!("{}...", position.fullmove_number())),
+				Black => (),
+			}
+			need_number = false;
+			tokens.push(m.mv.to_san(position.board()));
+			tokens.extend(m.nags.iter().map(|nag| format!("${}", nag)));
+			if let Some(comment) = &m.comment {
+				tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+				need_number = true;
+			}
+			position.play(m.mv);
+		}
+		tokens.push(self.result.to_string());
+
+		let mut line_len = 0;
+		for token in tokens {
+			if line_len != 0 && line_len + 1 + token.len() > MAX_LINE {
+				writeln!(f)?;
+				line_len = 0;
+			} else if line_len != 0 {
+				write!(f, " ")?;
+				line_len += 1;
+			}
+			write!(f, "{}", token)?;
+			line_len += token.len();
+		}
+		writeln!(f)
+	}
+}
+
+impl GameResult {
+	/// A win for `winner`, or a draw.
+	pub fn from_winner(winner: Option<Color>) -> Self {
+		match winner {
+			Some(White) => GameResult::WhiteWins,
+			Some(Black) => GameResult::BlackWins,
+			None => GameResult::Draw,
+		}
+	}
+
+	fn as_str(self) -> &'static str {
+		match self {
+			GameResult::WhiteWins => "1-0",
+			GameResult::BlackWins => "0-1",
+			GameResult::Draw => "1/2-1/2",
+			GameResult::Unfinished => "*",
+		}
+	}
+}
+
+impl fmt::Display for GameResult {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl FromStr for GameResult {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		Ok(match s {
+			"1-0" => GameResult::WhiteWins,
+			"0-1" => GameResult::BlackWins,
+			"1/2-1/2" => GameResult::Draw,
+			"*" => GameResult::Unfinished,
+			other => return Err(format_err!("invalid result: {}", other)),
+		})
+	}
+}
+
+/// All games in `pgn`.
+pub fn parse_pgn(pgn: &str) -> Result<Vec<Game>> {
+	PgnReader::new(pgn.as_bytes()).collect()
+}
+
+/// Reads games from a PGN file one at a time.
+///
+/// Comments (`{...}` and `; ...`) are kept with the move they follow, as are NAGs (`$1`, or `!`, `?`, ...).
+/// Variations (`(...)`) are skipped: only the main line is kept.
+/// After a game with an error, reading continues with the next game.
+pub struct PgnReader<R> {
+	lexer: Lexer<R>,
+	/// Games read so far, for error messages.
+	games: usize,
+}
+
+impl<R: BufRead> PgnReader<R> {
+	pub fn new(reader: R) -> Self {
+		Self {
+			lexer: Lexer::new(reader),
+			games: 0,
+		}
+	}
+
+	fn read_game(&mut self) -> Result<Option<Game>> {
+		let mut tags = vec![];
+		while self.lexer.peek()? == Some(&Token::TagOpen) {
+			self.lexer.next()?;
+			let (Some(Token::Symbol(name)), Some(Token::Str(value)), Some(Token::TagClose)) =
+				(self.lexer.next()?, self.lexer.next()?, self.lexer.next()?)
+			else {
+				return Err(format_err!("invalid tag, want [Name \"value\"]"));
+			};
+			tags.push((name, value));
+		}
+		if tags.is_empty() && self.lexer.peek()?.is_none() {
+			return Ok(None);
+		}
+
+		let start = match tags.iter().find(|(name, _)| name == "FEN") {
+			Some((_, fen)) => Position::from_fen(fen)?,
+			None => Position::starting_position(),
+		};
+		let mut game = Game::new(start);
+		for (name, value) in &tags {
+			if name != "SetUp" && name != "FEN" {
+				game.set_tag(name, value)?;
+			}
+		}
+
+		let mut position = game.start.clone();
+		loop {
+			match self.lexer.peek()? {
+				None | Some(Token::TagOpen) => break, // no termination marker
+				_ => (),
+			}
+			match self.lexer.next()?.unwrap() {
+				Token::Period => (),
+				Token::Symbol(s) if GameResult::from_str(&s).is_ok() => {
+					game.result = GameResult::from_str(&s)?;
+					break;
+				}
+				Token::Symbol(s) if s.bytes().all(|c| c.is_ascii_digit()) => (), // move number
+				Token::Symbol(san) => {
+					let mv = position
+						.parse_san(&san)
+						.map_err(|e| format_err!("move {}: {}", position.fullmove_number(), e))?;
+					position.play(mv);
+					game.push(mv);
+				}
+				Token::Comment(comment) => {
+					if let Some(last) = game.moves.last_mut() {
+						last.comment = Some(match last.comment.take() {
+							None => comment,
+							Some(earlier) => format!("{} {}", earlier, comment),
+						});
+					}
+				}
+				Token::Nag(nag) => {
+					if let Some(last) = game.moves.last_mut() {
+						last.nags.push(nag);
+					}
+				}
+				Token::VariationOpen => self.skip_variation()?,
+				other => return Err(format_err!("unexpected {:?}", other)),
+			}
+		}
+		Ok(Some(game))
+	}
+
+	/// Skip to the end of a variation (whose opening parenthesis has been read), including nested variations.
+	fn skip_variation(&mut self) -> Result<()> {
+		let mut depth = 1;
+		while depth > 0 {
+			match self.lexer.next()? {
+				None => return Err(format_err!("unterminated variation")),
+				Some(Token::VariationOpen) => depth += 1,
+				Some(Token::VariationClose) => depth -= 1,
+				Some(_) => (),
+			}
+		}
+		Ok(())
+	}
+
+	/// After an error, skip the rest of the game.
+	fn skip_game(&mut self) {
+		while let Ok(Some(token)) = self.lexer.next() {
+			if let Token::Symbol(s) = token {
+				if GameResult::from_str(&s).is_ok() {
+					return;
+				}
+			}
+		}
+	}
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+	type Item = Result<Game>;
+
+	fn next(&mut self) -> Option<Result<Game>> {
+		self.games += 1;
+		match self.read_game() {
+			Ok(game) => game.map(Ok),
+			Err(e) => {
+				let e = format_err!("PGN game {} (line {}): {}", self.games, self.lexer.line, e);
+				self.skip_game();
+				Some(Err(e))
+			}
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Token {
+	TagOpen,
+	TagClose,
+	Str(String),
+	/// Tag names, move numbers, moves and results.
+	Symbol(String),
+	Period,
+	Comment(String),
+	Nag(u8),
+	VariationOpen,
+	VariationClose,
+}
+
+/// Splits PGN into tokens, reading a line at a time.
+struct Lexer<R> {
+	reader: R,
+	chars: Vec<char>,
+	pos: usize,
+	/// Current line number, for error messages.
+	line: usize,
+	peeked: Option<Option<Token>>,
+}
+
+impl<R: BufRead> Lexer<R> {
+	fn new(reader: R) -> Self {
+		Self {
+			reader,
+			chars: vec![],
+			pos: 0,
+			line: 0,
+			peeked: None,
+		}
+	}
+
+	fn peek(&mut self) -> Result<Option<&Token>> {
+		if self.peeked.is_none() {
+			self.peeked = Some(self.token()?);
+		}
+		Ok(self.peeked.as_ref().unwrap().as_ref())
+	}
+
+	fn next(&mut self) -> Result<Option<Token>> {
+		match self.peeked.take() {
+			Some(token) => Ok(token),
+			None => self.token(),
+		}
+	}
+
+	/// Next character, `\n` at the end of each line, `None` at the end of input.
+	fn next_char(&mut self) -> Result<Option<char>> {
+		while self.pos >= self.chars.len() {
+			let mut line = String::new();
+			if self.reader.read_line(&mut line)? == 0 {
+				return Ok(None);
+			}
+			self.line += 1;
+			// lines starting with % are for other programs.
+			if line.starts_with('%') {
+				continue;
+			}
+			self.chars = line.trim_end().chars().chain(['\n']).collect();
+			self.pos = 0;
+		}
+		self.pos += 1;
+		Ok(Some(self.chars[self.pos - 1]))
+	}
+
+	fn peek_char(&self) -> Option<char> {
+		self.chars.get(self.pos).copied()
+	}
+
+	fn token(&mut self) -> Result<Option<Token>> {
+		let c = loop {
+			match self.next_char()? {
+				None => return Ok(None),
+				Some(c) if c.is_whitespace() => continue,
+				Some(c) => break c,
+			}
+		};
+
+		Ok(Some(match c {
+			'[' => Token::TagOpen,
+			']' => Token::TagClose,
+			'(' => Token::VariationOpen,
+			')' => Token::VariationClose,
+			'.' => Token::Period,
+			'*' => Token::Symbol("*".into()),
+			'{' => {
+				let mut comment = String::new();
+				loop {
+					match self.next_char()? {
+						None => return Err(format_err!("unterminated comment")),
+						Some('}') => break,
+						Some('\n') => comment.push(' '),
+						Some(c) => comment.push(c),
+					}
+				}
+				Token::Comment(comment.trim().into())
+			}
+			';' => {
+				let rest = self.chars[self.pos..].iter().collect::<String>();
+				self.pos = self.chars.len();
+				Token::Comment(rest.trim().into())
+			}
+			'"' => {
+				let mut value = String::new();
+				loop {
+					match self.next_char()? {
+						None | Some('\n') => return Err(format_err!("unterminated string")),
+						Some('"') => break,
+						Some('\\') => match self.next_char()? {
+							Some(c @ ('"' | '\\')) => value.push(c),
+							_ => return Err(format_err!("invalid escape in string")),
+						},
+						Some(c) => value.push(c),
+					}
+				}
+				Token::Str(value)
+			}
+			'$' => {
+				let mut digits = String::new();
+				while let Some(c) = self.peek_char().filter(char::is_ascii_digit) {
+					digits.push(c);
+					self.pos += 1;
+				}
+				Token::Nag(digits.parse().map_err(|_| format_err!("invalid NAG: ${}", digits))?)
+			}
+			'!' | '?' => {
+				let mut suffix = c.to_string();
+				while let Some(c) = self.peek_char().filter(|c| *c == '!' || *c == '?') {
+					suffix.push(c);
+					self.pos += 1;
+				}
+				Token::Nag(match suffix.as_str() {
+					"!" => 1,
+					"?" => 2,
+					"!!" => 3,
+					"??" => 4,
+					"!?" => 5,
+					"?!" => 6,
+					other => return Err(format_err!("invalid annotation: {}", other)),
+				})
+			}
+			c if c.is_ascii_alphanumeric() => {
+				let mut symbol = c.to_string();
+				while let Some(c) = self.peek_char().filter(|c| c.is_ascii_alphanumeric() || "_+#=:-/".contains(*c)) {
+					symbol.push(c);
+					self.pos += 1;
+				}
+				Token::Symbol(symbol)
+			}
+			other => return Err(format_err!("unexpected character '{}'", other)),
+		}))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn play(game: &mut Game, sans: &str) {
+		for san in sans.split_whitespace() {
+			let mv = game.position().parse_san(san).unwrap();
+			game.push(mv);
+		}
+	}
+
+	#[test]
+	fn write() {
+		let mut game = Game::new(Position::starting_position());
+		game.set_tag("Event", "Test \"quotes\"").unwrap();
+		game.set_tag("White", "alphabeta3-material").unwrap();
+		game.set_tag("Seed", "1234").unwrap();
+		play(&mut game, "e4 e5 Bc4 Nc6 Qh5");
+		game.moves[4].comment = Some("+1".into());
+		game.moves[4].nags.push(6);
+		play(&mut game, "Nf6 Qxf7#");
+		game.result = GameResult::WhiteWins;
+
+		assert_eq!(
+			game.to_string(),
+			r#"[Event "Test \"quotes\""]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "alphabeta3-material"]
+[Black "?"]
+[Result "1-0"]
+[Seed "1234"]
+
+1. e4 e5 2. Bc4 Nc6 3. Qh5 $6 {+1} 3... Nf6 4. Qxf7# 1-0
+"#
+		);
+
+		// from a position with Black to move.
+		let mut game = Game::new(Position::from_fen("4k3/8/8/8/8/8/4p3/4K3 b - - 0 40").unwrap());
+		play(&mut game, "Kd7 Kxe2");
+		assert!(game.to_string().contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4p3/4K3 b - - 0 40\"]\n"));
+		assert!(game.to_string().ends_with("\n\n40... Kd7 41. Kxe2 *\n"));
+	}
+
+	#[test]
+	fn wrap_lines() {
+		let mut game = Game::new(Position::starting_position());
+		for _ in 0..10 {
+			play(&mut game, "Nf3 Nf6 Ng1 Ng8");
+		}
+		let pgn = game.to_string();
+		let movetext = pgn.split("\n\n").nth(1).unwrap();
+		assert!(movetext.lines().count() > 1);
+		assert!(movetext.lines().all(|line| line.len() <= MAX_LINE));
+		assert_eq!(parse_pgn(&pgn).unwrap()[0].moves, game.moves);
+	}
+
+	#[test]
+	fn read() {
+		let pgn = r#"
+% a line for other programs
+[Event "Casual"]
+[White "Somebody"]
+[Black "Somebody else"]
+[Result "0-1"]
+
+1. f3 e5 $2 {A comment
+  over two lines} 2. g4?? (2. Kf2 (2. e4) d5) 2... Qh4# ; mate
+0-1
+
+[Event "Setup"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4p3/4K3 b - - 0 40"]
+
+40... Kd7 41. Kxe2 1/2-1/2
+
+1.e4 c5 2.Nf3 d6 *
+"#;
+		let games = parse_pgn(pgn).unwrap();
+		assert_eq!(games.len(), 3);
+
+		let fools_mate = &games[0];
+		assert_eq!(fools_mate.tag("Event"), Some("Casual"));
+		assert_eq!(fools_mate.tag("Black"), Some("Somebody else"));
+		assert_eq!(fools_mate.tag("Site"), Some("?"));
+		assert_eq!(fools_mate.result, GameResult::BlackWins);
+		let moves = fools_mate.moves.iter().map(|m| m.mv.to_string()).collect::<Vec<_>>();
+		assert_eq!(moves, ["f2f3", "e7e5", "g2g4", "d8h4"]);
+		assert_eq!(fools_mate.moves[1].nags, [2]);
+		assert_eq!(fools_mate.moves[1].comment.as_deref(), Some("A comment   over two lines"));
+		assert_eq!(fools_mate.moves[2].nags, [4]);
+		assert_eq!(fools_mate.moves[3].comment.as_deref(), Some("mate"));
+		assert!(fools_mate.position().is_mate());
+
+		let setup = &games[1];
+		assert_eq!(setup.start().to_fen(), "4k3/8/8/8/8/8/4p3/4K3 b - - 0 40");
+		assert_eq!(setup.moves.len(), 2);
+		assert_eq!(setup.result, GameResult::Draw);
+
+		let no_tags = &games[2];
+		assert_eq!(no_tags.moves.len(), 4);
+		assert_eq!(no_tags.result, GameResult::Unfinished);
+
+		// writing and reading again gives the same games.
+		for game in &games {
+			let again = &parse_pgn(&game.to_string()).unwrap()[0];
+			assert_eq!(again.moves, game.moves);
+			assert_eq!(again.all_tags(), game.all_tags());
+		}
+	}
+
+	#[test]
+	fn read_errors() {
+		let pgn = "1. e4 e5 2. Ke3 Nc6 *\n\n[Event \"next\"]\n1. d4 *\n[Event \"unterminated\n";
+		let mut games = PgnReader::new(pgn.as_bytes());
+		let e = games.next().unwrap().unwrap_err().to_string();
+		assert!(e.contains("game 1") && e.contains("Ke3"), "{}", e);
+		assert_eq!(games.next().unwrap().unwrap().tag("Event"), Some("next"));
+		assert!(games.next().unwrap().is_err());
+		assert!(games.next().is_none());
+
+		assert!(parse_pgn("1. e4 (1. d4 *").is_err());
+		assert!(parse_pgn("1. e4 {no end *").is_err());
+		assert!(parse_pgn("[Result \"2-0\"]\n*").is_err());
+	}
+}
@@ -139,17 +139,17 @@ mod test {
 
 	#[test]
 	fn is_valid() {
-		assert_eq!(pos(0, 0).is_valid(), true);
-		assert_eq!(pos(0, 7).is_valid(), true);
-		assert_eq!(pos(7, 0).is_valid(), true);
-		assert_eq!(pos(7, 7).is_valid(), true);
-
-		assert_eq!((pos(0, 0) + delta(1, 2)).is_valid(), true);
-		assert_eq!((pos(0, 0) + delta(-1, 0)).is_valid(), false);
-		assert_eq!((pos(0, 0) + delta(0, -1)).is_valid(), false);
-		assert_eq!((pos(0, 0) + delta(-1, -1)).is_valid(), false);
-		assert_eq!((pos(7, 2) + delta(0, -3)).is_valid(), false);
-		assert_eq!((pos(3, 6) + delta(0, 2)).is_valid(), false);
-		assert_eq!((pos(4, 2) + delta(4, 0)).is_valid(), false);
+		assert!(pos(0, 0).is_valid());
+		assert!(pos(0, 7).is_valid());
+		assert!(pos(7, 0).is_valid());
+		assert!(pos(7, 7).is_valid());
+
+		assert!((pos(0, 0) + delta(1, 2)).is_valid());
+		assert!(!(pos(0, 0) + delta(-1, 0)).is_valid());
+		assert!(!(pos(0, 0) + delta(0, -1)).is_valid());
+		assert!(!(pos(0, 0) + delta(-1, -1)).is_valid());
+		assert!(!(pos(7, 2) + delta(0, -3)).is_valid());
+		assert!(!(pos(3, 6) + delta(0, 2)).is_valid());
+		assert!(!(pos(4, 2) + delta(4, 0)).is_valid());
 	}
 }
@@ -0,0 +1,213 @@
+use super::internal::*;
+use Color::*;
+
+/// A board plus the game state that cannot be seen on the board:
+/// side to move, half-move clock, full-move number and earlier positions (for repetitions).
+/// (Castling rights and en-passant square are kept by the Board itself.)
+///
+/// Unlike Board, a Position knows whose turn it is,
+/// so moves can only be generated and played for the side to move.
+#[derive(Clone)]
+pub struct Position {
+	board: Board,
+	player: Color,
+	halfmove_clock: u32,
+	fullmove_number: u32,
+	/// Zobrist keys of the positions since the last capture or pawn move, oldest first.
+	history: Vec<u64>,
+}
+
+impl Position {
+	/// Position with `player` to move, clocks at the start of the game.
+	pub fn new(board: Board, player: Color) -> Self {
+		Self::with_clocks(board, player, 0, 1)
+	}
+
+	pub fn with_clocks(board: Board, player: Color, halfmove_clock: u32, fullmove_number: u32) -> Self {
+		Self {
+			board,
+			player,
+			halfmove_clock,
+			fullmove_number,
+			history: vec![],
+		}
+	}
+
+	pub fn starting_position() -> Self {
+		Self::new(Board::starting_position(), White)
+	}
+
+	#[inline]
+	pub fn board(&self) -> &Board {
+		&self.board
+	}
+
+	/// Side to move.
+	#[inline]
+	pub fn player(&self) -> Color {
+		self.player
+	}
+
+	/// Number of plies since the last capture or pawn move (for the fifty-move rule).
+	#[inline]
+	pub fn halfmove_clock(&self) -> u32 {
+		self.halfmove_clock
+	}
+
+	/// Starts at 1, incremented after each move by Black.
+	#[inline]
+	pub fn fullmove_number(&self) -> u32 {
+		self.fullmove_number
+	}
+
+	/// Zobrist keys of the earlier positions that can still repeat:
+	/// those since the last capture or pawn move (as far as this Position has seen them), oldest first.
+	#[inline]
+	pub fn history(&self) -> &[u64] {
+		&self.history
+	}
+
+	#[inline]
+	pub fn castling(&self) -> Castling {
+		self.board.castling()
+	}
+
+	#[inline]
+	pub fn en_passant(&self) -> Option<Pos> {
+		self.board.en_passant()
+	}
+
+	/// Zobrist key of the board and side to move.
+	#[inline]
+	pub fn zobrist(&self) -> u64 {
+		self.board.zobrist_for(self.player)
+	}
+
+	/// All legal moves for the side to move.
+	pub fn legal_moves(&self) -> SmVec<Move> {
+		self.board.legal_moves(self.player)
+	}
+
+	pub fn is_legal(&self, mv: Move) -> bool {
+		self.legal_moves().contains(&mv)
+	}
+
+	/// The legal move written `san` in Standard Algebraic Notation, see `Board::parse_san`.
+	pub fn parse_san(&self, san: &str) -> Result<Move> {
+		self.board.parse_san(self.player, san)
+	}
+
+	/// Is the side to move in check?
+	pub fn is_check(&self) -> bool {
+		self.board.is_check(self.player)
+	}
+
+	/// Does the side to move have no legal moves left?
+	pub fn is_mate(&self) -> bool {
+		is_mate(&self.board, self.player)
+	}
+
+	/// Play a move for the side to move.
+	/// Panics if it is the other side's move.
+	pub fn play(&mut self, mv: Move) {
+		assert!(mv.piece.is_color(self.player), "{}: it is {}'s move", mv, self.player);
+		debug_assert!(self.is_legal(mv), "illegal move: {}", mv);
+
+		let is_capture = !bit_at(self.board.empty(), mv.to) || Some(mv.to) == self.en_passant() && mv.piece.is_pawn();
+		if is_capture || mv.piece.is_pawn() {
+			self.halfmove_clock = 0;
+			self.history.clear();
+		} else {
+			self.halfmove_clock += 1;
+			self.history.push(self.zobrist());
+		}
+		if self.player == Black {
+			self.fullmove_number += 1;
+		}
+
+		self.board = self.board.with_move(mv);
+		self.player = self.player.opposite();
+	}
+
+	/// Copy of this position after playing `mv`.
+	pub fn with_move(&self, mv: Move) -> Self {
+		let mut next = self.clone();
+		next.play(mv);
+		next
+	}
+}
+
+impl fmt::Display for Position {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}\n{} to move", self.board, self.player)
+	}
+}
+
+impl fmt::Debug for Position {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn play() {
+		let mut p = Position::starting_position();
+		assert_eq!(p.player(), White);
+		assert_eq!(p.legal_moves().len(), 20);
+
+		p.play(Move::from_str("Ng1f3").unwrap());
+		assert_eq!((p.player(), p.halfmove_clock(), p.fullmove_number()), (Black, 1, 1));
+
+		p.play(Move::from_str("nb8c6").unwrap());
+		assert_eq!((p.player(), p.halfmove_clock(), p.fullmove_number()), (White, 2, 2));
+
+		p.play(Move::from_str("Pe2e4").unwrap());
+		assert_eq!((p.player(), p.halfmove_clock(), p.fullmove_number()), (Black, 0, 2));
+		assert_eq!(p.en_passant(), None); // no black pawn can capture
+
+		p.play(Move::from_str("ng8f6").unwrap());
+		assert_eq!(p.halfmove_clock(), 1);
+		p.play(Move::from_str("Pe4e5").unwrap());
+		p.play(Move::from_str("nc6d4").unwrap());
+		assert_eq!(p.halfmove_clock(), 1);
+		p.play(Move::from_str("Pe5f6").unwrap()); // capture
+		assert_eq!(p.halfmove_clock(), 0);
+		assert_eq!(p.fullmove_number(), 4);
+	}
+
+	#[test]
+	fn legal_moves() {
+		let board = Board::from_str(
+			r"
+			. . . . k . . .
+			. . . . r . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . B . . .
+			. . . . K . . .
+			",
+		)
+		.unwrap();
+
+		// the bisshop is pinned
+		let p = Position::new(board.clone(), White);
+		assert!(p.legal_moves().iter().all(|mv| mv.piece == Square::WKing));
+		assert!(!p.is_check());
+
+		let p = Position::new(board, Black);
+		assert!(p.legal_moves().iter().all(|mv| mv.piece.is_color(Black)));
+	}
+
+	#[test]
+	#[should_panic]
+	fn play_wrong_side() {
+		let mut p = Position::starting_position();
+		p.play(Move::from_str("pe7e5").unwrap());
+	}
+}
@@ -0,0 +1,315 @@
+use super::internal::*;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use Square::*;
+
+/// A middlegame and an endgame value (https://www.chessprogramming.org/Tapered_Eval),
+/// blended by the game phase.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Tapered {
+	pub mg: i32,
+	pub eg: i32,
+}
+
+/// Phase with all minor and major pieces on the board. 0 is a pawn (or bare king) ending.
+pub const MAX_PHASE: i32 = 24;
+
+impl Tapered {
+	pub const fn new(mg: i32, eg: i32) -> Self {
+		Self { mg, eg }
+	}
+
+	/// Interpolate between the middlegame value (at `MAX_PHASE`) and the endgame value (at 0).
+	#[inline]
+	pub fn taper(self, phase: i32) -> i32 {
+		let phase = phase.clamp(0, MAX_PHASE);
+		(self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+	}
+}
+
+impl Add for Tapered {
+	type Output = Self;
+	#[inline]
+	fn add(self, rhs: Self) -> Self {
+		Self::new(self.mg + rhs.mg, self.eg + rhs.eg)
+	}
+}
+
+impl Sub for Tapered {
+	type Output = Self;
+	#[inline]
+	fn sub(self, rhs: Self) -> Self {
+		Self::new(self.mg - rhs.mg, self.eg - rhs.eg)
+	}
+}
+
+impl Mul<i32> for Tapered {
+	type Output = Self;
+	#[inline]
+	fn mul(self, rhs: i32) -> Self {
+		Self::new(self.mg * rhs, self.eg * rhs)
+	}
+}
+
+impl Neg for Tapered {
+	type Output = Self;
+	#[inline]
+	fn neg(self) -> Self {
+		Self::new(-self.mg, -self.eg)
+	}
+}
+
+impl AddAssign for Tapered {
+	#[inline]
+	fn add_assign(&mut self, rhs: Self) {
+		*self = *self + rhs
+	}
+}
+
+impl SubAssign for Tapered {
+	#[inline]
+	fn sub_assign(&mut self, rhs: Self) {
+		*self = *self - rhs
+	}
+}
+
+/// Value, in centipawns from White's point of view, of `piece` on square index `i` (0 for Empty).
+/// Includes the piece's material value.
+#[inline]
+pub(super) fn pst_value(piece: Square, i: usize) -> Tapered {
+	TABLES[piece.index()][i]
+}
+
+impl Board {
+	/// Game phase from the remaining material: `MAX_PHASE` at the start, 0 with only kings and pawns left.
+	/// Knights and bishops count 1, rooks 2, queens 4 (capped in case of promotions).
+	pub fn phase(&self) -> i32 {
+		let count = |a, b| (self.bits(a) | self.bits(b)).count_ones() as i32;
+		let phase = count(WKnight, BKnight) + count(WBisshop, BBisshop) + 2 * count(WRook, BRook) + 4 * count(WQueen, BQueen);
+		phase.min(MAX_PHASE)
+	}
+}
+
+/// By piece and square index, computed at compile time from the tables below.
+static TABLES: [[Tapered; 64]; 13] = tables();
+
+/// Material values by piece kind (Pawn, Rook, Knight, Bishop, Queen, King), middlegame and endgame.
+const MATERIAL: [(i32, i32); 6] = [(100, 120), (500, 520), (320, 300), (330, 320), (900, 940), (0, 0)];
+
+/// Bonuses by piece kind (same order as `MATERIAL`), for the middlegame and the endgame,
+/// from White's point of view as seen on a diagram: a8 first, h1 last.
+#[rustfmt::skip]
+const BONUS: [([i32; 64], [i32; 64]); 6] = [
+	// pawns: keep the center and the king's shelter early, run late.
+	([
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		 50,  50,  50,  50,  50,  50,  50,  50,
+		 10,  10,  20,  30,  30,  20,  10,  10,
+		  5,   5,  10,  25,  25,  10,   5,   5,
+		  0,   0,   0,  20,  20,   0,   0,   0,
+		  5,  -5, -10,   0,   0, -10,  -5,   5,
+		  5,  10,  10, -20, -20,  10,  10,   5,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+	], [
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		 90,  90,  90,  90,  90,  90,  90,  90,
+		 55,  55,  55,  55,  55,  55,  55,  55,
+		 30,  30,  30,  30,  30,  30,  30,  30,
+		 15,  15,  15,  15,  15,  15,  15,  15,
+		  5,   5,   5,   5,   5,   5,   5,   5,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+	]),
+	// rooks: the seventh row, central files.
+	([
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		  5,  10,  10,  10,  10,  10,  10,   5,
+		 -5,   0,   0,   0,   0,   0,   0,  -5,
+		 -5,   0,   0,   0,   0,   0,   0,  -5,
+		 -5,   0,   0,   0,   0,   0,   0,  -5,
+		 -5,   0,   0,   0,   0,   0,   0,  -5,
+		 -5,   0,   0,   0,   0,   0,   0,  -5,
+		  0,   0,   0,   5,   5,   0,   0,   0,
+	], [
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		 10,  10,  10,  10,  10,  10,  10,  10,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+		  0,   0,   0,   0,   0,   0,   0,   0,
+	]),
+	// knights: the center, never the rim.
+	([
+		-50, -40, -30, -30, -30, -30, -40, -50,
+		-40, -20,   0,   0,   0,   0, -20, -40,
+		-30,   0,  10,  15,  15,  10,   0, -30,
+		-30,   5,  15,  20,  20,  15,   5, -30,
+		-30,   0,  15,  20,  20,  15,   0, -30,
+		-30,   5,  10,  15,  15,  10,   5, -30,
+		-40, -20,   0,   5,   5,   0, -20, -40,
+		-50, -40, -30, -30, -30, -30, -40, -50,
+	], [
+		-50, -40, -30, -30, -30, -30, -40, -50,
+		-40, -20,   0,   0,   0,   0, -20, -40,
+		-30,   0,  10,  15,  15,  10,   0, -30,
+		-30,   5,  15,  20,  20,  15,   5, -30,
+		-30,   0,  15,  20,  20,  15,   0, -30,
+		-30,   5,  10,  15,  15,  10,   5, -30,
+		-40, -20,   0,   5,   5,   0, -20, -40,
+		-50, -40, -30, -30, -30, -30, -40, -50,
+	]),
+	// bishops: long diagonals, not the corners.
+	([
+		-20, -10, -10, -10, -10, -10, -10, -20,
+		-10,   0,   0,   0,   0,   0,   0, -10,
+		-10,   0,   5,  10,  10,   5,   0, -10,
+		-10,   5,   5,  10,  10,   5,   5, -10,
+		-10,   0,  10,  10,  10,  10,   0, -10,
+		-10,  10,  10,  10,  10,  10,  10, -10,
+		-10,   5,   0,   0,   0,   0,   5, -10,
+		-20, -10, -10, -10, -10, -10, -10, -20,
+	], [
+		-20, -10, -10, -10, -10, -10, -10, -20,
+		-10,   0,   0,   0,   0,   0,   0, -10,
+		-10,   0,   5,  10,  10,   5,   0, -10,
+		-10,   5,   5,  10,  10,   5,   5, -10,
+		-10,   0,  10,  10,  10,  10,   0, -10,
+		-10,  10,  10,  10,  10,  10,  10, -10,
+		-10,   5,   0,   0,   0,   0,   5, -10,
+		-20, -10, -10, -10, -10, -10, -10, -20,
+	]),
+	// queens: a little centralization.
+	([
+		-20, -10, -10,  -5,  -5, -10, -10, -20,
+		-10,   0,   0,   0,   0,   0,   0, -10,
+		-10,   0,   5,   5,   5,   5,   0, -10,
+		 -5,   0,   5,   5,   5,   5,   0,  -5,
+		  0,   0,   5,   5,   5,   5,   0,  -5,
+		-10,   5,   5,   5,   5,   5,   0, -10,
+		-10,   0,   5,   0,   0,   0,   0, -10,
+		-20, -10, -10,  -5,  -5, -10, -10, -20,
+	], [
+		-20, -10, -10,  -5,  -5, -10, -10, -20,
+		-10,   0,   0,   0,   0,   0,   0, -10,
+		-10,   0,   5,   5,   5,   5,   0, -10,
+		 -5,   0,   5,  10,  10,   5,   0,  -5,
+		 -5,   0,   5,  10,  10,   5,   0,  -5,
+		-10,   0,   5,   5,   5,   5,   0, -10,
+		-10,   0,   0,   0,   0,   0,   0, -10,
+		-20, -10, -10,  -5,  -5, -10, -10, -20,
+	]),
+	// king: castled behind its pawns early, in the center late.
+	([
+		-30, -40, -40, -50, -50, -40, -40, -30,
+		-30, -40, -40, -50, -50, -40, -40, -30,
+		-30, -40, -40, -50, -50, -40, -40, -30,
+		-30, -40, -40, -50, -50, -40, -40, -30,
+		-20, -30, -30, -40, -40, -30, -30, -20,
+		-10, -20, -20, -20, -20, -20, -20, -10,
+		 20,  20,   0,   0,   0,   0,  20,  20,
+		 20,  30,  10,   0,   0,  10,  30,  20,
+	], [
+		-50, -40, -30, -20, -20, -30, -40, -50,
+		-30, -20, -10,   0,   0, -10, -20, -30,
+		-30, -10,  20,  30,  30,  20, -10, -30,
+		-30, -10,  30,  40,  40,  30, -10, -30,
+		-30, -10,  30,  40,  40,  30, -10, -30,
+		-30, -10,  20,  30,  30,  20, -10, -30,
+		-30, -30,   0,   0,   0,   0, -30, -30,
+		-50, -30, -30, -30, -30, -30, -30, -50,
+	]),
+];
+
+/// Material plus bonus for every piece and square index.
+/// Black's values are White's, mirrored top to bottom and negated.
+const fn tables() -> [[Tapered; 64]; 13] {
+	let mut tables = [[Tapered::new(0, 0); 64]; 13];
+	let mut kind = 0;
+	while kind < 6 {
+		let (mg, eg) = MATERIAL[kind];
+		let (mg_bonus, eg_bonus) = &BONUS[kind];
+		let mut i = 0;
+		while i < 64 {
+			// diagram index of square index `i` for White (a8 = 0), and for Black (mirrored).
+			let white = i ^ 56;
+			let black = i;
+			tables[WPawn.index() + kind][i] = Tapered::new(mg + mg_bonus[white], eg + eg_bonus[white]);
+			tables[BPawn.index() + kind][i] = Tapered::new(-mg - mg_bonus[black], -eg - eg_bonus[black]);
+			i += 1;
+		}
+		kind += 1;
+	}
+	tables
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::perft::PERFT_SUITE;
+	use super::*;
+
+	#[test]
+	fn taper() {
+		let t = Tapered::new(100, -20);
+		assert_eq!(t.taper(MAX_PHASE), 100);
+		assert_eq!(t.taper(0), -20);
+		assert_eq!(t.taper(MAX_PHASE / 2), 40);
+		assert_eq!(t.taper(100), 100);
+		assert_eq!(t + t - t, t);
+		assert_eq!(-t, Tapered::new(-100, 20));
+		assert_eq!(t * 3, Tapered::new(300, -60));
+	}
+
+	#[test]
+	fn phase() {
+		assert_eq!(Board::starting_position().phase(), MAX_PHASE);
+		assert_eq!(Position::from_fen("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1").unwrap().board().phase(), 0);
+		assert_eq!(Position::from_fen("3qk3/8/8/8/8/8/8/2R1KB2 w - - 0 1").unwrap().board().phase(), 7);
+		assert_eq!(
+			Position::from_fen("QQQQk3/8/8/8/8/8/8/QQQQKQQQ w - - 0 1").unwrap().board().phase(),
+			MAX_PHASE
+		);
+	}
+
+	#[test]
+	fn values() {
+		// material only counts once, at the bottom of the tables.
+		assert_eq!(pst_value(Empty, 12), Tapered::default());
+		assert_eq!(pst_value(WPawn, pos(1, 4).index()), Tapered::new(80, 120));
+		assert_eq!(pst_value(BPawn, pos(6, 4).index()), Tapered::new(-80, -120));
+		assert_eq!(pst_value(WKnight, pos(0, 0).index()), Tapered::new(270, 250));
+		assert_eq!(pst_value(WKing, pos(0, 6).index()), Tapered::new(30, -30));
+		assert_eq!(pst_value(BKing, pos(7, 6).index()), Tapered::new(-30, 30));
+
+		// mirrored pieces cancel out.
+		for (white, black) in [
+			(WPawn, BPawn),
+			(WRook, BRook),
+			(WKnight, BKnight),
+			(WBisshop, BBisshop),
+			(WQueen, BQueen),
+			(WKing, BKing),
+		] {
+			for i in 0..64 {
+				assert_eq!(pst_value(white, i), -pst_value(black, i ^ 56), "{:?} {}", white, i);
+			}
+		}
+	}
+
+	#[test]
+	fn incremental() {
+		assert_eq!(Board::starting_position().pst(), Tapered::default());
+
+		// `with_move` checks the incremental update in debug builds:
+		// play two plies of captures, castling, en passant and promotions.
+		for (_, fen, _) in PERFT_SUITE {
+			let position = Position::from_fen(fen).unwrap();
+			for mv in position.legal_moves() {
+				let board = position.board().with_move(mv);
+				for reply in Position::new(board.clone(), position.player().opposite()).legal_moves() {
+					board.with_move(reply);
+				}
+			}
+		}
+	}
+}
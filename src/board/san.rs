@@ -0,0 +1,254 @@
+use super::internal::*;
+use Color::*;
+
+impl Move {
+	/// Standard Algebraic Notation (https://en.wikipedia.org/wiki/Algebraic_notation_(chess)) of this move on `board`. E.g.:
+	///
+	///   e4, Nf3, exd5, O-O, e8=Q+, Raxd1#
+	///
+	/// The move must be legal on `board`.
+	pub fn to_san(self, board: &Board) -> String {
+		let mut san = String::new();
+
+		if is_castling(self) {
+			san += if self.to.col() > self.from.col() { "O-O" } else { "O-O-O" };
+		} else {
+			let is_capture = !board.at(self.to).is_empty() || (self.piece.is_pawn() && self.from.col() != self.to.col());
+			if self.piece.is_pawn() {
+				if is_capture {
+					san.push(self.from.to_string().as_bytes()[0] as char);
+				}
+			} else {
+				san.push(self.piece.to_char().to_ascii_uppercase());
+				san += &disambiguation(board, self);
+			}
+			if is_capture {
+				san.push('x');
+			}
+			san += &self.to.to_string();
+			if let Some(promotion) = self.promotion {
+				san.push('=');
+				san.push(promotion.to_char().to_ascii_uppercase());
+			}
+		}
+
+		let opponent = self.piece.color().expect("move without piece").opposite();
+		let next = board.with_move(self);
+		if next.is_check(opponent) {
+			san.push(if next.legal_moves(opponent).is_empty() { '#' } else { '+' });
+		}
+		san
+	}
+}
+
+impl Board {
+	/// The legal move for `player` written `san` in Standard Algebraic Notation (see `Move::to_san`).
+	///
+	/// Also accepts what people and other programs tend to write:
+	/// check marks and annotations (`+`, `#`, `!`, `?`, `e.p.`) are ignored,
+	/// castling may be written with zeros (`0-0`), the promotion `=` and the capture `x` may be left out,
+	/// and needless disambiguation is fine (`Ngf3`).
+	pub fn parse_san(&self, player: Color, san: &str) -> Result<Move> {
+		parse_san(self, player, san).map_err(|e| format_err!("move '{}': {}", san, e))
+	}
+}
+
+fn parse_san(board: &Board, player: Color, san: &str) -> Result<Move> {
+	let san = san.trim().trim_end_matches(['+', '#', '!', '?']);
+	let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+	let moves = board.legal_moves(player);
+
+	if let Some(long) = match san {
+		"O-O" | "0-0" => Some(false),
+		"O-O-O" | "0-0-0" => Some(true),
+		_ => None,
+	} {
+		return moves
+			.into_iter()
+			.find(|&mv| is_castling(mv) && (mv.to.col() < mv.from.col()) == long)
+			.ok_or_else(|| format_err!("cannot castle"));
+	}
+
+	let (piece, rest) = match san.chars().next() {
+		Some(c @ ('K' | 'Q' | 'R' | 'B' | 'N')) => (piece_of(player, c)?, &san[1..]),
+		_ => (piece_of(player, 'P')?, san),
+	};
+
+	let (rest, promotion) = match rest.char_indices().last() {
+		Some((i, c @ ('Q' | 'R' | 'B' | 'N'))) if piece.is_pawn() => (rest[..i].strip_suffix('=').unwrap_or(&rest[..i]), Some(piece_of(player, c)?)),
+		_ => (rest, None),
+	};
+
+	if rest.len() < 2 || !rest.is_ascii() {
+		return Err(format_err!("syntax error"));
+	}
+	let to = Pos::try_from(&rest.as_bytes()[rest.len() - 2..])?;
+	let rest = &rest[..rest.len() - 2];
+	let rest = rest.strip_suffix('x').unwrap_or(rest);
+
+	// disambiguation: file, rank or both.
+	let mut from_col = None;
+	let mut from_row = None;
+	for c in rest.bytes() {
+		match c {
+			b'a'..=b'h' if from_col.is_none() && from_row.is_none() => from_col = Some(c - b'a'),
+			b'1'..=b'8' if from_row.is_none() => from_row = Some(c - b'1'),
+			_ => return Err(format_err!("syntax error")),
+		}
+	}
+
+	let candidates = moves
+		.into_iter()
+		.filter(|mv| mv.piece == piece && mv.to == to && mv.promotion == promotion)
+		.filter(|mv| from_col.is_none_or(|col| mv.from.col() == col) && from_row.is_none_or(|row| mv.from.row() == row))
+		.collect::<SmVec<_>>();
+
+	match candidates[..] {
+		[] => Err(format_err!("no such legal move for {}", player)),
+		[mv] => Ok(mv),
+		_ => Err(format_err!("ambiguous, could be any of {:?}", candidates)),
+	}
+}
+
+/// `player`'s piece for an uppercase SAN letter (`P` for pawn).
+fn piece_of(player: Color, c: char) -> Result<Square> {
+	Square::try_from(match player {
+		White => c,
+		Black => c.to_ascii_lowercase(),
+	})
+}
+
+fn is_castling(mv: Move) -> bool {
+	mv.piece.is_king() && (mv.from.col() as i8 - mv.to.col() as i8).abs() == 2
+}
+
+/// File, rank or both of `mv`'s origin, as far as needed to tell it apart
+/// from moves of the same kind of piece to the same square.
+fn disambiguation(board: &Board, mv: Move) -> String {
+	let player = mv.piece.color().expect("move without piece");
+	let others = board
+		.legal_moves(player)
+		.into_iter()
+		.filter(|other| other.piece == mv.piece && other.to == mv.to && other.from != mv.from)
+		.collect::<SmVec<_>>();
+
+	let from = mv.from.to_string();
+	if others.is_empty() {
+		String::new()
+	} else if others.iter().all(|other| other.from.col() != mv.from.col()) {
+		from[..1].to_owned()
+	} else if others.iter().all(|other| other.from.row() != mv.from.row()) {
+		from[1..].to_owned()
+	} else {
+		from
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::super::{fen::STARTING_FEN, perft::PERFT_SUITE};
+	use super::*;
+
+	/// SAN of all legal moves in position `fen`.
+	fn all_san(fen: &str) -> Vec<String> {
+		let p = Position::from_fen(fen).unwrap();
+		p.legal_moves().iter().map(|mv| mv.to_san(p.board())).collect()
+	}
+
+	fn san(fen: &str, mv: &str) -> String {
+		let p = Position::from_fen(fen).unwrap();
+		let mv = p.legal_moves().into_iter().find(|m| m.to_string() == mv).unwrap();
+		mv.to_san(p.board())
+	}
+
+	#[test]
+	fn to_san() {
+		let mut start = all_san(STARTING_FEN);
+		start.sort();
+		assert_eq!(
+			start,
+			["Na3", "Nc3", "Nf3", "Nh3", "a3", "a4", "b3", "b4", "c3", "c4", "d3", "d4", "e3", "e4", "f3", "f4", "g3", "g4", "h3", "h4"]
+		);
+
+		let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+		assert_eq!(san(kiwipete, "e1g1"), "O-O");
+		assert_eq!(san(kiwipete, "e1c1"), "O-O-O");
+		assert_eq!(san(kiwipete, "d5e6"), "dxe6");
+		assert_eq!(san(kiwipete, "e5f7"), "Nxf7");
+		assert_eq!(san(kiwipete, "g2h3"), "gxh3");
+		assert_eq!(san(kiwipete, "c3b1"), "Nb1");
+		assert_eq!(san(kiwipete, "a1b1"), "Rb1");
+
+		// en passant, promotion, check.
+		assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+		assert_eq!(san("7k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8q"), "e8=Q+");
+		assert_eq!(san("7k/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8n"), "e8=N");
+		assert_eq!(san("4k3/8/8/8/8/8/1p6/R3K3 b - - 0 1", "b2a1r"), "bxa1=R+");
+
+		// mate.
+		let mate = "2rkr3/2p1p3/8/8/8/6K1/8/R2n3R w - - 0 1";
+		assert_eq!(san(mate, "a1d1"), "Raxd1#");
+		assert_eq!(san(mate, "h1d1"), "Rhxd1#");
+	}
+
+	#[test]
+	fn disambiguation() {
+		let knights = "7k/8/8/8/8/1N6/8/1N5K w - - 0 1";
+		assert_eq!(san(knights, "b1d2"), "N1d2");
+		assert_eq!(san(knights, "b3d2"), "N3d2");
+		assert_eq!(san(knights, "b3d4"), "Nd4");
+
+		let queens = "8/8/8/7k/8/Q1Q5/8/Q1Q4K w - - 0 1";
+		assert_eq!(san(queens, "a1b2"), "Qa1b2");
+		assert_eq!(san(queens, "c3b2"), "Qc3b2");
+		assert_eq!(san(queens, "a3b3"), "Qab3");
+		assert_eq!(san(queens, "c1b1"), "Qcb1");
+	}
+
+	#[test]
+	fn parse_san() {
+		let p = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+		let parse = |san| p.parse_san(san).map(|mv| mv.to_string());
+		assert_eq!(parse("O-O").unwrap(), "e1g1");
+		assert_eq!(parse("0-0-0").unwrap(), "e1c1");
+		assert_eq!(parse("Nxf7+").unwrap(), "e5f7");
+		assert_eq!(parse("Nf7").unwrap(), "e5f7");
+		assert_eq!(parse("Ne5xf7").unwrap(), "e5f7");
+		assert_eq!(parse("dxe6!?").unwrap(), "d5e6");
+		assert_eq!(parse("de6").unwrap(), "d5e6");
+		assert_eq!(parse("a4").unwrap(), "a2a4");
+
+		let black = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1").unwrap();
+		assert_eq!(black.parse_san("bxa3 e.p.").unwrap().to_string(), "b4a3");
+		assert_eq!(black.parse_san("O-O").unwrap().to_string(), "e8g8");
+		assert_eq!(black.parse_san("Bxe2").unwrap().to_string(), "a6e2");
+
+		let promotion = Position::from_fen("4k3/8/8/8/8/8/1p6/R3K3 b - - 0 1").unwrap();
+		assert_eq!(promotion.parse_san("bxa1=Q").unwrap().to_string(), "b2a1q");
+		assert_eq!(promotion.parse_san("bxa1N").unwrap().to_string(), "b2a1n");
+		assert!(promotion.parse_san("bxa1").is_err());
+
+		for invalid in ["", "x", "Nf9", "Zf3", "Kxe8", "Qh6", "N5f3", "e2e4", "exd5"] {
+			assert!(parse(invalid).is_err(), "{}", invalid);
+		}
+
+		let knights = Position::from_fen("7k/8/8/8/8/1N6/8/1N5K w - - 0 1").unwrap();
+		assert!(knights.parse_san("Nd2").unwrap_err().to_string().contains("ambiguous"));
+		assert_eq!(knights.parse_san("N1d2").unwrap().to_string(), "b1d2");
+		assert_eq!(knights.parse_san("Nbd4").unwrap().to_string(), "b3d4");
+	}
+
+	#[test]
+	fn round_trip() {
+		for (name, fen, _) in PERFT_SUITE {
+			let p = Position::from_fen(fen).unwrap();
+			for mv in p.legal_moves() {
+				let next = p.with_move(mv);
+				for reply in next.legal_moves() {
+					let san = reply.to_san(next.board());
+					assert_eq!(next.parse_san(&san).unwrap(), reply, "{}: {} {}", name, mv, san);
+				}
+			}
+		}
+	}
+}
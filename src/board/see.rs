@@ -0,0 +1,162 @@
+use super::internal::*;
+use Color::*;
+use Square::*;
+
+/// Piece values for `Board::see`, in pawns, by piece kind (Pawn, Rook, Knight, Bishop, Queen, King).
+/// The king is worth more than everything else together, so that it never captures onto a defended square.
+const SEE_VALUE: [i32; 6] = [1, 5, 3, 3, 9, 100];
+
+/// Piece kinds from least to most valuable, as offsets from `WPawn` (or `BPawn`).
+const LEAST_VALUABLE_FIRST: [usize; 6] = [0, 2, 3, 1, 4, 5];
+
+#[inline]
+fn see_value(piece: Square) -> i32 {
+	match piece.color() {
+		Some(color) => SEE_VALUE[piece.index() - color.pawn().index()],
+		None => 0,
+	}
+}
+
+impl Board {
+	/// Static exchange evaluation (https://www.chessprogramming.org/Static_Exchange_Evaluation):
+	/// pawns won by `mv` (usually a capture) once both sides have recaptured on its target square
+	/// for as long as it pays off, least valuable piece first.
+	///
+	/// Sliders lined up behind each other (x-rays) take part as soon as the piece in front of them has captured.
+	/// Pins and checks elsewhere on the board are not taken into account.
+	pub fn see(&self, mv: Move) -> i32 {
+		let player = mv.piece.color().expect("see: move without piece");
+		let to = mv.to.index() as u8;
+		let from = 1 << mv.from.index();
+		let mut occupied = !self.empty() ^ from;
+
+		let mut gain = [0; 32];
+		gain[0] = match self.at(mv.to) {
+			Empty if mv.piece.is_pawn() && mv.from.col() != mv.to.col() => {
+				// en passant: the captured pawn is not on the target square.
+				occupied &= !match player {
+					White => sh_s(1 << to),
+					Black => sh_n(1 << to),
+				};
+				SEE_VALUE[0]
+			}
+			victim => see_value(victim),
+		};
+		let mut on_square = mv.piece;
+		if let Some(promotion) = mv.promotion {
+			gain[0] += see_value(promotion) - SEE_VALUE[0];
+			on_square = promotion;
+		}
+
+		let mut side = player.opposite();
+		let mut d = 0;
+		loop {
+			d += 1;
+			// what `side` wins by taking, if the other side does not recapture.
+			gain[d] = see_value(on_square) - gain[d - 1];
+			if d == gain.len() - 1 {
+				break;
+			}
+			match self.least_valuable_attacker(to, occupied, side) {
+				None => break,
+				Some((piece, bit)) => {
+					occupied ^= bit;
+					on_square = piece;
+					side = side.opposite();
+				}
+			}
+		}
+
+		// each side may stop capturing when that is better.
+		while d > 1 {
+			d -= 1;
+			gain[d - 1] = -i32::max(-gain[d - 1], gain[d]);
+		}
+		gain[0]
+	}
+
+	/// Pieces of both colors attacking square index `i`, on a board where only `occupied` squares hold pieces.
+	pub fn attackers_to(&self, i: u8, occupied: u64) -> u64 {
+		let bit = 1 << i;
+		let rooks = self.bits(WRook) | self.bits(BRook) | self.bits(WQueen) | self.bits(BQueen);
+		let bisshops = self.bits(WBisshop) | self.bits(BBisshop) | self.bits(WQueen) | self.bits(BQueen);
+		let attackers = (rook_attacks(i, occupied) & rooks)
+			| (bisshop_attacks(i, occupied) & bisshops)
+			| (self.knight_vector(bit) & (self.bits(WKnight) | self.bits(BKnight)))
+			| (self.king_vector(bit) & (self.bits(WKing) | self.bits(BKing)))
+			| ((sh_sw(bit) | sh_se(bit)) & self.bits(WPawn))
+			| ((sh_nw(bit) | sh_ne(bit)) & self.bits(BPawn));
+		attackers & occupied
+	}
+
+	/// The least valuable piece of `player` attacking square index `i`, and its square as a bitmask.
+	fn least_valuable_attacker(&self, i: u8, occupied: u64, player: Color) -> Option<(Square, u64)> {
+		let attackers = self.attackers_to(i, occupied) & self.all_pieces(player);
+		if attackers == 0 {
+			return None;
+		}
+		let pawn = player.pawn().index();
+		LEAST_VALUABLE_FIRST.iter().find_map(|&kind| {
+			let piece = Square::ALL_SQUARES[pawn + kind];
+			let bits = attackers & self.bits(piece);
+			(bits != 0).then(|| (piece, bits & bits.wrapping_neg()))
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn see(fen: &str, mv: &str) -> i32 {
+		let position = Position::from_fen(fen).unwrap();
+		let mv = position.parse_san(mv).unwrap();
+		position.board().see(mv)
+	}
+
+	#[test]
+	fn undefended_and_defended() {
+		// https://www.chessprogramming.org/SEE_-_The_Swap_Algorithm
+		assert_eq!(see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "Rxe5"), 1);
+		assert_eq!(see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "Nxe5"), -2);
+
+		// a queen taking a pawn defended by a pawn.
+		assert_eq!(see("4k3/5p2/4p3/8/8/8/4Q3/4K3 w - - 0 1", "Qxe6"), -8);
+		// a pawn taking a defended queen.
+		assert_eq!(see("4k3/8/4r3/4q3/3P4/8/8/4K3 w - - 0 1", "dxe5"), 8);
+		// not a capture.
+		assert_eq!(see("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "Ra5"), 0);
+		assert_eq!(see("4k3/r7/8/8/8/8/8/R3K3 w - - 0 1", "Ra5"), -5);
+	}
+
+	#[test]
+	fn x_rays() {
+		// the second rook joins in once the first has captured.
+		assert_eq!(see("4r1k1/8/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "R2xe5"), 1);
+		assert_eq!(see("4r1k1/8/8/4p3/8/8/4R3/6K1 w - - 0 1", "Rxe5"), -4);
+		// so does a queen behind a bishop, on both sides.
+		assert_eq!(see("6k1/8/8/3p4/4p3/8/6B1/K6Q w - - 0 1", "Bxe4"), -1);
+		assert_eq!(see("6k1/1q6/2b5/3p4/4p3/8/6B1/K6Q w - - 0 1", "Bxe4"), -2);
+	}
+
+	#[test]
+	fn stop_capturing() {
+		assert_eq!(see("3qk3/8/8/3p4/8/8/8/3RK3 w - - 0 1", "Rxd5"), -4);
+		// with the queen behind the rook, black would rather not take back.
+		assert_eq!(see("3qk3/8/8/3p4/8/8/3R4/3QK3 w - - 0 1", "Rxd5"), 1);
+
+		// the king only takes undefended pieces (taking a defended one is not even legal).
+		assert_eq!(see("4k3/8/8/8/8/8/3n4/4K3 w - - 0 1", "Kxd2"), 3);
+		let board = Position::from_fen("4k3/8/8/8/8/1n6/3n4/4K3 w - - 0 1").unwrap().board().clone();
+		assert_eq!(board.see(Move::from_str("Ke1d2").unwrap()), -97);
+	}
+
+	#[test]
+	fn en_passant_and_promotion() {
+		assert_eq!(see("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"), 1);
+		assert_eq!(see("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"), 0);
+		assert_eq!(see("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a8=Q"), 8);
+		assert_eq!(see("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "bxa8=Q"), 13);
+		assert_eq!(see("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a8=Q"), -1);
+	}
+}
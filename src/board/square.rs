@@ -3,8 +3,9 @@ use std::{convert::TryFrom, fmt::Write};
 use super::internal::*;
 
 #[repr(u8)]
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord, Default)]
 pub enum Square {
+	#[default]
 	Empty = 0,
 
 	WPawn = 1,
@@ -70,10 +71,15 @@ impl Square {
 	}
 
 	pub fn is_king(self) -> bool {
-		match self {
-			WKing | BKing => true,
-			_ => false,
-		}
+		matches!(self, WKing | BKing)
+	}
+
+	pub fn is_pawn(self) -> bool {
+		matches!(self, WPawn | BPawn)
+	}
+
+	pub fn is_queen(self) -> bool {
+		matches!(self, WQueen | BQueen)
 	}
 
 	pub fn unicode(self) -> char {
@@ -94,9 +100,9 @@ impl fmt::Display for Square {
 	}
 }
 
-impl Into<char> for Square {
-	fn into(self) -> char {
-		Self::ASCII[self.index()]
+impl From<Square> for char {
+	fn from(sq: Square) -> char {
+		Square::ASCII[sq.index()]
 	}
 }
 
@@ -123,9 +129,3 @@ impl TryFrom<char> for Square {
 		})
 	}
 }
-
-impl Default for Square {
-	fn default() -> Self {
-		Square::Empty
-	}
-}
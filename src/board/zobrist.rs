@@ -0,0 +1,184 @@
+use super::internal::*;
+use Color::*;
+
+/// Random keys for Zobrist hashing (https://www.chessprogramming.org/Zobrist_Hashing).
+///
+/// A position's key is the xor of the keys of everything in it:
+/// each piece on its square, the castling rights, the en-passant column and the side to move.
+/// Playing a move then only needs to xor out what changed and xor in the new state.
+struct Keys {
+	/// By piece and square index. Empty squares have key 0.
+	pieces: [[u64; 64]; 13],
+	/// By castling rights bits.
+	castling: [u64; 16],
+	/// By column of the en-passant target square.
+	en_passant: [u64; 8],
+	/// Xor-ed in when Black is to move.
+	black: u64,
+}
+
+static KEYS: Keys = Keys::new(0x_5EED_CAFE_F00D_0001);
+
+impl Keys {
+	/// Pseudo-random keys from `seed` (SplitMix64), computed at compile time.
+	const fn new(seed: u64) -> Self {
+		let mut state = seed;
+		let mut keys = Self {
+			pieces: [[0; 64]; 13],
+			castling: [0; 16],
+			en_passant: [0; 8],
+			black: 0,
+		};
+
+		let mut piece = 1; // Empty keeps key 0
+		while piece < 13 {
+			let mut i = 0;
+			while i < 64 {
+				keys.pieces[piece][i] = split_mix(&mut state);
+				i += 1;
+			}
+			piece += 1;
+		}
+
+		// castling keys combine the keys of the individual rights,
+		// so that losing one right is a single xor.
+		let single = [split_mix(&mut state), split_mix(&mut state), split_mix(&mut state), split_mix(&mut state)];
+		let mut rights = 0;
+		while rights < 16 {
+			let mut bit = 0;
+			while bit < 4 {
+				if rights & (1 << bit) != 0 {
+					keys.castling[rights] ^= single[bit];
+				}
+				bit += 1;
+			}
+			rights += 1;
+		}
+
+		let mut col = 0;
+		while col < 8 {
+			keys.en_passant[col] = split_mix(&mut state);
+			col += 1;
+		}
+
+		keys.black = split_mix(&mut state);
+		keys
+	}
+}
+
+const fn split_mix(state: &mut u64) -> u64 {
+	*state = state.wrapping_add(0x_9E37_79B9_7F4A_7C15);
+	let mut z = *state;
+	z = (z ^ (z >> 30)).wrapping_mul(0x_BF58_476D_1CE4_E5B9);
+	z = (z ^ (z >> 27)).wrapping_mul(0x_94D0_49BB_1331_11EB);
+	z ^ (z >> 31)
+}
+
+/// Key for `piece` on square index `i` (0 for Empty).
+#[inline]
+pub(super) fn piece_key(piece: Square, i: usize) -> u64 {
+	KEYS.pieces[piece.index()][i]
+}
+
+/// Key for `piece` on square index `i` if it is a pawn, 0 otherwise (see `Board::pawn_zobrist`).
+#[inline]
+pub(super) fn pawn_key(piece: Square, i: usize) -> u64 {
+	match piece.is_pawn() {
+		true => piece_key(piece, i),
+		false => 0,
+	}
+}
+
+#[inline]
+pub(super) fn castling_key(castling: Castling) -> u64 {
+	KEYS.castling[castling.bits() as usize]
+}
+
+/// Key for an en-passant target square given as a bitmask (0 for none).
+#[inline]
+pub(super) fn en_passant_key(en_passant: u64) -> u64 {
+	match en_passant {
+		0 => 0,
+		bits => KEYS.en_passant[bits.trailing_zeros() as usize % 8],
+	}
+}
+
+/// Key for the side to move (0 for White).
+#[inline]
+pub fn side_key(player: Color) -> u64 {
+	match player {
+		White => 0,
+		Black => KEYS.black,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn transpositions() {
+		let start = Position::starting_position();
+
+		// the knights go out and back: same position.
+		let mut p = start.clone();
+		for mv in ["Ng1f3", "ng8f6", "Nf3g1", "nf6g8"] {
+			p.play(Move::from_str(mv).unwrap());
+		}
+		assert_eq!(p.zobrist(), start.zobrist());
+		assert_eq!(p.board(), start.board());
+
+		// same pieces, different side to move.
+		let board = start
+			.board()
+			.with_move(Move::from_str("Ng1f3").unwrap())
+			.with_move(Move::from_str("Nf3g1").unwrap());
+		assert_eq!(board.zobrist(), start.board().zobrist());
+		assert_ne!(Position::new(board, Black).zobrist(), start.zobrist());
+
+		// different move order, same position.
+		let a = Position::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+		let mut b = start.clone();
+		for mv in ["Ng1f3", "nb8c6", "Pe2e4", "pe7e5"] {
+			b.play(Move::from_str(mv).unwrap());
+		}
+		assert_eq!(b.zobrist(), a.zobrist()); // no en-passant square: no pawn can capture on e6
+	}
+
+	#[test]
+	fn castling_and_en_passant() {
+		let fens = [
+			"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+			"r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1",
+			"r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1",
+			"r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+			"4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+			"4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1",
+		];
+		let keys = fens.iter().map(|fen| Position::from_fen(fen).unwrap().zobrist()).collect::<Set<_>>();
+		assert_eq!(keys.len(), fens.len());
+	}
+
+	#[test]
+	fn pawn_keys() {
+		let start = Position::starting_position();
+		let pawns = start.board().pawn_zobrist();
+		assert_ne!(pawns, 0);
+		assert_eq!(Board::new().pawn_zobrist(), 0);
+
+		// pieces other than pawns do not matter.
+		let board = start.board().with_move(Move::from_str("Ng1f3").unwrap());
+		assert_eq!(board.pawn_zobrist(), pawns);
+		assert_eq!(
+			Position::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1")
+				.unwrap()
+				.board()
+				.pawn_zobrist(),
+			pawns
+		);
+
+		let board = board.with_move(Move::from_str("pe7e5").unwrap());
+		assert_ne!(board.pawn_zobrist(), pawns);
+		assert_ne!(board.pawn_zobrist(), board.zobrist());
+	}
+}
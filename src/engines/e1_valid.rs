@@ -1,15 +1,25 @@
 use super::internal::*;
+use std::sync::atomic::AtomicBool;
 
 /// Just returns valid moves, all valuated at 0.
 /// Results in random play.
 pub struct Valid();
 
 impl Engine for Valid {
-	fn eval_moves(&self, board: &Board, player: Color) -> SmVec<(Move, i32)> {
-		board
-			.iter_moves(player)
-			.filter(|&mv| !board.with_move(mv).is_check(player))
-			.map(|mv| (mv, 0))
-			.collect()
+	fn search(&self, position: &Position, limits: &SearchLimits, _: Option<&AtomicBool>, progress: &mut dyn FnMut(&SearchResult)) -> SearchResult {
+		let search = Search::with_limits(&zero, &NoTable, limits);
+		search.run(
+			position,
+			1,
+			|root, _| {
+				root.iter()
+					.map(|_| {
+						search.visit();
+						0
+					})
+					.collect()
+			},
+			progress,
+		)
 	}
 }
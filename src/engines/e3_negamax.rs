@@ -1,4 +1,5 @@
 use super::internal::*;
+use std::sync::atomic::AtomicBool;
 
 pub struct NegaMax<F: Fn(&Board, Color) -> i32> {
 	depth: u32,
@@ -6,13 +7,16 @@ pub struct NegaMax<F: Fn(&Board, Color) -> i32> {
 }
 
 impl<F: Fn(&Board, Color) -> i32> Engine for NegaMax<F> {
-	fn eval_moves(&self, board: &Board, player: Color) -> SmVec<(Move, i32)> {
-		board
-			.iter_moves(player)
-			.map(|mv| (mv, board.with_move(mv)))
-			.filter(|(_, board)| !board.is_check(player))
-			.map(|(mv, board)| (mv, self.negamax(&board, player, self.depth)))
-			.collect()
+	fn search(&self, position: &Position, limits: &SearchLimits, stop: Option<&AtomicBool>, progress: &mut dyn FnMut(&SearchResult)) -> SearchResult {
+		let player = position.player();
+		let limits = SearchLimits::depth(self.depth + 1).both(limits);
+		let search = Search::with_limits(&self.leaf_value, &NoTable, &limits).with_stop_signal(stop);
+		search.run(
+			position,
+			limits.max_depth(),
+			|root, depth| root.iter().map(|(_, board)| self.negamax(&search, board, player, depth)).collect(),
+			progress,
+		)
 	}
 }
 
@@ -20,18 +24,22 @@ impl<F: Fn(&Board, Color) -> i32> NegaMax<F> {
 	pub fn new(depth: u32, leaf_value: F) -> Self {
 		Self { depth, leaf_value }
 	}
-	fn negamax(&self, board: &Board, has_played: Color, depth: u32) -> i32 {
+	fn negamax(&self, search: &Search<F, NoTable>, board: &Board, has_played: Color, depth: u32) -> i32 {
+		let out_of_budget = search.visit();
 		if depth == 0 {
 			(self.leaf_value)(board, has_played)
+		} else if out_of_budget {
+			0 // thrown away
 		} else {
 			let will_play = has_played.opposite();
 			-board
-				.iter_moves(will_play)
-				.map(|mv| board.with_move(mv))
-				.filter(|board| !board.is_check(will_play))
-				.map(|board| self.negamax(&board, will_play, depth - 1))
+				.iter_legal_moves(will_play)
+				.map(|mv| self.negamax(search, &board.with_move(mv), will_play, depth - 1))
 				.max()
-				.unwrap_or(-INF)
+				.unwrap_or(match board.is_check(will_play) {
+					true => -INF,  // mate
+					false => DRAW, // stalemate
+				})
 		}
 	}
 }
@@ -1,33 +1,64 @@
 use super::internal::*;
+use std::sync::atomic::AtomicBool;
 
 pub struct AlphaBeta<F: Fn(&Board, Color) -> i32> {
-	depth: u32,
+	limits: SearchLimits,
+	options: SearchOptions,
 	leaf_value: F,
+	tt: TranspositionTable,
 }
+
+/// Default transposition table size, in MB.
+pub const DEFAULT_TT_MB: usize = 16;
+
 impl<F: Fn(&Board, Color) -> i32> AlphaBeta<F> {
+	/// Searches `depth` plies below each move.
 	pub fn new(depth: u32, leaf_value: F) -> Self {
-		Self { leaf_value, depth }
+		Self::with_tt_mb(depth, leaf_value, DEFAULT_TT_MB)
+	}
+
+	/// With a transposition table of `tt_mb` megabytes.
+	pub fn with_tt_mb(depth: u32, leaf_value: F, tt_mb: usize) -> Self {
+		Self {
+			leaf_value,
+			limits: SearchLimits::depth(depth + 1),
+			options: SearchOptions::default(),
+			tt: TranspositionTable::new(tt_mb),
+		}
+	}
+
+	/// Search deeper and deeper until one of `limits` is hit, instead of to a fixed depth.
+	pub fn with_limits(self, limits: SearchLimits) -> Self {
+		Self { limits, ..self }
+	}
+
+	pub fn with_options(self, options: SearchOptions) -> Self {
+		Self { options, ..self }
 	}
 }
 
 impl<F: Fn(&Board, Color) -> i32> Engine for AlphaBeta<F> {
-	fn eval_moves(&self, board: &Board, player: Color) -> SmVec<(Move, i32)> {
-		let mv_board = board
-			.iter_moves(player)
-			.map(|mv| (mv, board.with_move(mv)))
-			.filter(|(_, board)| !board.is_check(player))
-			.collect::<SmVec<_>>();
-
-		if mv_board.is_empty() {
-			return SmVec::new();
-		}
+	fn search(&self, position: &Position, limits: &SearchLimits, stop: Option<&AtomicBool>, progress: &mut dyn FnMut(&SearchResult)) -> SearchResult {
+		let player = position.player();
+		let limits = self.limits.both(limits);
+		let search = Search::with_limits(&self.leaf_value, &self.tt, &limits)
+			.with_options(self.options)
+			.with_stop_signal(stop)
+			.with_history(position);
+		search.run(
+			position,
+			limits.max_depth(),
+			|root, depth| {
+				root.iter()
+					.map(|(_, board)| -search.alphabeta(board, player.opposite(), -INF, INF, depth, 1).1)
+					.collect()
+			},
+			progress,
+		)
+	}
 
-		let mut result = SmVec::new();
-		for (mv, board) in mv_board {
-			let value = -alphabeta(&board, player.opposite(), &self.leaf_value, self.depth);
-			result.push((mv, value));
-		}
-		result
+	fn new_game(&self) {
+		self.tt.clear();
 	}
 }
 
@@ -41,66 +72,12 @@ where
 	alphabeta_(board, player, leaf_eval, -INF, INF, depth).1
 }
 
+/// Alpha-beta search without transposition table, returns the best move (if any) and its value.
 pub fn alphabeta_<F>(board: &Board, player: Color, leaf_eval: &F, alpha: i32, beta: i32, depth: u32) -> (Option<Move>, i32)
 where
 	F: Fn(&Board, Color) -> i32,
 {
-	// must stop iteration so that we would not trade a king for a king :-)
-	if !board.has_king(player) {
-		return (None, -INF + (depth as i32));
-	}
-	//if !board.has_king(player.opposite()) {
-	//	unreachable!();
-	//	return (None, INF - (depth as i32));
-	//}
-
-	if depth == 0 {
-		return (None, leaf_eval(board, player));
-	}
-
-	let mut mv_boards = board //
-		.collect_moves(player)
-		.iter()
-		.map(|&mv| (mv, board.with_move(mv)))
-		.collect::<Vec<_>>();
-
-	// sorting moves most promising first
-	// results in massively better alpha-beta pruning
-	// but is only worth the cost at least two levels above leaf.
-	if depth > 1 {
-		let mut mv_board_value = mv_boards
-			.into_iter()
-			.map(|(mv, board)| {
-				let value = leaf_eval(&board, player);
-				(mv, board, value)
-			})
-			.collect::<SmVec<_>>();
-		mv_board_value.sort_by_key(|(_, _, v)| *v);
-		mv_boards = mv_board_value.into_iter().map(|(mv, board, _)| (mv, board)).collect();
-	}
-
-	let mut best_value = -INF - (depth as i32);
-	let mut best_move = None;
-	let mut alpha = alpha;
-	for (mv, board) in mv_boards {
-		// TODO: filter out bad moves at board level.
-		if board.is_check(player) {
-			continue;
-		}
-
-		let (_, value) = alphabeta_(&board, player.opposite(), leaf_eval, -beta, -alpha, depth - 1);
-		let value = -value;
-		if value >= best_value {
-			best_value = value;
-			best_move = Some(mv);
-		}
-
-		alpha = i32::max(alpha, value);
-		if alpha >= beta {
-			break;
-		}
-	}
-	(best_move, best_value)
+	Search::new(leaf_eval, &NoTable).alphabeta(board, player, alpha, beta, depth, 0)
 }
 
 #[cfg(test)]
@@ -215,6 +192,24 @@ mod test {
 		debug_assert_eq!(alphabeta(&board, White, &material, 4), -8);
 	}
 
+	#[test]
+	fn transposition_table() {
+		// Searching increasing depths up to 3, an entry is never used where less depth remains than it was searched with,
+		// so the table must not change any values.
+		let tt = TranspositionTable::new(1);
+		for fen in [STARTING_FEN, "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"] {
+			let board = Board::from_fen(fen).unwrap();
+			for depth in 1..=3 {
+				for player in [White, Black] {
+					let want = alphabeta(&board, player, &material, depth);
+					let have = Search::new(&material, &tt).alphabeta(&board, player, -INF, INF, depth, 0).1;
+					assert_eq!(have, want, "{} {} depth {}", fen, player, depth);
+					assert!(tt.probe(board.zobrist_for(player)).is_some());
+				}
+			}
+		}
+	}
+
 	fn board(board: &str) -> Board {
 		Board::from_str(board).unwrap()
 	}
@@ -1,112 +1,74 @@
 use super::internal::*;
 use rayon::prelude::*;
+use std::sync::atomic::AtomicBool;
 
 /// Parallel alpha-beta
 pub struct ParAlphaBeta<F: Fn(&Board, Color) -> i32 + Sync> {
-	depth: u32,
+	limits: SearchLimits,
+	options: SearchOptions,
 	leaf_value: F,
+	tt: SharedTranspositionTable,
 }
 impl<F: Fn(&Board, Color) -> i32 + Sync> ParAlphaBeta<F> {
+	/// Searches `depth` plies below each move.
 	pub fn new(depth: u32, leaf_value: F) -> Self {
-		Self { leaf_value, depth }
+		Self::with_tt_mb(depth, leaf_value, DEFAULT_TT_MB)
 	}
-}
 
-impl<F: Fn(&Board, Color) -> i32 + Sync> Engine for ParAlphaBeta<F> {
-	fn eval_moves(&self, board: &Board, player: Color) -> SmVec<(Move, i32)> {
-		board
-			.iter_moves(player)
-			.map(|mv| (mv, board.with_move(mv)))
-			.filter(|(_, board)| !board.is_check(player))
-			.collect::<Vec<_>>()
-			.par_iter()
-			.map(|(mv, board)| (*mv, -alphabeta(&board, player.opposite(), &self.leaf_value, self.depth)))
-			.collect::<Vec<_>>()
-			.into_iter()
-			.collect::<SmVec<_>>()
+	/// With a transposition table of `tt_mb` megabytes, shared by all threads.
+	pub fn with_tt_mb(depth: u32, leaf_value: F, tt_mb: usize) -> Self {
+		Self {
+			leaf_value,
+			limits: SearchLimits::depth(depth + 1),
+			options: SearchOptions::default(),
+			tt: SharedTranspositionTable::new(tt_mb),
+		}
 	}
-}
-
-/// How good is board for player?
-/// Good scores are always positive, bad scores always negative,
-/// regardless of player color.
-pub fn alphabeta<F>(board: &Board, player: Color, leaf_eval: &F, depth: u32) -> i32
-where
-	F: Fn(&Board, Color) -> i32,
-{
-	alphabeta_(board, player, leaf_eval, -INF, INF, depth).1
-}
 
-pub fn alphabeta_<F>(board: &Board, player: Color, leaf_eval: &F, alpha: i32, beta: i32, depth: u32) -> (Option<Move>, i32)
-where
-	F: Fn(&Board, Color) -> i32,
-{
-	// must stop iteration so that we would not trade a king for a king :-)
-	if !board.has_king(player) {
-		return (None, -INF + (depth as i32));
+	/// Search deeper and deeper until one of `limits` is hit, instead of to a fixed depth.
+	pub fn with_limits(self, limits: SearchLimits) -> Self {
+		Self { limits, ..self }
 	}
-	// Not possible if we make no illegal moves:
-	// if !board.has_king(player.opposite()) {
-	// 	unreachable!();
-	// }
 
-	if depth == 0 {
-		return (None, leaf_eval(board, player));
+	pub fn with_options(self, options: SearchOptions) -> Self {
+		Self { options, ..self }
 	}
+}
 
-	let mut mv_boards = board //
-		.collect_moves(player)
-		.iter()
-		.map(|&mv| (mv, board.with_move(mv)))
-		.collect::<Vec<_>>();
-
-	// sorting moves most promising first
-	// results in massively better alpha-beta pruning
-	// but is only worth the cost at least two levels above leaf.
-	if depth > 1 {
-		let mut mv_board_value = mv_boards
-			.into_iter()
-			.map(|(mv, board)| {
-				let value = leaf_eval(&board, player);
-				(mv, board, value)
-			})
-			.collect::<SmVec<_>>();
-		mv_board_value.sort_by_key(|(_, _, v)| *v);
-		mv_boards = mv_board_value.into_iter().map(|(mv, board, _)| (mv, board)).collect();
+impl<F: Fn(&Board, Color) -> i32 + Sync> Engine for ParAlphaBeta<F> {
+	fn search(&self, position: &Position, limits: &SearchLimits, stop: Option<&AtomicBool>, progress: &mut dyn FnMut(&SearchResult)) -> SearchResult {
+		let player = position.player();
+		let limits = self.limits.both(limits);
+		let search = Search::with_limits(&self.leaf_value, &self.tt, &limits)
+			.with_options(self.options)
+			.with_stop_signal(stop)
+			.with_history(position);
+		search.run(
+			position,
+			limits.max_depth(),
+			|root, depth| {
+				root.par_iter()
+					.map(|(_, board)| -search.alphabeta(board, player.opposite(), -INF, INF, depth, 1).1)
+					.collect::<Vec<_>>()
+					.into_iter()
+					.collect()
+			},
+			progress,
+		)
 	}
 
-	let mut best_value = -INF - (depth as i32);
-	let mut best_move = None;
-	let mut alpha = alpha;
-	for (mv, board) in mv_boards {
-		// TODO: filter out bad moves at board level.
-		if board.is_check(player) {
-			continue;
-		}
-
-		let (_, value) = alphabeta_(&board, player.opposite(), leaf_eval, -beta, -alpha, depth - 1);
-		let value = -value;
-		if value >= best_value {
-			best_value = value;
-			best_move = Some(mv);
-		}
-
-		alpha = i32::max(alpha, value);
-		if alpha >= beta {
-			break;
-		}
+	fn new_game(&self) {
+		self.tt.clear();
 	}
-	(best_move, best_value)
 }
 
 #[cfg(test)]
 mod test {
 	use super::*;
 
-	#[test]
-	fn test_alphabeta_mate() {
-		let board = board(
-			r"
+	const BOARDS: &[&str] = &[
+		// the black king can be taken
+		r"
 		. . . . R . . k
 		. . . . R . . .
 		. . . . . . . .
@@ -116,19 +78,8 @@ mod test {
 		. . . . . . . .
 		. . . . . . . K
 		",
-		);
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 0), 10);
-		debug_assert_eq!(alphabeta(&board, Black, &material, 0), -10);
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 1), INF);
-		//debug_assert_eq!(alphabeta(&board, Black, &material, 2), -INF+2);
-	}
-
-	#[test]
-	fn test_alpahbeta_1() {
-		let board = board(
-			r"
+		// a protected pawn
+		r"
 		. . . . . . . .
 		. . . . . p . .
 		. . . . p . . .
@@ -138,21 +89,8 @@ mod test {
 		. . . . . . . .
 		k . . . . . . K
 		",
-		);
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 0), 7);
-		debug_assert_eq!(alphabeta(&board, Black, &material, 0), -7);
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 1), 8); // white greedily takes a pawn
-		debug_assert_eq!(alphabeta(&board, Black, &material, 1), -7);
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 2), 7); // white sees that the pawn is protected
-	}
-
-	#[test]
-	fn test_alphabeta_2() {
-		let board = board(
-			r"
+		// pawns to take one by one, black cannot move
+		r"
 		Q . . p p p . k
 		. . . . . . . .
 		. . . . . . . .
@@ -162,28 +100,8 @@ mod test {
 		. . . . . . . .
 		. . . . . . . K
 		",
-		);
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 0), 6); // no moves
-		debug_assert_eq!(alphabeta(&board, Black, &material, 0), -6); // no moves
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 1), 7); // white: Qd8xp
-		debug_assert_eq!(alphabeta(&board, Black, &material, 1), -6); // black: no moves
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 2), 7); // white: Qd8xp,    black: no moves
-		debug_assert_eq!(alphabeta(&board, Black, &material, 2), -7); // black: no moves, white: Qd8xp
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 3), 8); // white: Qd8xp,   black: no moves, white: Qe8xp
-		debug_assert_eq!(alphabeta(&board, Black, &material, 3), -7); // black: no moves, white: Qd8xp,  black: no moves
-
-		debug_assert_eq!(alphabeta(&board, White, &material, 4), 8); // white: Qd8xp,   black: no moves, white: Qe8xp,    black: no moves
-		debug_assert_eq!(alphabeta(&board, Black, &material, 4), -8); // black: no moves, white: Qd8xp,  black: no moves, white: Qe8xp
-	}
-
-	#[test]
-	fn test_alphabeta_3() {
-		let board = board(
-			r"
+		// the same for black
+		r"
 		. . . . . . . k
 		. . . . . . . .
 		. . . . . . . .
@@ -193,25 +111,21 @@ mod test {
 		. . . . . . . .
 		q . . P P P . K
 		",
-		);
-
-		debug_assert_eq!(alphabeta(&board, Black, &material, 0), 6);
-		debug_assert_eq!(alphabeta(&board, White, &material, 0), -6);
-
-		debug_assert_eq!(alphabeta(&board, Black, &material, 1), 7);
-		debug_assert_eq!(alphabeta(&board, White, &material, 1), -6);
-
-		debug_assert_eq!(alphabeta(&board, Black, &material, 2), 7);
-		debug_assert_eq!(alphabeta(&board, White, &material, 2), -7);
+	];
 
-		debug_assert_eq!(alphabeta(&board, Black, &material, 3), 8);
-		debug_assert_eq!(alphabeta(&board, White, &material, 3), -7);
-
-		debug_assert_eq!(alphabeta(&board, Black, &material, 4), 8);
-		debug_assert_eq!(alphabeta(&board, White, &material, 4), -8);
-	}
-
-	fn board(board: &str) -> Board {
-		Board::from_str(board).unwrap()
+	#[test]
+	fn same_as_alphabeta() {
+		for diagram in BOARDS {
+			let board = Board::from_str(diagram).unwrap();
+			for player in [White, Black] {
+				let position = Position::new(board.clone(), player);
+				for depth in 0..=4 {
+					let want = AlphaBeta::new(depth, material).search(&position, &SearchLimits::none(), None, &mut |_| ());
+					let have = ParAlphaBeta::new(depth, material).search(&position, &SearchLimits::none(), None, &mut |_| ());
+					assert_eq!(have.root_moves, want.root_moves, "{}{:?} depth {}", diagram, player, depth);
+					assert_eq!(have.score, want.score, "{}{:?} depth {}", diagram, player, depth);
+				}
+			}
+		}
 	}
 }
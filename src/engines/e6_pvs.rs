@@ -0,0 +1,125 @@
+use super::internal::*;
+use std::sync::atomic::AtomicBool;
+
+/// Principal variation search (https://www.chessprogramming.org/Principal_Variation_Search)
+/// with aspiration windows at the root: alphabeta that expects the first move of each node to be the best,
+/// and only proves the others are no better, with null windows.
+///
+/// Only the best of the `root_moves` has an exact value, the others have upper bounds (`Bound::Upper`).
+pub struct Pvs<F: Fn(&Board, Color) -> i32> {
+	limits: SearchLimits,
+	options: SearchOptions,
+	leaf_value: F,
+	tt: TranspositionTable,
+	window: i32,
+}
+
+/// Default half width of the aspiration window, in centipawns.
+pub const DEFAULT_ASPIRATION_WINDOW: i32 = 50;
+
+impl<F: Fn(&Board, Color) -> i32> Pvs<F> {
+	/// Searches `depth` plies below each move.
+	pub fn new(depth: u32, leaf_value: F) -> Self {
+		Self::with_tt_mb(depth, leaf_value, DEFAULT_TT_MB)
+	}
+
+	/// With a transposition table of `tt_mb` megabytes.
+	pub fn with_tt_mb(depth: u32, leaf_value: F, tt_mb: usize) -> Self {
+		Self {
+			leaf_value,
+			limits: SearchLimits::depth(depth + 1),
+			options: SearchOptions::default(),
+			tt: TranspositionTable::new(tt_mb),
+			window: DEFAULT_ASPIRATION_WINDOW,
+		}
+	}
+
+	/// Search deeper and deeper until one of `limits` is hit, instead of to a fixed depth.
+	pub fn with_limits(self, limits: SearchLimits) -> Self {
+		Self { limits, ..self }
+	}
+
+	pub fn with_options(self, options: SearchOptions) -> Self {
+		Self { options, ..self }
+	}
+
+	/// Aspiration window of ± `centipawns` around the value of the previous iteration.
+	pub fn with_window(self, centipawns: i32) -> Self {
+		Self { window: centipawns, ..self }
+	}
+}
+
+impl<F: Fn(&Board, Color) -> i32> Engine for Pvs<F> {
+	fn search(&self, position: &Position, limits: &SearchLimits, stop: Option<&AtomicBool>, progress: &mut dyn FnMut(&SearchResult)) -> SearchResult {
+		let player = position.player();
+		let limits = self.limits.both(limits);
+		let search = Search::with_limits(&self.leaf_value, &self.tt, &limits)
+			.with_options(self.options.with_pvs())
+			.with_stop_signal(stop)
+			.with_history(position);
+		let window = self.window * self.options.pawn_value / 100;
+		let mut previous = None;
+		search.run(
+			position,
+			limits.max_depth(),
+			|root, depth| {
+				let values = search.aspiration(root, player, depth, previous, window);
+				previous = values.iter().copied().max();
+				values
+			},
+			progress,
+		)
+	}
+
+	fn new_game(&self) {
+		self.tt.clear();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn same_as_alphabeta() {
+		// the same best value as alphabeta, with any window.
+		for fen in [
+			STARTING_FEN,
+			"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+			"6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1",
+		] {
+			let position = Position::from_fen(fen).unwrap();
+			let want = AlphaBeta::new(3, material).search(&position, &SearchLimits::none(), None, &mut |_| ());
+			for window in [0, 100, 300, 10_000] {
+				let have = Pvs::new(3, material)
+					.with_window(window)
+					.search(&position, &SearchLimits::none(), None, &mut |_| ());
+				assert_eq!(have.score, want.score, "{} window {}", fen, window);
+				assert_eq!(have.score_of(have.best_move.unwrap()), Some(have.score));
+				// the other moves were only shown to be no better.
+				assert_eq!(
+					have.exact_root_moves().iter().map(|(mv, _)| *mv).collect::<Vec<_>>(),
+					have.best_move.into_iter().collect::<Vec<_>>()
+				);
+				assert_eq!(
+					have.root_moves.iter().filter(|(_, _, bound)| *bound == Bound::Upper).count(),
+					have.root_moves.len() - 1
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn stats() {
+		let position = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+		let result = Pvs::new(3, material)
+			.with_window(0)
+			.search(&position, &SearchLimits::none(), None, &mut |_| ());
+		// with the narrowest window, every change in value fails high or low.
+		assert!(result.stats.fail_highs + result.stats.fail_lows > 0, "{:?}", result.stats);
+		assert!(result.stats.researches >= result.stats.fail_highs + result.stats.fail_lows);
+
+		let result = AlphaBeta::new(3, material).search(&position, &SearchLimits::none(), None, &mut |_| ());
+		assert_eq!(result.stats, SearchStats::default());
+	}
+}
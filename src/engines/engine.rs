@@ -1,5 +1,187 @@
 use super::internal::*;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 
 pub trait Engine {
-	fn eval_moves(&self, board: &Board, player: Color) -> SmVec<(Move, i32)>;
+	/// Search the best move in `position`, until the engine's own limits or `limits` are hit
+	/// (whichever comes first), or `stop` is set.
+	/// After each completed iteration, `progress` gets the result so far.
+	fn search(&self, position: &Position, limits: &SearchLimits, stop: Option<&AtomicBool>, progress: &mut dyn FnMut(&SearchResult)) -> SearchResult;
+
+	/// Forget what earlier searches learned (e.g. the transposition table), so that the next search starts afresh.
+	fn new_game(&self) {}
+
+	/// Values of the legal moves for `player` that the engine knows exactly (at least the best one, see `SearchResult::root_moves`),
+	/// in move generation order, searching within the engine's own limits.
+	fn eval_moves(&self, board: &Board, player: Color) -> SmVec<(Move, i32)> {
+		let position = Position::new(board.clone(), player);
+		self.search(&position, &SearchLimits::none(), None, &mut |_| ()).exact_root_moves()
+	}
+}
+
+/// What a search found (so far).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchResult {
+	/// `None` if there are no legal moves.
+	pub best_move: Option<Move>,
+	/// Of the best move. Without legal moves: mate 0 or a draw.
+	pub score: Score,
+	/// Principal variation: the best move, the best reply, and so on.
+	pub pv: Vec<Move>,
+	/// Plies searched, counting the best move.
+	pub depth: u32,
+	/// Positions visited.
+	pub nodes: u64,
+	pub stats: SearchStats,
+	pub time: Duration,
+	/// Values of all legal moves, in move generation order, in the units of the engine's evaluation.
+	/// Not every engine proves exact values for moves that are not the best: the bound tells how a value relates to the true one
+	/// (e.g. principal variation search only shows that the other moves are no better than the best, `Bound::Upper`).
+	/// The best move's value is always exact.
+	pub root_moves: SmVec<(Move, i32, Bound)>,
+	/// What a pawn is worth in `root_moves`.
+	pub pawn_value: i32,
+}
+
+/// How often a search had to search again with a wider window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+	/// Root searches whose best value turned out at or above the aspiration window.
+	pub fail_highs: u64,
+	/// Root searches whose best value turned out at or below the aspiration window.
+	pub fail_lows: u64,
+	/// Searches done again with a wider window: after a failed aspiration window,
+	/// or a null window search in principal variation search that turned out better than expected.
+	pub researches: u64,
+}
+
+impl SearchResult {
+	/// Score of one of the `root_moves` (which may be a bound).
+	pub fn score_of(&self, mv: Move) -> Option<Score> {
+		self.root_moves
+			.iter()
+			.find(|(m, _, _)| *m == mv)
+			.map(|(_, value, _)| Score::new(*value, self.pawn_value))
+	}
+
+	/// The `root_moves` with exact values, to pick a move from: the others may be worse than their value suggests.
+	pub fn exact_root_moves(&self) -> SmVec<(Move, i32)> {
+		self.root_moves
+			.iter()
+			.filter(|(_, _, bound)| *bound == Bound::Exact)
+			.map(|&(mv, value, _)| (mv, value))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn all_engines() -> Vec<(&'static str, Box<dyn Engine>)> {
+		vec![
+			("valid", Box::new(Valid())),
+			("greedy", Box::new(Greedy(material))),
+			("negamax", Box::new(NegaMax::new(2, material))),
+			("alphabeta", Box::new(AlphaBeta::new(2, material))),
+			("palphabeta", Box::new(ParAlphaBeta::new(2, material))),
+			("pvs", Box::new(Pvs::new(2, material))),
+		]
+	}
+
+	#[test]
+	fn search_result() {
+		let position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+		for (name, engine) in all_engines() {
+			let mut iterations = vec![];
+			let result = engine.search(&position, &SearchLimits::none(), None, &mut |r| iterations.push(r.clone()));
+			let last = iterations.last().unwrap();
+			assert_eq!(
+				&SearchResult {
+					time: result.time,
+					..last.clone()
+				},
+				&result,
+				"{}",
+				name
+			);
+			assert_eq!(
+				iterations.iter().map(|r| r.depth).collect::<Vec<_>>(),
+				(1..=result.depth).collect::<Vec<_>>()
+			);
+			assert_eq!(result.root_moves.len(), position.legal_moves().len(), "{}", name);
+			assert_eq!(result.pv.first(), result.best_move.as_ref(), "{}", name);
+			// the best move's value is exact, whatever the others are.
+			let exact = result.exact_root_moves();
+			assert!(
+				equally_best_options(&exact).iter().any(|(mv, _)| Some(*mv) == result.best_move),
+				"{}",
+				name
+			);
+			if name != "pvs" {
+				assert_eq!(exact.len(), result.root_moves.len(), "{}", name);
+			}
+			assert!(result.nodes >= result.root_moves.len() as u64, "{}", name);
+			assert_eq!(engine.eval_moves(position.board(), position.player()), exact, "{}", name);
+		}
+	}
+
+	#[test]
+	fn new_game() {
+		// a search after `new_game` does not profit from the ones before it.
+		let position = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+		let search = |engine: &dyn Engine| engine.search(&position, &SearchLimits::none(), None, &mut |_| ()).nodes;
+		// (palphabeta's node count depends on the order its threads finish in)
+		for (name, engine) in all_engines().into_iter().filter(|(name, _)| *name != "palphabeta") {
+			let first = search(&*engine);
+			search(&*engine);
+			engine.new_game();
+			assert_eq!(search(&*engine), first, "{}", name);
+		}
+	}
+
+	#[test]
+	fn mate_in_one() {
+		let position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+		for (name, engine) in all_engines().into_iter().skip(2) {
+			let result = engine.search(&position, &SearchLimits::none(), None, &mut |_| ());
+			assert_eq!(result.best_move.map(|mv| mv.to_string()), Some("a1a8".into()), "{}", name);
+			assert_eq!(result.score_of(result.best_move.unwrap()), Some(result.score));
+		}
+
+		let result = AlphaBeta::new(3, material).search(&position, &SearchLimits::none(), None, &mut |_| ());
+		assert_eq!(result.score, Score::Mate(1));
+		assert_eq!(result.pv.len(), 1);
+	}
+
+	#[test]
+	fn no_moves() {
+		let mated = Position::from_fen("R5k1/5ppp/8/8/8/8/8/4K3 b - - 1 1").unwrap();
+		let stalemate = Position::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+		for (name, engine) in all_engines() {
+			let result = engine.search(&mated, &SearchLimits::none(), None, &mut |_| ());
+			assert_eq!((result.best_move, result.score), (None, Score::Mate(0)), "{}", name);
+			let result = engine.search(&stalemate, &SearchLimits::none(), None, &mut |_| ());
+			assert_eq!((result.best_move, result.score), (None, Score::Cp(0)), "{}", name);
+		}
+	}
+
+	#[test]
+	fn limits_and_stop() {
+		let position = Position::starting_position();
+		let engine = AlphaBeta::new(MAX_DEPTH, material);
+
+		let result = engine.search(&position, &SearchLimits::nodes(5000), None, &mut |_| ());
+		assert!(result.nodes < 6000, "{}", result.nodes);
+		assert!(result.best_move.is_some());
+
+		let result = engine.search(&position, &SearchLimits::depth(3), None, &mut |_| ());
+		assert_eq!(result.depth, 3);
+
+		// stopped before it started: only the first iteration.
+		let stop = AtomicBool::new(true);
+		let result = ParAlphaBeta::new(MAX_DEPTH, material).search(&position, &SearchLimits::none(), Some(&stop), &mut |_| ());
+		assert_eq!(result.depth, 1);
+		assert!(result.best_move.is_some());
+	}
 }
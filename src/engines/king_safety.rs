@@ -0,0 +1,197 @@
+use super::internal::*;
+use super::pawn_structure::Shift;
+
+/// Weights of the king safety terms, in centipawns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KingSafetyWeights {
+	/// Per enemy attack on a square of the king zone (the king and the squares around it),
+	/// by attacker: pawn, rook, knight, bishop, queen, king (the order of `Square`).
+	pub zone_attack: [Tapered; 6],
+	/// Per own pawn right in front of the king, on its own or a neighboring file.
+	pub shield: Tapered,
+	/// Per own pawn two rows in front of the king.
+	pub shield_far: Tapered,
+	/// Per enemy pawn up to four rows in front of the king, on its own or a neighboring file.
+	pub storm: Tapered,
+	/// Per file at or next to the king without own pawns, but with enemy pawns.
+	pub half_open_file: Tapered,
+	/// Per file at or next to the king without any pawns.
+	pub open_file: Tapered,
+}
+
+impl Default for KingSafetyWeights {
+	fn default() -> Self {
+		let t = Tapered::new;
+		Self {
+			zone_attack: [t(-4, 0), t(-10, -2), t(-8, -2), t(-8, -2), t(-15, -4), t(0, 0)],
+			shield: t(15, 0),
+			shield_far: t(8, 0),
+			storm: t(-10, 0),
+			half_open_file: t(-15, 0),
+			open_file: t(-25, -5),
+		}
+	}
+}
+
+/// King safety, to be added to other evaluation terms.
+/// Takes the attack maps as an argument so that value functions computing them anyway can share them.
+pub struct KingSafety {
+	weights: KingSafetyWeights,
+}
+
+impl Default for KingSafety {
+	fn default() -> Self {
+		Self::new(KingSafetyWeights::default())
+	}
+}
+
+impl KingSafety {
+	pub fn new(weights: KingSafetyWeights) -> Self {
+		Self { weights }
+	}
+
+	pub fn weights(&self) -> &KingSafetyWeights {
+		&self.weights
+	}
+
+	/// Safety of White's king minus that of Black's, from White's point of view.
+	pub fn eval(&self, board: &Board, attck: &AttacVector) -> Tapered {
+		self.eval1(board, attck, White) - self.eval1(board, attck, Black)
+	}
+
+	/// Safety of `player`'s king, higher is safer. 0 without a king.
+	pub fn eval1(&self, board: &Board, attck: &AttacVector, player: Color) -> Tapered {
+		let king = board.bits(player.king());
+		if king == 0 {
+			return Tapered::default();
+		}
+		let w = &self.weights;
+		let (fwd, fill_fwd): (Shift, Shift) = match player {
+			White => (sh_n, fill_n),
+			Black => (sh_s, fill_s),
+		};
+		let own_pawns = board.bits(player.pawn());
+		let enemy_pawns = board.bits(player.opposite().pawn());
+
+		// enemy attacks on the king zone, by attacker.
+		let zone = king | attck.bitfields[player.king().index()];
+		let enemy = player.opposite().pawn().index();
+		let attacks = (0..6)
+			.map(|kind| w.zone_attack[kind] * (attck.bitfields[enemy + kind] & zone).count_ones() as i32)
+			.fold(Tapered::default(), |a, b| a + b);
+
+		// pawns in front of the king, on its own and the neighboring files.
+		let files = king | sh_e(king) | sh_w(king);
+		let shield = own_pawns & fwd(files);
+		let shield_far = own_pawns & fwd(fwd(files));
+		let ahead = fill_fwd(fwd(files));
+		let storm = enemy_pawns & ahead & !fwd(fwd(fwd(fwd(ahead))));
+
+		let mut open_files = Tapered::default();
+		for col in iter_bitfield(files) {
+			let file = fill_n(1 << col) | fill_s(1 << col);
+			open_files += match (own_pawns & file, enemy_pawns & file) {
+				(0, 0) => w.open_file,
+				(0, _) => w.half_open_file,
+				_ => Tapered::default(),
+			};
+		}
+
+		attacks
+			+ w.shield * shield.count_ones() as i32
+			+ w.shield_far * shield_far.count_ones() as i32
+			+ w.storm * storm.count_ones() as i32
+			+ open_files
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn safety(board: &str, player: Color) -> Tapered {
+		let board = Board::from_str(board).unwrap();
+		KingSafety::default().eval1(&board, &board.attack_vectors(), player)
+	}
+
+	#[test]
+	fn shield_and_files() {
+		let w = KingSafetyWeights::default();
+		let castled = r"
+			. . . . . . k .
+			. . . . . p p p
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . P P P
+			. . . . . . K .
+			";
+		assert_eq!(safety(castled, White), w.shield * 3);
+		assert_eq!(safety(castled, Black), w.shield * 3);
+		let board = Board::from_str(castled).unwrap();
+		assert_eq!(KingSafety::default().eval(&board, &board.attack_vectors()), Tapered::default());
+
+		// g2-g4 leaves a half-open file, and a pawn two rows up.
+		let half_open = r"
+			. . . . . . k .
+			. . . . . p . p
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . P .
+			. . . . . P . P
+			. . . . . . K .
+			";
+		assert_eq!(safety(half_open, White), w.shield * 2 + w.shield_far);
+		assert_eq!(safety(half_open, Black), w.shield * 2 + w.half_open_file);
+
+		// without any pawns on the g file, it is open.
+		let open = r"
+			. . . . . . k .
+			. . . . . p . p
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . P . P
+			. . . . . . K .
+			";
+		assert_eq!(safety(open, White), w.shield * 2 + w.open_file);
+	}
+
+	#[test]
+	fn attacks_and_storm() {
+		let w = KingSafetyWeights::default();
+		let base = w.shield * 3;
+
+		// the queen attacks f2 and h2.
+		let attacked = r"
+			. . . . . . k .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . q
+			. . . . . . . .
+			. . . . . P P P
+			. . . . . . K .
+			";
+		assert_eq!(safety(attacked, White), base + w.zone_attack[4] * 2);
+
+		// pawns up to four rows in front of the king count as a storm.
+		let storm = r"
+			. . . . . . k .
+			. . . . . . . .
+			. . . . . . . p
+			. . . . . . p .
+			. . . . . p . .
+			. . . . . . . .
+			. . . . . P P P
+			. . . . . . K .
+			";
+		assert_eq!(safety(storm, White), base + w.storm * 2);
+
+		// no king, no safety.
+		assert_eq!(safety(&storm.replace('K', "."), White), Tapered::default());
+	}
+}
@@ -18,14 +18,38 @@ pub use e4_alphabeta::*;
 mod e5_par_alphabeta;
 pub use e5_par_alphabeta::*;
 
+mod e6_pvs;
+pub use e6_pvs::*;
+
+mod search;
+pub use search::*;
+
+mod move_ordering;
+pub use move_ordering::*;
+
+mod pruning;
+pub use pruning::*;
+
+mod search_limits;
+pub use search_limits::*;
+
+mod transposition_table;
+pub use transposition_table::*;
+
 mod value_functions;
 pub use value_functions::*;
 
+mod pawn_structure;
+pub use pawn_structure::*;
+
+mod king_safety;
+pub use king_safety::*;
+
 mod pick_move;
 pub use pick_move::*;
 
-mod parse_engine;
-pub use parse_engine::*;
+mod registry;
+pub use registry::*;
 
 /// Effectively infinity,
 /// but don't use MAX_I32 to stay away from wrap-around.
@@ -0,0 +1,326 @@
+use super::internal::*;
+use super::transposition_table::{pack_move, unpack_move};
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering::Relaxed};
+
+/// Killer moves kept per ply.
+const NUM_KILLERS: usize = 2;
+
+/// History scores stay within ±this: the more a score has grown, the less a new cutoff adds to it,
+/// so that moves that stopped working can be overtaken.
+const MAX_HISTORY: i32 = 1 << 14;
+
+/// What a search learns about quiet moves causing beta cutoffs, to try them early elsewhere in the tree
+/// (https://www.chessprogramming.org/Move_Ordering).
+///
+/// Shared between threads without locks like `SharedTranspositionTable`.
+/// Updates may get lost when threads race, which only costs a little ordering.
+pub struct MoveOrdering {
+	/// Killer moves by ply: quiet moves that caused a cutoff in a sibling node, most recent first.
+	killers: Vec<[AtomicU32; NUM_KILLERS]>,
+	/// Butterfly history by player, from and to square.
+	history: Vec<AtomicI32>,
+	/// The quiet move that refuted a move, by the moving piece and target square of the move refuted.
+	countermoves: Vec<AtomicU32>,
+}
+
+impl Default for MoveOrdering {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl MoveOrdering {
+	pub fn new() -> Self {
+		Self {
+			killers: (0..=MAX_DEPTH + 1).map(|_| [AtomicU32::new(0), AtomicU32::new(0)]).collect(),
+			history: (0..2 * 64 * 64).map(|_| AtomicI32::new(0)).collect(),
+			countermoves: (0..13 * 64).map(|_| AtomicU32::new(0)).collect(),
+		}
+	}
+
+	/// Killer moves at `ply`, most recent first.
+	pub fn killers(&self, ply: u32) -> [Option<Move>; NUM_KILLERS] {
+		match self.killers.get(ply as usize) {
+			Some(killers) => killers.each_ref().map(|k| unpack_move(k.load(Relaxed) as u64)),
+			None => [None; NUM_KILLERS],
+		}
+	}
+
+	/// History score of quiet move `mv` by `player`: higher is better.
+	pub fn history(&self, player: Color, mv: Move) -> i32 {
+		self.history[history_index(player, mv)].load(Relaxed)
+	}
+
+	/// The move that refuted `previous` (the opponent's last move) before, if any.
+	pub fn countermove(&self, previous: Option<Move>) -> Option<Move> {
+		previous.and_then(|prev| unpack_move(self.countermoves[countermove_index(prev)].load(Relaxed) as u64))
+	}
+
+	/// Quiet move `mv` by `player` caused a beta cutoff at `ply`, searching `depth` plies deep, in reply to `previous`.
+	/// The quiet moves in `tried` were searched before it without causing one.
+	pub fn cutoff(&self, player: Color, ply: u32, depth: u32, previous: Option<Move>, mv: Move, tried: &[Move]) {
+		if let Some([first, second]) = self.killers.get(ply as usize) {
+			let packed = pack_move(Some(mv)) as u32;
+			if first.load(Relaxed) != packed {
+				second.store(first.load(Relaxed), Relaxed);
+				first.store(packed, Relaxed);
+			}
+		}
+
+		let bonus = i32::min((depth * depth) as i32, MAX_HISTORY);
+		self.add_history(player, mv, bonus);
+		for &other in tried {
+			self.add_history(player, other, -bonus);
+		}
+
+		if let Some(prev) = previous {
+			self.countermoves[countermove_index(prev)].store(pack_move(Some(mv)) as u32, Relaxed);
+		}
+	}
+
+	fn add_history(&self, player: Color, mv: Move, bonus: i32) {
+		let entry = &self.history[history_index(player, mv)];
+		let value = entry.load(Relaxed);
+		entry.store(value + bonus - value * bonus.abs() / MAX_HISTORY, Relaxed);
+	}
+}
+
+#[inline]
+fn history_index(player: Color, mv: Move) -> usize {
+	player.index() * 64 * 64 + mv.from.index() * 64 + mv.to.index()
+}
+
+#[inline]
+fn countermove_index(mv: Move) -> usize {
+	mv.piece.index() * 64 + mv.to.index()
+}
+
+/// Captures, en passant and promotions: the moves that change the material.
+pub fn is_capture(board: &Board, mv: Move) -> bool {
+	board.at(mv.to) != Square::Empty || mv.promotion.is_some() || (mv.piece.is_pawn() && mv.from.col() != mv.to.col())
+}
+
+/// Legal moves of a node, most promising first, generated in stages
+/// (https://www.chessprogramming.org/Move_Generation#Staged_move_generation):
+///
+///   1. the hash move (the best move of an earlier search, if still legal),
+///   2. captures that do not lose material, by static exchange evaluation, then least valuable attacker first,
+///   3. the killer moves,
+///   4. the countermove,
+///   5. the other quiet moves, by history score,
+///   6. captures that lose material.
+///
+/// The hash move is tried before generating any moves, so a cutoff by it saves generating them at all.
+/// The other moves are picked one at a time, instead of sorting them all up front.
+pub struct MovePicker<'a> {
+	board: &'a Board,
+	player: Color,
+	ordering: &'a MoveOrdering,
+	stage: Stage,
+	hash_move: Option<Move>,
+	/// Killer moves, then the countermove, if any.
+	refutations: [Option<Move>; NUM_KILLERS + 1],
+	/// Generated moves not returned yet, with their scores (higher first).
+	good_captures: SmVec<(Move, i32)>,
+	quiets: SmVec<(Move, i32)>,
+	bad_captures: SmVec<(Move, i32)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+	HashMove,
+	Generate,
+	GoodCaptures,
+	Refutations(usize),
+	Quiets,
+	BadCaptures,
+	Done,
+}
+
+impl<'a> MovePicker<'a> {
+	/// Moves for `player`, `ply` plies from the root, after the opponent played `previous`.
+	pub fn new(board: &'a Board, player: Color, hash_move: Option<Move>, ordering: &'a MoveOrdering, ply: u32, previous: Option<Move>) -> Self {
+		let [first, second] = ordering.killers(ply);
+		Self {
+			board,
+			player,
+			ordering,
+			stage: Stage::HashMove,
+			hash_move,
+			refutations: [first, second, ordering.countermove(previous)],
+			good_captures: SmVec::new(),
+			quiets: SmVec::new(),
+			bad_captures: SmVec::new(),
+		}
+	}
+
+	fn generate(&mut self) {
+		for mv in self.board.legal_moves(self.player) {
+			if Some(mv) == self.hash_move {
+				continue;
+			}
+			if is_capture(self.board, mv) {
+				let see = self.board.see(mv);
+				let score = 16 * see - mv.piece.value().abs();
+				match see >= 0 {
+					true => self.good_captures.push((mv, score)),
+					false => self.bad_captures.push((mv, score)),
+				}
+			} else {
+				self.quiets.push((mv, self.ordering.history(self.player, mv)));
+			}
+		}
+	}
+
+	/// The refutation `i`, if it is one of the quiet moves left and has not been returned already.
+	fn refutation(&mut self, i: usize) -> Option<Move> {
+		let mv = self.refutations[i]?;
+		if self.refutations[..i].contains(&Some(mv)) {
+			return None;
+		}
+		let j = self.quiets.iter().position(|(m, _)| *m == mv)?;
+		Some(self.quiets.remove(j).0)
+	}
+}
+
+/// Remove and return the move with the highest score (the first of equals).
+fn pick_best(moves: &mut SmVec<(Move, i32)>) -> Option<Move> {
+	let (i, _) = moves.iter().enumerate().min_by_key(|(_, (_, score))| -score)?;
+	Some(moves.remove(i).0)
+}
+
+impl Iterator for MovePicker<'_> {
+	type Item = Move;
+
+	fn next(&mut self) -> Option<Move> {
+		loop {
+			match self.stage {
+				Stage::HashMove => {
+					self.stage = Stage::Generate;
+					if let Some(mv) = self.hash_move.filter(|&mv| self.board.is_legal(self.player, mv)) {
+						return Some(mv);
+					}
+				}
+				Stage::Generate => {
+					self.generate();
+					self.stage = Stage::GoodCaptures;
+				}
+				Stage::GoodCaptures => match pick_best(&mut self.good_captures) {
+					Some(mv) => return Some(mv),
+					None => self.stage = Stage::Refutations(0),
+				},
+				Stage::Refutations(i) => {
+					self.stage = match i + 1 < self.refutations.len() {
+						true => Stage::Refutations(i + 1),
+						false => Stage::Quiets,
+					};
+					if let Some(mv) = self.refutation(i) {
+						return Some(mv);
+					}
+				}
+				Stage::Quiets => match pick_best(&mut self.quiets) {
+					Some(mv) => return Some(mv),
+					None => self.stage = Stage::BadCaptures,
+				},
+				Stage::BadCaptures => match pick_best(&mut self.bad_captures) {
+					Some(mv) => return Some(mv),
+					None => self.stage = Stage::Done,
+				},
+				Stage::Done => return None,
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn mv(s: &str) -> Move {
+		Move::from_str(s).unwrap()
+	}
+
+	fn picked(board: &Board, player: Color, hash_move: Option<Move>, ordering: &MoveOrdering, ply: u32, previous: Option<Move>) -> Vec<String> {
+		MovePicker::new(board, player, hash_move, ordering, ply, previous)
+			.map(|mv| mv.to_string())
+			.collect()
+	}
+
+	#[test]
+	fn all_moves_once() {
+		let ordering = MoveOrdering::new();
+		for (_, fen, _) in PERFT_SUITE {
+			let position = Position::from_fen(fen).unwrap();
+			let (board, player) = (position.board(), position.player());
+			let legal = board.legal_moves(player);
+			// a legal hash move, and one from another position.
+			for hash_move in [legal.last().copied(), Some(mv("Ne4f6"))] {
+				let mut have = picked(board, player, hash_move, &ordering, 3, None);
+				let mut want = legal.iter().map(|mv| mv.to_string()).collect::<Vec<_>>();
+				have.sort();
+				want.sort();
+				assert_eq!(have, want, "{}", fen);
+			}
+		}
+	}
+
+	#[test]
+	fn stages() {
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . . .
+		. p . . . . . p
+		. . . . . . r .
+		. . N . . . . .
+		. . . . . . . .
+		. . . Q . . . K
+		",
+		)
+		.unwrap();
+		let ordering = MoveOrdering::new();
+		ordering.cutoff(White, 2, 3, None, mv("Nc3a4"), &[]);
+		ordering.cutoff(White, 2, 3, None, mv("Qd1d2"), &[]);
+		ordering.cutoff(White, 5, 3, Some(mv("ph5h4")), mv("Qd1d8"), &[]);
+		ordering.cutoff(White, 5, 2, None, mv("Nc3d5"), &[]);
+
+		let have = picked(&board, White, Some(mv("Kh1h2")), &ordering, 2, Some(mv("ph5h4")));
+		assert_eq!(have[..6], ["h1h2", "c3b5", "d1d2", "c3a4", "d1d8", "c3d5"]);
+		assert_eq!(have.last().unwrap(), "d1g4");
+		assert_eq!(have.len(), board.legal_moves(White).len());
+	}
+
+	#[test]
+	fn killers() {
+		let ordering = MoveOrdering::new();
+		assert_eq!(ordering.killers(1), [None, None]);
+		ordering.cutoff(White, 1, 1, None, mv("Ne4f6"), &[]);
+		ordering.cutoff(White, 1, 1, None, mv("Ne4f6"), &[]);
+		assert_eq!(ordering.killers(1), [Some(mv("Ne4f6")), None]);
+		ordering.cutoff(White, 1, 1, None, mv("Bc1g5"), &[]);
+		ordering.cutoff(White, 1, 1, None, mv("Qd1d2"), &[]);
+		assert_eq!(ordering.killers(1), [Some(mv("Qd1d2")), Some(mv("Bc1g5"))]);
+		assert_eq!(ordering.killers(2), [None, None]);
+		assert_eq!(ordering.killers(MAX_DEPTH + 10), [None, None]);
+	}
+
+	#[test]
+	fn history_and_countermove() {
+		let ordering = MoveOrdering::new();
+		ordering.cutoff(White, 1, 4, Some(mv("pe7e5")), mv("Ng1f3"), &[mv("Pa2a3"), mv("Ph2h3")]);
+		assert_eq!(ordering.history(White, mv("Ng1f3")), 16);
+		assert_eq!(ordering.history(White, mv("Pa2a3")), -16);
+		assert_eq!(ordering.history(Black, mv("Ng1f3")), 0);
+		assert_eq!(ordering.countermove(Some(mv("pe7e5"))), Some(mv("Ng1f3")));
+		assert_eq!(ordering.countermove(Some(mv("pd7d5"))), None);
+		assert_eq!(ordering.countermove(None), None);
+
+		// history saturates.
+		for _ in 0..10_000 {
+			ordering.cutoff(White, 1, 20, None, mv("Ng1f3"), &[]);
+		}
+		let value = ordering.history(White, mv("Ng1f3"));
+		assert!(value > MAX_HISTORY / 2 && value <= MAX_HISTORY, "{}", value);
+	}
+}
@@ -0,0 +1,328 @@
+use super::internal::*;
+use super::transposition_table::num_slots;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Weights of the pawn structure terms, per pawn, in centipawns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PawnWeights {
+	/// Another pawn of the same color in front of it (on the same file).
+	pub doubled: Tapered,
+	/// No pawns of the same color on the neighboring files.
+	pub isolated: Tapered,
+	/// Cannot advance without being taken by a pawn, and no pawns of the same color can come to its support.
+	pub backward: Tapered,
+	/// Protected by a pawn of the same color, or side by side with one.
+	pub connected: Tapered,
+	/// No enemy pawns in front of it on its own or the neighboring files.
+	/// By row, counted from the pawn's own side (row 1 is the starting row).
+	pub passed: [Tapered; 8],
+}
+
+impl Default for PawnWeights {
+	fn default() -> Self {
+		let t = Tapered::new;
+		Self {
+			doubled: t(-10, -20),
+			isolated: t(-10, -15),
+			backward: t(-8, -10),
+			connected: t(5, 8),
+			passed: [t(0, 0), t(5, 10), t(10, 15), t(15, 25), t(25, 45), t(40, 75), t(60, 110), t(0, 0)],
+		}
+	}
+}
+
+impl PawnWeights {
+	/// Value of the pawn structure, from White's point of view.
+	pub fn eval(&self, board: &Board) -> Tapered {
+		self.eval1(board, White) - self.eval1(board, Black)
+	}
+
+	fn eval1(&self, board: &Board, player: Color) -> Tapered {
+		let f = PawnFeatures::new(board, player);
+		let passed = iter_bitfield(f.passed)
+			.map(|i| {
+				let row = Pos::from_index(i as usize).row() as usize;
+				self.passed[if player == White { row } else { 7 - row }]
+			})
+			.fold(Tapered::default(), |a, b| a + b);
+		passed
+			+ self.doubled * f.doubled.count_ones() as i32
+			+ self.isolated * f.isolated.count_ones() as i32
+			+ self.backward * f.backward.count_ones() as i32
+			+ self.connected * f.connected.count_ones() as i32
+	}
+}
+
+/// The pawns of one color that are doubled, isolated, etc. (see `PawnWeights`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct PawnFeatures {
+	/// Only the pawns behind another one count, the front pawn does not.
+	pub doubled: u64,
+	pub isolated: u64,
+	/// Isolated pawns do not count as backward as well.
+	pub backward: u64,
+	pub connected: u64,
+	/// Pawns behind another one of the same color do not count as passed, only the front pawn.
+	pub passed: u64,
+}
+
+impl PawnFeatures {
+	pub fn new(board: &Board, player: Color) -> Self {
+		// "forward" is where `player`'s pawns are going.
+		let (fwd, back, fill_fwd, fill_back): (Shift, Shift, Shift, Shift) = match player {
+			White => (sh_n, sh_s, fill_n, fill_s),
+			Black => (sh_s, sh_n, fill_s, fill_n),
+		};
+		let own = board.bits(player.pawn());
+		let enemy = board.bits(player.opposite().pawn());
+		let sides = |bits| sh_e(bits) | sh_w(bits);
+
+		let own_attacks = fwd(sides(own));
+		let enemy_attacks = back(sides(enemy));
+
+		let doubled = own & fill_back(back(own));
+		let isolated = own & !(fill_n(sides(own)) | fill_s(sides(own)));
+		// the square in front is attacked by an enemy pawn, and no pawn behind on a neighboring file can come to defend it.
+		let backward = own & back(enemy_attacks & !fill_fwd(own_attacks)) & !isolated;
+		let connected = own & (own_attacks | sides(own));
+		let passed = own & !fill_back(back(enemy | sides(enemy))) & !doubled;
+
+		Self {
+			doubled,
+			isolated,
+			backward,
+			connected,
+			passed,
+		}
+	}
+}
+
+/// `PawnWeights::eval` cached by `Board::pawn_zobrist`,
+/// so that the pawn structure is only evaluated again when pawns have moved.
+pub struct PawnStructure {
+	weights: PawnWeights,
+	table: PawnTable,
+}
+
+impl PawnStructure {
+	/// With a table taking up (at most) `mb` megabytes.
+	pub fn new(weights: PawnWeights, mb: usize) -> Self {
+		Self {
+			weights,
+			table: PawnTable::new(mb),
+		}
+	}
+
+	/// Value of the pawn structure, from White's point of view.
+	pub fn eval(&self, board: &Board) -> Tapered {
+		let key = board.pawn_zobrist();
+		match self.table.probe(key) {
+			Some(value) => value,
+			None => {
+				let value = self.weights.eval(board);
+				self.table.store(key, value);
+				value
+			}
+		}
+	}
+
+	pub fn weights(&self) -> &PawnWeights {
+		&self.weights
+	}
+}
+
+/// Pawn structure values by pawn key, shared between threads without locks like `SharedTranspositionTable`.
+///
+/// An empty slot holds key 0 and value 0, which is right for boards without pawns.
+struct PawnTable {
+	slots: Vec<[AtomicU64; 2]>,
+	mask: u64,
+}
+
+impl PawnTable {
+	fn new(mb: usize) -> Self {
+		let len = num_slots(mb);
+		Self {
+			slots: (0..len).map(|_| [AtomicU64::new(0), AtomicU64::new(0)]).collect(),
+			mask: len as u64 - 1,
+		}
+	}
+
+	#[inline]
+	fn probe(&self, key: u64) -> Option<Tapered> {
+		let [stored_key, stored_data] = &self.slots[(key & self.mask) as usize];
+		let data = stored_data.load(Relaxed);
+		match stored_key.load(Relaxed) ^ data == key {
+			true => Some(Tapered::new(data as u32 as i32, (data >> 32) as u32 as i32)),
+			false => None,
+		}
+	}
+
+	#[inline]
+	fn store(&self, key: u64, value: Tapered) {
+		let [stored_key, stored_data] = &self.slots[(key & self.mask) as usize];
+		let data = value.mg as u32 as u64 | (value.eg as u32 as u64) << 32;
+		stored_key.store(key ^ data, Relaxed);
+		stored_data.store(data, Relaxed);
+	}
+}
+
+/// A board shift or fill like `sh_n`, `fill_n`, chosen by color.
+pub(super) type Shift = fn(u64) -> u64;
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn board(board: &str) -> Board {
+		Board::from_str(board).unwrap()
+	}
+
+	/// Bitboard of squares given by name, e.g. "a2 h3".
+	fn squares(names: &str) -> u64 {
+		names
+			.split_whitespace()
+			.map(|name| 1 << Pos::try_from(name.as_bytes()).unwrap().index())
+			.fold(0, |a, b| a | b)
+	}
+
+	#[test]
+	fn doubled_isolated_connected_passed() {
+		let b = board(
+			r"
+			. . . . k . . .
+			. . . . . p . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			P . . . . . . P
+			P . . . . . P .
+			. . . . K . . .
+			",
+		);
+		assert_eq!(
+			PawnFeatures::new(&b, White),
+			PawnFeatures {
+				doubled: squares("a2"),
+				isolated: squares("a2 a3"),
+				backward: 0,
+				connected: squares("h3"),
+				passed: squares("a3 h3"),
+			}
+		);
+		assert_eq!(
+			PawnFeatures::new(&b, Black),
+			PawnFeatures {
+				isolated: squares("f7"),
+				..PawnFeatures::default()
+			}
+		);
+	}
+
+	#[test]
+	fn backward() {
+		let b = board(
+			r"
+			. . . . k . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . p . . .
+			. . . . P . . .
+			. . . P . . . .
+			. . . . . . . .
+			. . . . K . . .
+			",
+		);
+		assert_eq!(
+			PawnFeatures::new(&b, White),
+			PawnFeatures {
+				backward: squares("d3"),
+				connected: squares("e4"),
+				..PawnFeatures::default()
+			}
+		);
+		assert_eq!(
+			PawnFeatures::new(&b, Black),
+			PawnFeatures {
+				isolated: squares("e5"),
+				..PawnFeatures::default()
+			}
+		);
+
+		// with a pawn on c2 to support it, d3 is no longer backward.
+		let b = board(
+			r"
+			. . . . k . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . p . . .
+			. . . . P . . .
+			. . . P . . . .
+			. . P . . . . .
+			. . . . K . . .
+			",
+		);
+		assert_eq!(PawnFeatures::new(&b, White).backward, 0);
+		assert_eq!(PawnFeatures::new(&b, White).connected, squares("d3 e4"));
+	}
+
+	#[test]
+	fn passed_black() {
+		let b = board(
+			r"
+			. . . . k . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. p . . . . . .
+			. . . . . . p .
+			. . . . . . . P
+			. . . . K . . .
+			",
+		);
+		assert_eq!(PawnFeatures::new(&b, Black).passed, squares("b4"));
+		assert_eq!(PawnFeatures::new(&b, White).passed, 0);
+
+		let w = PawnWeights::default();
+		assert_eq!(w.eval(&b), w.eval1(&b, White) - w.eval1(&b, Black));
+		assert!(w.eval(&b).eg < 0); // Black's passed pawn
+	}
+
+	#[test]
+	fn eval() {
+		let w = PawnWeights::default();
+		assert_eq!(w.eval(&Board::starting_position()), Tapered::default());
+		assert_eq!(w.eval(&Board::new()), Tapered::default());
+
+		// two isolated pawns: the front one passed on row 2 (counted from its side), the one behind it doubled (so not passed).
+		let b = board(
+			r"
+			. . . . k . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			. . . . . . . .
+			P . . . . . . .
+			P . . . . . . .
+			. . . . K . . .
+			",
+		);
+		assert_eq!(w.eval(&b), w.doubled + w.isolated * 2 + w.passed[2]);
+	}
+
+	#[test]
+	fn pawn_table() {
+		let pawns = PawnStructure::new(PawnWeights::default(), 1);
+		for (_, fen, _) in PERFT_SUITE {
+			let board = Position::from_fen(fen).unwrap().board().clone();
+			assert_eq!(pawns.eval(&board), pawns.weights().eval(&board), "{}", fen);
+			assert_eq!(pawns.table.probe(board.pawn_zobrist()), Some(pawns.weights().eval(&board)), "{}", fen);
+		}
+
+		let table = PawnTable::new(1);
+		assert_eq!(table.probe(0), Some(Tapered::default()));
+		assert_eq!(table.probe(12345), None);
+		table.store(12345, Tapered::new(-3, 4));
+		assert_eq!(table.probe(12345), Some(Tapered::new(-3, 4)));
+	}
+}
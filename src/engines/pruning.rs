@@ -0,0 +1,167 @@
+use super::internal::*;
+
+/// Selective search: searching some moves less deep than others, or not at all, to get deeper where it matters.
+/// Each kind can be turned on separately. Without any of them, the search finds the same values as plain alpha-beta.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pruning {
+	/// Null-move pruning (https://www.chessprogramming.org/Null_Move_Pruning): if passing the move
+	/// and searching less deep still gets at least beta, a real move surely will.
+	/// Not for a side with only king and pawns, which may be in zugzwang: worse off whatever move it makes.
+	pub null_move: bool,
+	/// Late move reductions (https://www.chessprogramming.org/Late_Move_Reductions):
+	/// quiet moves late in the move ordering are searched less deep first, and only fully if that looks good.
+	pub lmr: Option<Lmr>,
+	/// Futility pruning (https://www.chessprogramming.org/Futility_Pruning): near the leaves,
+	/// do not search quiet moves when even a margin above the static evaluation cannot reach alpha.
+	pub futility: bool,
+	/// Reverse futility pruning: near the leaves, return the static evaluation when even a margin below it reaches beta.
+	pub reverse_futility: bool,
+}
+
+impl Default for Pruning {
+	fn default() -> Self {
+		Self::none()
+	}
+}
+
+impl Pruning {
+	pub const fn none() -> Self {
+		Self {
+			null_move: false,
+			lmr: None,
+			futility: false,
+			reverse_futility: false,
+		}
+	}
+
+	/// All of them, with the default reductions.
+	pub const fn all() -> Self {
+		Self {
+			null_move: true,
+			lmr: Some(Lmr::new()),
+			futility: true,
+			reverse_futility: true,
+		}
+	}
+
+	/// Is any kind on?
+	pub fn any(&self) -> bool {
+		self.null_move || self.lmr.is_some() || self.futility || self.reverse_futility
+	}
+}
+
+/// How late moves are reduced: by `base + ln(depth) * ln(move number) / divisor` plies (rounded down),
+/// both given in hundredths of a ply, so that they can be tuned as integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lmr {
+	pub base: u32,
+	pub divisor: u32,
+}
+
+impl Default for Lmr {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Lmr {
+	pub const fn new() -> Self {
+		Self { base: 75, divisor: 225 }
+	}
+}
+
+/// Moves searched at full depth before reducing any.
+pub const LMR_FULL_DEPTH_MOVES: usize = 3;
+
+/// Minimum depth (plies left) for late move reductions and null-move pruning.
+pub const REDUCTION_MIN_DEPTH: u32 = 3;
+
+/// Maximum depth (plies left) for (reverse) futility pruning.
+pub const FUTILITY_MAX_DEPTH: u32 = 3;
+
+/// Futility margins by depth, in centipawns.
+const FUTILITY_MARGIN: [i32; FUTILITY_MAX_DEPTH as usize + 1] = [0, 150, 300, 500];
+
+/// Futility margin `depth` plies above the leaves, for an evaluation that values a pawn `pawn_value`.
+pub fn futility_margin(depth: u32, pawn_value: i32) -> i32 {
+	FUTILITY_MARGIN[depth as usize] * pawn_value / 100
+}
+
+/// Plies that null-move pruning searches less deep than the node (not counting the null move itself).
+pub fn null_move_reduction(depth: u32) -> u32 {
+	2 + depth / 6
+}
+
+/// Does `player` have any pieces other than king and pawns? If not, null-move pruning is off for them.
+pub fn has_non_pawn_material(board: &Board, player: Color) -> bool {
+	board.all_pieces(player) & !board.bits(player.pawn()) & !board.bits(player.king()) != 0
+}
+
+/// Late move reductions by depth and move number (counting from 1), precomputed from an `Lmr`.
+pub struct Reductions {
+	table: Vec<[u8; 64]>,
+}
+
+impl Reductions {
+	pub fn new(lmr: Lmr) -> Self {
+		let reduction = |depth: usize, moves: usize| {
+			let r = lmr.base as f64 / 100.0 + (depth as f64).ln() * (moves as f64).ln() * 100.0 / u32::max(lmr.divisor, 1) as f64;
+			r.clamp(0.0, 63.0) as u8
+		};
+		Self {
+			table: (0..64)
+				.map(|depth| std::array::from_fn(|moves| if depth == 0 || moves == 0 { 0 } else { reduction(depth, moves) }))
+				.collect(),
+		}
+	}
+
+	/// Plies to reduce the `moves`-th move of a node `depth` plies above the leaves.
+	/// (The search only reduces moves after the first `LMR_FULL_DEPTH_MOVES`, at least `REDUCTION_MIN_DEPTH` plies above the leaves.)
+	pub fn get(&self, depth: u32, moves: usize) -> u32 {
+		self.table[usize::min(depth as usize, 63)][usize::min(moves, 63)] as u32
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn reductions() {
+		let r = Reductions::new(Lmr::new());
+		assert_eq!(r.get(1, 1), 0);
+		assert_eq!(r.get(3, 4), 1);
+		assert!(r.get(10, 30) > r.get(3, 4));
+		for depth in 1..70 {
+			for moves in 1..70 {
+				assert!(r.get(depth, moves + 1) >= r.get(depth, moves));
+				assert!(r.get(depth + 1, moves) >= r.get(depth, moves));
+			}
+		}
+
+		// a larger divisor reduces less, a larger base more.
+		let gentle = Reductions::new(Lmr { base: 0, divisor: 1000 });
+		assert_eq!(gentle.get(3, 4), 0);
+		let aggressive = Reductions::new(Lmr { base: 200, divisor: 100 });
+		assert_eq!(aggressive.get(3, 4), 3);
+	}
+
+	#[test]
+	fn non_pawn_material() {
+		let board = Board::from_str(
+			r"
+		. . . . k . . .
+		. . . . p . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . P . . .
+		. . . . K . N .
+		",
+		)
+		.unwrap();
+		assert!(has_non_pawn_material(&board, White));
+		assert!(!has_non_pawn_material(&board, Black));
+	}
+}
@@ -0,0 +1,797 @@
+use super::internal::*;
+use std::time::Duration;
+
+/// A leaf evaluation chosen at run time.
+pub type DynEval = Box<dyn Fn(&Board, Color) -> i32 + Send + Sync>;
+
+/// A search algorithm that `parse_engine` can build.
+pub struct Algorithm {
+	pub name: &'static str,
+	pub help: &'static str,
+	pub params: &'static [Param],
+	build: fn(Args) -> Result<Box<dyn Engine + Send>>,
+}
+
+/// A leaf evaluation that engines can be built with (`eval=...`).
+pub struct Evaluation {
+	pub name: &'static str,
+	pub help: &'static str,
+	/// What a pawn is worth to this evaluation.
+	pub pawn_value: i32,
+	pub params: &'static [Param],
+	build: fn(Args) -> Result<DynEval>,
+}
+
+/// A named, typed parameter of an algorithm or evaluation.
+pub struct Param {
+	pub name: &'static str,
+	pub kind: ParamKind,
+	/// `None`: not set unless given.
+	pub default: Option<&'static str>,
+	pub help: &'static str,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamKind {
+	Int {
+		min: u64,
+		max: u64,
+	},
+	/// `on`/`off`, `true`/`false`, `1`/`0`.
+	Bool,
+	/// E.g. `64mb`, `1gb`, or just `64`; from 1mb up to `MAX_MEGABYTES`.
+	Megabytes,
+	/// E.g. `500ms`, `2s`, or just `500`.
+	Millis,
+	/// The name of an `Evaluation`, possibly with parameters.
+	Eval,
+}
+
+/// Largest table size a `ParamKind::Megabytes` parameter takes.
+pub const MAX_MEGABYTES: u64 = 4096;
+
+/// Algorithms, for `parse_engine`.
+pub const ALGORITHMS: &[Algorithm] = &[
+	Algorithm {
+		name: "valid",
+		help: "plays a random legal move",
+		params: &[],
+		build: |_| Ok(Box::new(Valid())),
+	},
+	Algorithm {
+		name: "greedy",
+		help: "plays the move with the best evaluation, without looking ahead",
+		params: &[EVAL],
+		build: |mut args| Ok(Box::new(Greedy(args.eval("eval").eval))),
+	},
+	Algorithm {
+		name: "negamax",
+		help: "full-width minimax search",
+		params: &[
+			Param {
+				default: Some("2"),
+				kind: ParamKind::Int { min: 0, max: 8 },
+				..DEPTH
+			},
+			EVAL,
+		],
+		build: |mut args| Ok(Box::new(NegaMax::new(args.int("depth") as u32, args.eval("eval").eval))),
+	},
+	Algorithm {
+		name: "alphabeta",
+		help: "alpha-beta search with iterative deepening and a transposition table",
+		params: ALPHABETA_PARAMS,
+		build: |mut args| {
+			let leaf = args.eval("eval");
+			let engine = AlphaBeta::with_tt_mb(0, leaf.eval, args.int("tt") as usize);
+			Ok(Box::new(engine.with_limits(limits(&args)).with_options(options(&args, leaf.pawn_value))))
+		},
+	},
+	Algorithm {
+		name: "palphabeta",
+		help: "alphabeta, searching the moves in parallel, sharing one transposition table",
+		params: ALPHABETA_PARAMS,
+		build: |mut args| {
+			let leaf = args.eval("eval");
+			let engine = ParAlphaBeta::with_tt_mb(0, leaf.eval, args.int("tt") as usize);
+			Ok(Box::new(engine.with_limits(limits(&args)).with_options(options(&args, leaf.pawn_value))))
+		},
+	},
+	Algorithm {
+		name: "pvs",
+		help: "alphabeta with principal variation search and aspiration windows",
+		params: PVS_PARAMS,
+		build: |mut args| {
+			let leaf = args.eval("eval");
+			let engine = Pvs::with_tt_mb(0, leaf.eval, args.int("tt") as usize).with_window(args.int("window") as i32);
+			Ok(Box::new(engine.with_limits(limits(&args)).with_options(options(&args, leaf.pawn_value))))
+		},
+	},
+];
+
+/// Evaluations, for the `eval` parameter.
+pub const EVALUATIONS: &[Evaluation] = &[
+	Evaluation {
+		name: "zero",
+		help: "everything is worth nothing",
+		pawn_value: 1,
+		params: &[],
+		build: |_| Ok(Box::new(zero)),
+	},
+	Evaluation {
+		name: "material",
+		help: "material balance, in pawns",
+		pawn_value: 1,
+		params: &[],
+		build: |_| Ok(Box::new(material)),
+	},
+	Evaluation {
+		name: "strat1",
+		help: "material, protection, threats and mobility",
+		pawn_value: 1000,
+		params: &[],
+		build: |_| Ok(Box::new(heuristic1)),
+	},
+	Evaluation {
+		name: "strat2",
+		help: "material and check",
+		pawn_value: 1000,
+		params: &[],
+		build: |_| Ok(Box::new(heuristic2)),
+	},
+	Evaluation {
+		name: "pst",
+		help: "material and piece-square tables, tapered from middlegame to endgame",
+		pawn_value: 100,
+		params: &[
+			Param {
+				name: "pawns",
+				kind: ParamKind::Bool,
+				default: Some("off"),
+				help: "evaluate doubled, isolated, backward, connected and passed pawns",
+			},
+			Param {
+				name: "pawntt",
+				kind: ParamKind::Megabytes,
+				default: Some("1mb"),
+				help: "pawn structure hash table size",
+			},
+			Param {
+				name: "king",
+				kind: ParamKind::Bool,
+				default: Some("off"),
+				help: "evaluate king safety: attacks near the king, pawn shield and storm, open files",
+			},
+		],
+		build: |args| {
+			let pawns = args
+				.bool("pawns")
+				.then(|| PawnStructure::new(PawnWeights::default(), args.int("pawntt") as usize));
+			let king = args.bool("king").then(KingSafety::default);
+			match (pawns, king) {
+				(None, None) => Ok(Box::new(tapered)),
+				(pawns, king) => Ok(Box::new(move |board: &Board, player| {
+					tapered_with(board, player, pawns.as_ref(), king.as_ref())
+				})),
+			}
+		},
+	},
+];
+
+const DEPTH: Param = Param {
+	name: "depth",
+	kind: ParamKind::Int {
+		min: 0,
+		max: MAX_DEPTH as u64 - 1,
+	},
+	default: Some("4"),
+	help: "plies searched below each move",
+};
+
+const EVAL: Param = Param {
+	name: "eval",
+	kind: ParamKind::Eval,
+	default: Some("material"),
+	help: "leaf evaluation",
+};
+
+const ALPHABETA_PARAMS: &[Param] = &[
+	DEPTH,
+	EVAL,
+	QSEARCH,
+	ORDERING,
+	NMP,
+	LMR,
+	LMR_BASE,
+	LMR_DIVISOR,
+	FUTILITY,
+	RFP,
+	TT,
+	TIME,
+	NODES,
+];
+
+const PVS_PARAMS: &[Param] = &[
+	DEPTH,
+	EVAL,
+	QSEARCH,
+	ORDERING,
+	NMP,
+	LMR,
+	LMR_BASE,
+	LMR_DIVISOR,
+	FUTILITY,
+	RFP,
+	TT,
+	TIME,
+	NODES,
+	Param {
+		name: "window",
+		kind: ParamKind::Int { min: 0, max: 100_000 },
+		default: Some("50"),
+		help: "aspiration window around the previous iteration's value, in centipawns",
+	},
+];
+
+const QSEARCH: Param = Param {
+	name: "qsearch",
+	kind: ParamKind::Bool,
+	default: Some("off"),
+	help: "quiescence search: play out captures at the leaves",
+};
+
+const ORDERING: Param = Param {
+	name: "ordering",
+	kind: ParamKind::Bool,
+	default: Some("on"),
+	help: "staged move ordering with killers, history and countermoves (off: by leaf evaluation)",
+};
+
+const NMP: Param = Param {
+	name: "nmp",
+	kind: ParamKind::Bool,
+	default: Some("off"),
+	help: "null-move pruning (not with only king and pawns)",
+};
+
+const LMR: Param = Param {
+	name: "lmr",
+	kind: ParamKind::Bool,
+	default: Some("off"),
+	help: "late move reductions",
+};
+
+const LMR_BASE: Param = Param {
+	name: "lmrbase",
+	kind: ParamKind::Int { min: 0, max: 1000 },
+	default: Some("75"),
+	help: "late move reductions: base + ln(depth) * ln(move number) / divisor, in hundredths of a ply",
+};
+
+const LMR_DIVISOR: Param = Param {
+	name: "lmrdiv",
+	kind: ParamKind::Int { min: 1, max: 10_000 },
+	default: Some("225"),
+	help: "late move reductions: divisor, in hundredths",
+};
+
+const FUTILITY: Param = Param {
+	name: "futility",
+	kind: ParamKind::Bool,
+	default: Some("off"),
+	help: "futility pruning of quiet moves near the leaves",
+};
+
+const RFP: Param = Param {
+	name: "rfp",
+	kind: ParamKind::Bool,
+	default: Some("off"),
+	help: "reverse futility pruning near the leaves",
+};
+
+const TT: Param = Param {
+	name: "tt",
+	kind: ParamKind::Megabytes,
+	default: Some("16mb"),
+	help: "transposition table size",
+};
+
+const TIME: Param = Param {
+	name: "time",
+	kind: ParamKind::Millis,
+	default: None,
+	help: "time per move (searching at most `depth` deep)",
+};
+
+const NODES: Param = Param {
+	name: "nodes",
+	kind: ParamKind::Int { min: 1, max: u64::MAX },
+	default: None,
+	help: "positions visited per move (searching at most `depth` deep)",
+};
+
+/// The limits given by `depth`, `time` and `nodes`.
+fn limits(args: &Args) -> SearchLimits {
+	SearchLimits {
+		depth: Some(args.int("depth") as u32 + 1),
+		time: args.opt_int("time").map(Duration::from_millis),
+		nodes: args.opt_int("nodes"),
+	}
+}
+
+fn options(args: &Args, pawn_value: i32) -> SearchOptions {
+	SearchOptions {
+		quiescence: args.bool("qsearch"),
+		move_ordering: args.bool("ordering"),
+		pruning: Pruning {
+			null_move: args.bool("nmp"),
+			lmr: args.bool("lmr").then(|| Lmr {
+				base: args.int("lmrbase") as u32,
+				divisor: args.int("lmrdiv") as u32,
+			}),
+			futility: args.bool("futility"),
+			reverse_futility: args.bool("rfp"),
+		},
+		..SearchOptions::new(pawn_value)
+	}
+}
+
+/// The engine described by `spec`: an algorithm, optionally with parameters. E.g.:
+///
+///   valid
+///   alphabeta(depth=5, eval=strat1, qsearch=on, tt=64mb)
+///   palphabeta(time=500ms, depth=63)
+///
+/// Parameters that are not given take their default value. See `list_engines` for all algorithms and parameters.
+pub fn parse_engine(spec: &str) -> Result<Box<dyn Engine + Send>> {
+	parse_engine_with_defaults(spec, &[])
+}
+
+/// Like `parse_engine`, but parameters not given in `spec` take their value from `defaults` (e.g. `("tt", "64mb")`),
+/// if the algorithm has them.
+pub fn parse_engine_with_defaults(spec: &str, defaults: &[(&str, &str)]) -> Result<Box<dyn Engine + Send>> {
+	build_engine(spec, defaults).map_err(|e| format_err!("engine '{}': {}", spec, e))
+}
+
+fn build_engine(spec: &str, defaults: &[(&str, &str)]) -> Result<Box<dyn Engine + Send>> {
+	let mut spec = Spec::parse(spec)?;
+	let algorithm = ALGORITHMS.iter().find(|a| a.name == spec.name).ok_or_else(|| {
+		format_err!(
+			"unknown algorithm '{}', valid algorithms: {}",
+			spec.name,
+			ALGORITHMS.iter().map(|a| a.name).collect::<Vec<_>>().join(", ")
+		)
+	})?;
+	for &(name, value) in defaults {
+		if algorithm.params.iter().any(|p| p.name == name) && !spec.args.iter().any(|(n, _)| n == name) {
+			spec.args.push((name.to_owned(), Spec::parse(value)?));
+		}
+	}
+	(algorithm.build)(Args::new(algorithm.name, algorithm.params, &spec.args)?)
+}
+
+fn build_eval(spec: &Spec) -> Result<Leaf> {
+	let evaluation = EVALUATIONS.iter().find(|e| e.name == spec.name).ok_or_else(|| {
+		format_err!(
+			"unknown evaluation '{}', valid evaluations: {}",
+			spec.name,
+			EVALUATIONS.iter().map(|e| e.name).collect::<Vec<_>>().join(", ")
+		)
+	})?;
+	Ok(Leaf {
+		eval: (evaluation.build)(Args::new(evaluation.name, evaluation.params, &spec.args)?)?,
+		pawn_value: evaluation.pawn_value,
+	})
+}
+
+/// All algorithms and evaluations, with their parameters, for `--list-engines`.
+pub fn list_engines() -> String {
+	fn list_params(params: &[Param]) -> String {
+		params
+			.iter()
+			.map(|p| {
+				format!(
+					"    {:<8} {:<16} {:<10} {}\n",
+					p.name,
+					p.kind.to_string(),
+					p.default.unwrap_or("-"),
+					p.help
+				)
+			})
+			.collect()
+	}
+
+	let mut list = String::from("algorithms:\n");
+	for a in ALGORITHMS {
+		list += &format!("  {:<10} {}\n{}", a.name, a.help, list_params(a.params));
+	}
+	list += "evaluations (eval=...):\n";
+	for e in EVALUATIONS {
+		list += &format!("  {:<10} {} (pawn = {})\n{}", e.name, e.help, e.pawn_value, list_params(e.params));
+	}
+	list += "e.g.: alphabeta(depth=5,eval=strat1,qsearch=on,tt=64mb)\n";
+	list
+}
+
+impl fmt::Display for ParamKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ParamKind::Int { min, max: u64::MAX } => write!(f, "int {}..", min),
+			ParamKind::Int { min, max } => write!(f, "int {}..={}", min, max),
+			ParamKind::Bool => write!(f, "on|off"),
+			ParamKind::Megabytes => write!(f, "size (mb, gb)"),
+			ParamKind::Millis => write!(f, "time (ms, s)"),
+			ParamKind::Eval => write!(f, "evaluation"),
+		}
+	}
+}
+
+/// An evaluation with its pawn value.
+struct Leaf {
+	eval: DynEval,
+	pawn_value: i32,
+}
+
+/// Parameter values, checked against the `Param`s they are for.
+/// The getters panic on names that are not parameters (of the right kind): that is a bug in the registry.
+struct Args {
+	values: Vec<(&'static str, Value)>,
+}
+
+enum Value {
+	Int(u64),
+	Bool(bool),
+	Eval(Leaf),
+}
+
+impl Args {
+	/// Check `given` values against `owner`'s `params`, filling in defaults.
+	fn new(owner: &str, params: &'static [Param], given: &[(String, Spec)]) -> Result<Self> {
+		let mut values = vec![];
+		for (name, spec) in given {
+			let param = params.iter().find(|p| p.name == name).ok_or_else(|| match params.len() {
+				0 => format_err!("{} has no parameters, got '{}'", owner, name),
+				_ => format_err!(
+					"{} has no parameter '{}', valid parameters: {}",
+					owner,
+					name,
+					params.iter().map(|p| p.name).collect::<Vec<_>>().join(", ")
+				),
+			})?;
+			if values.iter().any(|(n, _)| *n == param.name) {
+				return Err(format_err!("{}: parameter '{}' given twice", owner, name));
+			}
+			let value = Self::value(param, spec).map_err(|e| format_err!("{}: {}: {}", owner, name, e))?;
+			values.push((param.name, value));
+		}
+
+		for param in params {
+			if let (Some(default), false) = (param.default, values.iter().any(|(n, _)| *n == param.name)) {
+				let spec = Spec::parse(default).expect("valid default");
+				values.push((param.name, Self::value(param, &spec).expect("valid default")));
+			}
+		}
+		Ok(Self { values })
+	}
+
+	fn value(param: &Param, spec: &Spec) -> Result<Value> {
+		if param.kind == ParamKind::Eval {
+			return Ok(Value::Eval(build_eval(spec)?));
+		}
+		if !spec.args.is_empty() {
+			return Err(format_err!("expected {}, got parameters", param.kind));
+		}
+
+		let s = spec.name.to_ascii_lowercase();
+		let invalid = || format_err!("expected {}, got '{}'", param.kind, spec.name);
+		let number = |s: &str, suffixes: &[(&str, u64)]| {
+			let (digits, unit) = suffixes
+				.iter()
+				.find_map(|&(suffix, unit)| Some((s.strip_suffix(suffix)?, unit)))
+				.unwrap_or((s, 1));
+			digits.parse::<u64>().ok().and_then(|n| n.checked_mul(unit)).ok_or_else(invalid)
+		};
+		Ok(match param.kind {
+			ParamKind::Int { min, max } => {
+				let n = number(&s, &[])?;
+				if !(min..=max).contains(&n) {
+					return Err(invalid());
+				}
+				Value::Int(n)
+			}
+			ParamKind::Bool => Value::Bool(match s.as_str() {
+				"on" | "true" | "1" => true,
+				"off" | "false" | "0" => false,
+				_ => return Err(invalid()),
+			}),
+			ParamKind::Megabytes => {
+				let mb = number(&s, &[("mb", 1), ("gb", 1024), ("m", 1), ("g", 1024)])?;
+				if mb > MAX_MEGABYTES {
+					return Err(format_err!("expected {} of at most {}mb, got '{}'", param.kind, MAX_MEGABYTES, spec.name));
+				}
+				Value::Int(mb.max(1))
+			}
+			ParamKind::Millis => Value::Int(number(&s, &[("ms", 1), ("s", 1000)])?),
+			ParamKind::Eval => unreachable!(),
+		})
+	}
+
+	fn get(&self, name: &str) -> Option<&Value> {
+		self.values.iter().find(|(n, _)| *n == name).map(|(_, v)| v)
+	}
+
+	fn opt_int(&self, name: &str) -> Option<u64> {
+		match self.get(name) {
+			Some(Value::Int(n)) => Some(*n),
+			None => None,
+			_ => panic!("{} is not an integer", name),
+		}
+	}
+
+	fn int(&self, name: &str) -> u64 {
+		self.opt_int(name).unwrap_or_else(|| panic!("{} has no default", name))
+	}
+
+	fn bool(&self, name: &str) -> bool {
+		match self.get(name) {
+			Some(Value::Bool(b)) => *b,
+			_ => panic!("{} is not a bool", name),
+		}
+	}
+
+	/// Take the evaluation out (evaluations are not Clone).
+	fn eval(&mut self, name: &str) -> Leaf {
+		let i = self.values.iter().position(|(n, _)| *n == name).unwrap_or_else(|| panic!("no {}", name));
+		match self.values.remove(i).1 {
+			Value::Eval(leaf) => leaf,
+			_ => panic!("{} is not an evaluation", name),
+		}
+	}
+}
+
+/// Parsed, but not yet checked, engine (or evaluation) spec:
+///
+///   spec  = name [ "(" [ param { "," param } ] ")" ]
+///   param = name "=" spec
+#[derive(Debug, PartialEq, Eq)]
+struct Spec {
+	name: String,
+	args: Vec<(String, Spec)>,
+}
+
+impl Spec {
+	fn parse(s: &str) -> Result<Self> {
+		let mut parser = SpecParser {
+			chars: s.chars().collect(),
+			pos: 0,
+		};
+		let spec = parser.spec()?;
+		match parser.peek() {
+			None => Ok(spec),
+			Some(c) => Err(parser.error(&format!("unexpected '{}'", c))),
+		}
+	}
+}
+
+struct SpecParser {
+	chars: Vec<char>,
+	pos: usize,
+}
+
+impl SpecParser {
+	fn spec(&mut self) -> Result<Spec> {
+		let name = self.name()?;
+		let mut args = vec![];
+		if self.peek() == Some('(') {
+			self.pos += 1;
+			if self.peek() != Some(')') {
+				loop {
+					let param = self.name()?;
+					self.expect('=')?;
+					args.push((param, self.spec()?));
+					if self.peek() != Some(',') {
+						break;
+					}
+					self.pos += 1;
+				}
+			}
+			self.expect(')')?;
+		}
+		Ok(Spec { name, args })
+	}
+
+	fn name(&mut self) -> Result<String> {
+		self.skip_whitespace();
+		let start = self.pos;
+		while self.pos < self.chars.len() && (self.chars[self.pos].is_alphanumeric() || "_-.".contains(self.chars[self.pos])) {
+			self.pos += 1;
+		}
+		match self.pos - start {
+			0 => Err(self.error("expected a name")),
+			_ => Ok(self.chars[start..self.pos].iter().collect()),
+		}
+	}
+
+	fn expect(&mut self, want: char) -> Result<()> {
+		match self.peek() {
+			Some(c) if c == want => {
+				self.pos += 1;
+				Ok(())
+			}
+			_ => Err(self.error(&format!("expected '{}'", want))),
+		}
+	}
+
+	/// The next non-whitespace character.
+	fn peek(&mut self) -> Option<char> {
+		self.skip_whitespace();
+		self.chars.get(self.pos).copied()
+	}
+
+	fn skip_whitespace(&mut self) {
+		while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+			self.pos += 1;
+		}
+	}
+
+	fn error(&self, msg: &str) -> anyhow::Error {
+		match self.chars.get(self.pos) {
+			Some(_) => format_err!("{} at column {}", msg, self.pos + 1),
+			None => format_err!("{} at the end", msg),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn spec(name: &str, args: Vec<(&str, Spec)>) -> Spec {
+		Spec {
+			name: name.into(),
+			args: args.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+		}
+	}
+
+	fn error(s: &str) -> String {
+		parse_engine(s).err().expect("should not parse").to_string()
+	}
+
+	#[test]
+	fn parse_spec() {
+		assert_eq!(Spec::parse("valid").unwrap(), spec("valid", vec![]));
+		assert_eq!(Spec::parse(" greedy() ").unwrap(), spec("greedy", vec![]));
+		assert_eq!(
+			Spec::parse("alphabeta( depth = 5, eval=strat1(x=1), tt=64mb )").unwrap(),
+			spec(
+				"alphabeta",
+				vec![
+					("depth", spec("5", vec![])),
+					("eval", spec("strat1", vec![("x", spec("1", vec![]))])),
+					("tt", spec("64mb", vec![])),
+				]
+			)
+		);
+
+		assert_eq!(Spec::parse("alphabeta(depth=5").unwrap_err().to_string(), "expected ')' at the end");
+		assert_eq!(Spec::parse("alphabeta(depth 5)").unwrap_err().to_string(), "expected '=' at column 17");
+		assert_eq!(Spec::parse("alphabeta)").unwrap_err().to_string(), "unexpected ')' at column 10");
+		assert!(Spec::parse("").is_err());
+		assert!(Spec::parse("alphabeta(,)").is_err());
+	}
+
+	#[test]
+	fn all_engines_build() {
+		let position = Position::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+		for algorithm in ALGORITHMS {
+			let engine = parse_engine(algorithm.name).unwrap();
+			let result = engine.search(&position, &SearchLimits::none(), None, &mut |_| ());
+			assert_eq!(result.root_moves.len(), 16, "{}", algorithm.name);
+			assert_eq!(
+				engine.eval_moves(position.board(), White),
+				result.exact_root_moves(),
+				"{}",
+				algorithm.name
+			);
+		}
+		for evaluation in EVALUATIONS {
+			parse_engine(&format!("greedy(eval={})", evaluation.name)).unwrap();
+		}
+		assert!(list_engines().contains("alphabeta"));
+	}
+
+	#[test]
+	fn parameters() {
+		let position = Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+		let eval = |engine: &dyn Engine| engine.eval_moves(position.board(), position.player());
+
+		let spec = parse_engine("alphabeta(depth=2, tt=1mb)").unwrap();
+		assert_eq!(eval(spec.as_ref()), eval(&AlphaBeta::new(2, material)));
+
+		let spec = parse_engine("palphabeta(depth=1,eval=strat2,qsearch=on)").unwrap();
+		let engine = ParAlphaBeta::new(1, heuristic2).with_options(SearchOptions::new(1000).with_quiescence());
+		assert_eq!(eval(spec.as_ref()), eval(&engine));
+
+		let spec = parse_engine("pvs(depth=2, window=25)").unwrap();
+		assert_eq!(eval(spec.as_ref()), eval(&Pvs::new(2, material).with_window(25)));
+
+		let spec = parse_engine("alphabeta(depth=3, nmp=on, lmr=on, lmrdiv=300, rfp=on)").unwrap();
+		let pruning = Pruning {
+			lmr: Some(Lmr { base: 75, divisor: 300 }),
+			futility: false,
+			..Pruning::all()
+		};
+		assert_eq!(
+			eval(spec.as_ref()),
+			eval(&AlphaBeta::new(3, material).with_options(SearchOptions::new(1).with_pruning(pruning)))
+		);
+
+		let spec = parse_engine("negamax(depth=1, eval=material)").unwrap();
+		assert_eq!(eval(spec.as_ref()), eval(&NegaMax::new(1, material)));
+
+		let result = parse_engine("alphabeta(depth=63, nodes=2000)")
+			.unwrap()
+			.search(&position, &SearchLimits::none(), None, &mut |_| ());
+		assert!(result.nodes < 3000, "{}", result.nodes);
+		assert!(parse_engine("palphabeta(time=2s, tt=1mb, qsearch=true)").is_ok());
+
+		// defaults apply where the spec gives nothing, and only to algorithms that have the parameter.
+		let defaults = [("depth", "3"), ("tt", "1mb")];
+		let spec = parse_engine_with_defaults("alphabeta(depth=2)", &defaults).unwrap();
+		assert_eq!(eval(spec.as_ref()), eval(&AlphaBeta::new(2, material)));
+		let spec = parse_engine_with_defaults("alphabeta", &defaults).unwrap();
+		assert_eq!(eval(spec.as_ref()), eval(&AlphaBeta::new(3, material)));
+		assert!(parse_engine_with_defaults("valid", &defaults).is_ok());
+		assert!(parse_engine_with_defaults("alphabeta", &[("tt", "lots")]).is_err());
+	}
+
+	#[test]
+	fn units() {
+		// parsed without building the engine, which would allocate the table.
+		let args = |spec: &str| Args::new("alphabeta", ALPHABETA_PARAMS, &Spec::parse(spec).unwrap().args).unwrap();
+		assert_eq!(args("alphabeta(tt=1GB)").int("tt"), 1024);
+		assert_eq!(args("alphabeta(tt=2g)").int("tt"), 2048);
+		assert_eq!(args("alphabeta(tt=64mb)").int("tt"), 64);
+		assert_eq!(args("alphabeta(tt=64)").int("tt"), 64);
+		assert_eq!(args("alphabeta(tt=4gb)").int("tt"), 4096);
+		assert_eq!(args("alphabeta(tt=0)").int("tt"), 1);
+		assert_eq!(args("alphabeta").int("tt"), 16);
+		assert_eq!(args("alphabeta(time=2s)").opt_int("time"), Some(2000));
+		assert_eq!(args("alphabeta(time=150ms)").opt_int("time"), Some(150));
+		assert_eq!(args("alphabeta").opt_int("time"), None);
+	}
+
+	#[test]
+	fn errors() {
+		assert_eq!(
+			error("alphabet"),
+			"engine 'alphabet': unknown algorithm 'alphabet', valid algorithms: valid, greedy, negamax, alphabeta, palphabeta, pvs"
+		);
+		assert_eq!(
+			error("alphabeta(dept=3)"),
+			"engine 'alphabeta(dept=3)': alphabeta has no parameter 'dept', valid parameters: depth, eval, qsearch, ordering, nmp, lmr, lmrbase, lmrdiv, futility, rfp, tt, time, nodes"
+		);
+		assert_eq!(error("valid(depth=3)"), "engine 'valid(depth=3)': valid has no parameters, got 'depth'");
+		assert_eq!(
+			error("negamax(depth=9)"),
+			"engine 'negamax(depth=9)': negamax: depth: expected int 0..=8, got '9'"
+		);
+		assert_eq!(
+			error("alphabeta(qsearch=yes)"),
+			"engine 'alphabeta(qsearch=yes)': alphabeta: qsearch: expected on|off, got 'yes'"
+		);
+		assert_eq!(
+			error("alphabeta(tt=lots)"),
+			"engine 'alphabeta(tt=lots)': alphabeta: tt: expected size (mb, gb), got 'lots'"
+		);
+		assert_eq!(
+			error("alphabeta(tt=5gb)"),
+			"engine 'alphabeta(tt=5gb)': alphabeta: tt: expected size (mb, gb) of at most 4096mb, got '5gb'"
+		);
+		assert!(error("alphabeta(eval=pst(pawns=on,pawntt=4097mb))").contains("pawntt: expected size (mb, gb) of at most 4096mb, got '4097mb'"));
+		assert!(error("alphabeta(eval=strat3)").contains("unknown evaluation 'strat3', valid evaluations: zero, material, strat1, strat2"));
+		assert!(error("alphabeta(eval=material(x=1))").contains("material has no parameters"));
+		assert!(error("alphabeta(depth=2,depth=3)").contains("given twice"));
+		assert!(error("alphabeta(depth=3(x=1))").contains("got parameters"));
+	}
+}
@@ -0,0 +1,1190 @@
+use super::internal::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::Relaxed};
+use std::time::{Duration, Instant};
+
+/// What all nodes of one alpha-beta search share.
+pub struct Search<'a, F, T> {
+	leaf_eval: &'a F,
+	tt: &'a T,
+	/// Positions visited so far.
+	nodes: AtomicU64,
+	/// Set once a limit is hit. All nodes then return at once, with meaningless values.
+	stopped: AtomicBool,
+	/// Set while `iterative_deepening` runs its first iteration, which must complete:
+	/// there, quiescence search plays out all captures regardless of the limits.
+	first_iteration: AtomicBool,
+	start: Instant,
+	time: Option<Duration>,
+	max_nodes: Option<u64>,
+	/// Set by someone else (e.g. the user) to stop the search.
+	stop_signal: Option<&'a AtomicBool>,
+	/// Zobrist keys of the positions played before the root, and of the root.
+	history: &'a [u64],
+	root: Option<u64>,
+	options: SearchOptions,
+	/// Killer moves, history and countermoves, for `SearchOptions::move_ordering`.
+	ordering: MoveOrdering,
+	/// For `Pruning::lmr`.
+	reductions: Option<Reductions>,
+	/// For `SearchStats`.
+	fail_highs: AtomicU64,
+	fail_lows: AtomicU64,
+	researches: AtomicU64,
+}
+
+/// How the search got to a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reached {
+	/// `alphabeta` was called on it from outside.
+	Entry,
+	Move(Move),
+	/// The side to move before passed (null-move pruning).
+	NullMove,
+}
+
+impl Reached {
+	fn last_move(self) -> Option<Move> {
+		match self {
+			Reached::Move(mv) => Some(mv),
+			_ => None,
+		}
+	}
+}
+
+/// The positions from the root to a node, to find repetitions.
+struct Line<'l> {
+	key: u64,
+	parent: Option<&'l Line<'l>>,
+}
+
+/// How to search, independent of the limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+	/// What a pawn is worth to the leaf evaluation, which has its own scale.
+	/// Used for margins, like in delta pruning.
+	pub pawn_value: i32,
+	/// Resolve captures at the leaves instead of evaluating positions in the middle of an exchange.
+	pub quiescence: bool,
+	/// Order moves with a `MovePicker`: hash move, good captures, killers, countermove, quiets by history, bad captures.
+	/// Otherwise: hash move, then by leaf evaluation of the resulting positions.
+	pub move_ordering: bool,
+	/// Principal variation search: all moves but the first of a node are searched with a null window,
+	/// and only searched again with the full window if they turn out better.
+	pub pvs: bool,
+	/// Null-move pruning, late move reductions, futility pruning.
+	pub pruning: Pruning,
+}
+
+impl Default for SearchOptions {
+	fn default() -> Self {
+		Self::new(1)
+	}
+}
+
+impl SearchOptions {
+	/// Defaults for a leaf evaluation that values a pawn `pawn_value`.
+	pub const fn new(pawn_value: i32) -> Self {
+		Self {
+			pawn_value,
+			quiescence: false,
+			move_ordering: true,
+			pvs: false,
+			pruning: Pruning::none(),
+		}
+	}
+
+	pub const fn with_quiescence(self) -> Self {
+		Self { quiescence: true, ..self }
+	}
+
+	pub const fn with_move_ordering(self, move_ordering: bool) -> Self {
+		Self { move_ordering, ..self }
+	}
+
+	pub const fn with_pvs(self) -> Self {
+		Self { pvs: true, ..self }
+	}
+
+	pub const fn with_pruning(self, pruning: Pruning) -> Self {
+		Self { pruning, ..self }
+	}
+}
+
+/// A search value the way a user (or UCI) wants to see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+	/// Centipawns.
+	Cp(i32),
+	/// Mate in this many moves, negative when being mated.
+	Mate(i32),
+}
+
+impl Score {
+	/// Score of a value of a search with a leaf evaluation that values a pawn `pawn_value`.
+	pub fn new(value: i32, pawn_value: i32) -> Self {
+		match value {
+			v if v > MATE_BOUND => Score::Mate((INF - v + 1) / 2),
+			v if v < -MATE_BOUND => Score::Mate(-(INF + v) / 2),
+			v => Score::Cp((v as i64 * 100 / pawn_value as i64) as i32),
+		}
+	}
+}
+
+impl fmt::Display for Score {
+	/// In UCI notation: `cp 25` or `mate -3`.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Score::Cp(cp) => write!(f, "cp {}", cp),
+			Score::Mate(moves) => write!(f, "mate {}", moves),
+		}
+	}
+}
+
+/// Delta pruning: captures that cannot raise the value to alpha, even winning this many pawns extra,
+/// are not searched.
+const DELTA_MARGIN: i32 = 2;
+
+/// Value of a drawn position.
+pub const DRAW: i32 = 0;
+
+/// The clock is only read every this many nodes.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+impl<'a, F, T> Search<'a, F, T>
+where
+	F: Fn(&Board, Color) -> i32,
+	T: TTable,
+{
+	/// Search without time or node limits.
+	pub fn new(leaf_eval: &'a F, tt: &'a T) -> Self {
+		Self::with_limits(leaf_eval, tt, &SearchLimits::none())
+	}
+
+	/// Search that stops when it runs out of the time or nodes in `limits`. The clock starts now.
+	/// (The depth limit is up to the caller.)
+	pub fn with_limits(leaf_eval: &'a F, tt: &'a T, limits: &SearchLimits) -> Self {
+		Self {
+			leaf_eval,
+			tt,
+			nodes: AtomicU64::new(0),
+			stopped: AtomicBool::new(false),
+			first_iteration: AtomicBool::new(false),
+			start: Instant::now(),
+			time: limits.time,
+			max_nodes: limits.nodes,
+			stop_signal: None,
+			history: &[],
+			root: None,
+			options: SearchOptions::default(),
+			ordering: MoveOrdering::new(),
+			reductions: None,
+			fail_highs: AtomicU64::new(0),
+			fail_lows: AtomicU64::new(0),
+			researches: AtomicU64::new(0),
+		}
+	}
+
+	/// Also stop as soon as `stop_signal` (if any) is set.
+	pub fn with_stop_signal(self, stop_signal: Option<&'a AtomicBool>) -> Self {
+		Self { stop_signal, ..self }
+	}
+
+	pub fn with_options(self, options: SearchOptions) -> Self {
+		Self {
+			options,
+			reductions: options.pruning.lmr.map(Reductions::new),
+			..self
+		}
+	}
+
+	/// The game so far, ending in the root `position`.
+	/// Positions repeating one of the game (or one earlier in the search) are draws.
+	pub fn with_history(self, position: &'a Position) -> Self {
+		Self {
+			history: position.history(),
+			root: Some(position.zobrist()),
+			..self
+		}
+	}
+
+	/// Positions visited so far.
+	pub fn nodes(&self) -> u64 {
+		self.nodes.load(Relaxed)
+	}
+
+	/// Aspiration window failures and re-searches so far.
+	pub fn stats(&self) -> SearchStats {
+		SearchStats {
+			fail_highs: self.fail_highs.load(Relaxed),
+			fail_lows: self.fail_lows.load(Relaxed),
+			researches: self.researches.load(Relaxed),
+		}
+	}
+
+	/// Has the search run out of time or nodes, or been stopped?
+	pub fn is_stopped(&self) -> bool {
+		self.stopped.load(Relaxed)
+	}
+
+	/// Time since the search started.
+	pub fn elapsed(&self) -> Duration {
+		self.start.elapsed()
+	}
+
+	/// Values of all legal moves for `player`, searching deeper and deeper
+	/// until the depth limit `max_depth` (in plies, counting the move itself), the time or the nodes run out.
+	///
+	/// `eval_iteration(root, depth)` must return the value of each root move (with the board it leads to),
+	/// searching `depth` plies below it. The values of an iteration that ran out of time or nodes are thrown away,
+	/// so the result is that of the last completed iteration. (The first iteration always completes).
+	///
+	/// Each iteration searches the best moves of the previous one first.
+	/// Below the root, the transposition table holds on to the best moves found earlier.
+	pub fn iterative_deepening<I>(&self, board: &Board, player: Color, max_depth: u32, eval_iteration: I) -> SmVec<(Move, i32)>
+	where
+		I: FnMut(&[(Move, Board)], u32) -> SmVec<i32>,
+	{
+		self.iterative_deepening_with_progress(board, player, max_depth, eval_iteration, |_, _| ())
+	}
+
+	/// `iterative_deepening`, calling `on_iteration(depth, values)` after each completed iteration,
+	/// with the depth in plies (counting the root move) and the values of the root moves, best first.
+	pub fn iterative_deepening_with_progress<I, P>(
+		&self,
+		board: &Board,
+		player: Color,
+		max_depth: u32,
+		mut eval_iteration: I,
+		mut on_iteration: P,
+	) -> SmVec<(Move, i32)>
+	where
+		I: FnMut(&[(Move, Board)], u32) -> SmVec<i32>,
+		P: FnMut(u32, &[(Move, i32)]),
+	{
+		let moves = board.legal_moves(player);
+		let mut root = moves.iter().map(|&mv| (mv, board.with_move(mv))).collect::<Vec<_>>();
+		let mut values = SmVec::<i32>::new(); // of the last completed iteration, in the order of `root`.
+
+		for depth in 0..u32::max(max_depth, 1) {
+			if depth > 0 && (self.is_stopped() || self.not_enough_time_left()) {
+				break;
+			}
+
+			self.first_iteration.store(depth == 0, Relaxed);
+			let iteration = eval_iteration(&root, depth);
+			self.first_iteration.store(false, Relaxed);
+			if depth > 0 && self.is_stopped() {
+				break;
+			}
+
+			let mut scored = root.into_iter().zip(iteration).collect::<Vec<_>>();
+			scored.sort_by_key(|(_, value)| -value); // stable: ties keep the previous order
+			(root, values) = scored.into_iter().unzip();
+			if !root.is_empty() {
+				on_iteration(
+					depth + 1,
+					&root.iter().map(|(mv, _)| *mv).zip(values.iter().copied()).collect::<SmVec<_>>(),
+				);
+			}
+
+			// mates are found at the lowest depth they exist, searching deeper cannot find a faster one.
+			if values.first().is_some_and(|&v| v > MATE_BOUND) {
+				break;
+			}
+		}
+
+		// back in move generation order.
+		moves
+			.iter()
+			.map(|&mv| {
+				let i = root.iter().position(|(m, _)| *m == mv).unwrap();
+				(mv, values[i])
+			})
+			.collect()
+	}
+
+	/// `mv` followed by the best replies stored in the transposition table, at most `max_len` moves.
+	pub fn principal_variation(&self, board: &Board, player: Color, mv: Move, max_len: usize) -> Vec<Move> {
+		let mut pv = vec![mv];
+		let mut board = board.with_move(mv);
+		let mut player = player.opposite();
+		while pv.len() < max_len {
+			let Some(mv) = self.tt.probe(board.zobrist_for(player)).and_then(|entry| entry.best_move) else {
+				break;
+			};
+			// another position may have taken the slot, or collided with this one's key.
+			if !board.legal_moves(player).contains(&mv) {
+				break;
+			}
+			pv.push(mv);
+			board = board.with_move(mv);
+			player = player.opposite();
+		}
+		pv
+	}
+
+	/// `iterative_deepening` for the side to move in `position`, as a SearchResult.
+	/// `progress` gets the result of each completed iteration.
+	pub fn run<I>(&self, position: &Position, max_depth: u32, eval_iteration: I, progress: &mut dyn FnMut(&SearchResult)) -> SearchResult
+	where
+		I: FnMut(&[(Move, Board)], u32) -> SmVec<i32>,
+	{
+		let (board, player) = (position.board(), position.player());
+		let mut last = (0, SmVec::new());
+		self.iterative_deepening_with_progress(board, player, max_depth, eval_iteration, |depth, values| {
+			last = (depth, values.into());
+			progress(&self.result(board, player, depth, values));
+		});
+		self.result(board, player, last.0, &last.1)
+	}
+
+	/// Result of an iteration `depth` plies deep, that gave `values` (best first).
+	fn result(&self, board: &Board, player: Color, depth: u32, values: &[(Move, i32)]) -> SearchResult {
+		let (best_move, value) = match values.first() {
+			Some(&(mv, value)) => (Some(mv), value),
+			None if board.is_check(player) => (None, -INF),
+			None => (None, DRAW),
+		};
+		// principal variation search only proves that the moves after the best are no better.
+		let bound = |i: usize| match self.options.pvs && i > 0 {
+			true => Bound::Upper,
+			false => Bound::Exact,
+		};
+		let moves = board.legal_moves(player);
+		let mut root_moves = values
+			.iter()
+			.enumerate()
+			.map(|(i, &(mv, value))| (mv, value, bound(i)))
+			.collect::<SmVec<_>>();
+		root_moves.sort_by_key(|(mv, _, _)| moves.iter().position(|m| m == mv));
+		SearchResult {
+			best_move,
+			score: Score::new(value, self.options.pawn_value),
+			pv: best_move
+				.map(|mv| self.principal_variation(board, player, mv, depth as usize))
+				.unwrap_or_default(),
+			depth,
+			nodes: self.nodes(),
+			stats: self.stats(),
+			time: self.elapsed(),
+			root_moves,
+			pawn_value: self.options.pawn_value,
+		}
+	}
+
+	/// Count a visited node, for searches other than `alphabeta`.
+	/// Returns whether the search must stop (but the first iteration must complete regardless).
+	pub fn visit(&self) -> bool {
+		let nodes = self.nodes.fetch_add(1, Relaxed) + 1;
+		self.out_of_budget(nodes)
+	}
+
+	/// Every iteration takes a multiple of the previous one.
+	/// If half the time is gone, the next one would not complete anyway.
+	fn not_enough_time_left(&self) -> bool {
+		self.time.is_some_and(|time| self.start.elapsed() * 2 > time)
+	}
+
+	/// Count a node, and stop once the time or nodes are up.
+	fn out_of_budget(&self, nodes: u64) -> bool {
+		if self.is_stopped() {
+			return true;
+		}
+		let out_of_nodes = self.max_nodes.is_some_and(|max| nodes >= max);
+		let out_of_time = nodes.is_multiple_of(TIME_CHECK_INTERVAL) && self.time.is_some_and(|time| self.start.elapsed() >= time);
+		let stop_signal = self.stop_signal.is_some_and(|signal| signal.load(Relaxed));
+		if out_of_nodes || out_of_time || stop_signal {
+			self.stopped.store(true, Relaxed);
+		}
+		out_of_nodes || out_of_time || stop_signal
+	}
+
+	/// Best move (if any) and value for `player`, searching `depth` plies deep.
+	/// `ply` counts the plies from the root, so that quicker mates get better values:
+	/// being mate at ply `n` is worth `-INF + n`. Stalemate is a draw (0),
+	/// and so is repeating a position (below the root), since the opponent could repeat it once more.
+	///
+	/// Once the search is stopped, returns nonsense (and stores nothing in the table).
+	pub fn alphabeta(&self, board: &Board, player: Color, alpha: i32, beta: i32, depth: u32, ply: u32) -> (Option<Move>, i32) {
+		self.alphabeta_(board, player, alpha, beta, depth, ply, None, Reached::Entry)
+	}
+
+	/// `alphabeta` for a node reached as in `reached`, through the positions on `line`.
+	#[allow(clippy::too_many_arguments)]
+	fn alphabeta_(
+		&self,
+		board: &Board,
+		player: Color,
+		alpha: i32,
+		beta: i32,
+		depth: u32,
+		ply: u32,
+		line: Option<&Line>,
+		reached: Reached,
+	) -> (Option<Move>, i32) {
+		let nodes = self.nodes.fetch_add(1, Relaxed) + 1;
+
+		// must stop iteration so that we would not trade a king for a king :-)
+		// (losing the king on the previous ply is as bad as being mate there.)
+		if !board.has_king(player) {
+			return (None, -INF + ply as i32 - 1);
+		}
+
+		let key = board.zobrist_for(player);
+		if ply > 0 && self.is_repetition(key, line) {
+			return (None, DRAW);
+		}
+
+		if depth == 0 {
+			return match self.options.quiescence {
+				true => (None, self.quiescence(board, player, alpha, beta, ply)),
+				false => (None, (self.leaf_eval)(board, player)),
+			};
+		}
+
+		if self.out_of_budget(nodes) {
+			return (None, 0);
+		}
+
+		let mut tt_move = None;
+		if let Some(entry) = self.tt.probe(key) {
+			let entry = TTEntry {
+				value: value_from_tt(entry.value, ply),
+				..entry
+			};
+			if entry.cutoff(depth, alpha, beta) {
+				return (entry.best_move, entry.value);
+			}
+			tt_move = entry.best_move;
+		}
+
+		let line = Line { key, parent: line };
+		let pruning = self.options.pruning;
+		let in_check = pruning.any() && board.is_check(player);
+		// only where pruning may use it, and never in check, where it means little.
+		let static_eval = match pruning.any() && !in_check && ply > 0 {
+			true => Some((self.leaf_eval)(board, player)),
+			false => None,
+		};
+		// pruning cannot prove or refute mates.
+		let (alpha_no_mate, beta_no_mate) = (alpha.abs() < MATE_BOUND, beta.abs() < MATE_BOUND);
+
+		if let Some(eval) = static_eval.filter(|_| pruning.reverse_futility && depth <= FUTILITY_MAX_DEPTH && beta_no_mate) {
+			if eval - futility_margin(depth, self.options.pawn_value) >= beta {
+				return (None, eval);
+			}
+		}
+
+		if pruning.null_move
+			&& depth >= REDUCTION_MIN_DEPTH
+			&& reached != Reached::NullMove
+			&& beta_no_mate
+			&& static_eval.is_some_and(|eval| eval >= beta)
+			&& has_non_pawn_material(board, player)
+		{
+			let depth = depth.saturating_sub(1 + null_move_reduction(depth));
+			let (_, value) = self.alphabeta_(
+				&board.with_null_move(),
+				player.opposite(),
+				-beta,
+				-beta + 1,
+				depth,
+				ply + 1,
+				Some(&line),
+				Reached::NullMove,
+			);
+			if self.is_stopped() {
+				return (None, 0);
+			}
+			// a mate found after passing may not exist otherwise.
+			if -value >= beta {
+				return (None, if -value > MATE_BOUND { beta } else { -value });
+			}
+		}
+
+		let futile = static_eval.is_some_and(|eval| {
+			pruning.futility && depth <= FUTILITY_MAX_DEPTH && alpha_no_mate && eval + futility_margin(depth, self.options.pawn_value) <= alpha
+		});
+
+		let moves = match self.options.move_ordering {
+			true => Moves::Staged(MovePicker::new(board, player, tt_move, &self.ordering, ply, reached.last_move())),
+			false => Moves::Sorted(self.sorted_moves(board, player, depth, tt_move).into_iter()),
+		};
+
+		let mut best_value = -INF + ply as i32;
+		let mut best_move = None;
+		let mut alpha = alpha;
+		let original_alpha = alpha;
+		let mut legal = 0; // including moves pruned
+		let mut quiets = SmVec::new(); // searched without a cutoff
+		for mv in moves {
+			legal += 1;
+			let quiet = !is_capture(board, mv);
+			let child = board.with_move(mv);
+			// quiet moves that do not give check, after the first.
+			let late_quiet = || legal > 1 && quiet && !in_check && !child.is_check(player.opposite());
+
+			if futile && late_quiet() {
+				continue;
+			}
+			let reduction = match pruning.lmr {
+				Some(_) if depth >= REDUCTION_MIN_DEPTH && legal > LMR_FULL_DEPTH_MOVES && late_quiet() => {
+					self.reductions.as_ref().map_or(0, |r| r.get(depth, legal)).min(depth - 2)
+				}
+				_ => 0,
+			};
+
+			let null_window = self.options.pvs && legal > 1;
+			let value = self.child_value(&child, player, mv, alpha, beta, depth - 1, ply + 1, Some(&line), null_window, reduction);
+			if self.is_stopped() {
+				return (None, 0);
+			}
+			if value >= best_value {
+				best_value = value;
+				best_move = Some(mv);
+			}
+
+			alpha = i32::max(alpha, value);
+			if alpha >= beta {
+				if quiet && self.options.move_ordering {
+					self.ordering.cutoff(player, ply, depth, reached.last_move(), mv, &quiets);
+				}
+				break;
+			}
+			if quiet {
+				quiets.push(mv);
+			}
+		}
+
+		if legal == 0 {
+			return match board.is_check(player) {
+				true => (None, -INF + ply as i32),
+				false => (None, DRAW),
+			};
+		}
+
+		let bound = match best_value {
+			v if v <= original_alpha => Bound::Upper,
+			v if v >= beta => Bound::Lower,
+			_ => Bound::Exact,
+		};
+		self.tt.store(
+			key,
+			TTEntry {
+				depth,
+				bound,
+				value: value_to_tt(best_value, ply),
+				best_move,
+			},
+		);
+
+		(best_move, best_value)
+	}
+
+	/// Value for `player` of `child`, the board after their move `mv`, searching `depth` plies below it.
+	/// With `null_window`, first only find out whether the value is above `alpha`, and if so,
+	/// search again with the full window (unless the value is at least `beta` anyway).
+	/// With a `reduction`, first find that out searching that many plies less deep.
+	#[allow(clippy::too_many_arguments)]
+	fn child_value(
+		&self,
+		child: &Board,
+		player: Color,
+		mv: Move,
+		alpha: i32,
+		beta: i32,
+		depth: u32,
+		ply: u32,
+		line: Option<&Line>,
+		null_window: bool,
+		reduction: u32,
+	) -> i32 {
+		let opponent = player.opposite();
+		if reduction > 0 {
+			let value = -self
+				.alphabeta_(child, opponent, -alpha - 1, -alpha, depth - reduction, ply, line, Reached::Move(mv))
+				.1;
+			if value <= alpha || self.is_stopped() {
+				return value;
+			}
+		}
+		if null_window && beta > alpha + 1 {
+			let value = -self
+				.alphabeta_(child, opponent, -alpha - 1, -alpha, depth, ply, line, Reached::Move(mv))
+				.1;
+			if value <= alpha || value >= beta || self.is_stopped() {
+				return value;
+			}
+			self.researches.fetch_add(1, Relaxed);
+		}
+		-self.alphabeta_(child, opponent, -beta, -alpha, depth, ply, line, Reached::Move(mv)).1
+	}
+
+	/// Values of the `root` moves of `player` (with the boards they lead to), searching `depth` plies below each,
+	/// for `iterative_deepening`. Principal variation search within the window `(alpha, beta)`:
+	/// the first root move gets the full window, the others a null window.
+	///
+	/// Only the value of the best move is exact, and only if it is inside the window.
+	/// The others are upper bounds (at most the best value). After a cutoff at `beta`, the remaining moves are not searched (-INF).
+	pub fn pvs_root(&self, root: &[(Move, Board)], player: Color, depth: u32, alpha: i32, beta: i32) -> SmVec<i32> {
+		let mut alpha = alpha;
+		let mut values = SmVec::new();
+		for (i, (mv, board)) in root.iter().enumerate() {
+			let value = match alpha >= beta {
+				true => -INF,
+				false => self.child_value(board, player, *mv, alpha, beta, depth, 1, None, i > 0, 0),
+			};
+			values.push(value);
+			alpha = i32::max(alpha, value);
+		}
+		values
+	}
+
+	/// `pvs_root` with an aspiration window (https://www.chessprogramming.org/Aspiration_Windows):
+	/// the value of the best move will likely be close to its value `previous` of the last iteration,
+	/// so search with a window of `previous ± window` first. When the best value falls outside,
+	/// widen the window on that side, twice as much each time, and search again.
+	///
+	/// Without a previous value, or when it is a mate, search with the full window.
+	pub fn aspiration(&self, root: &[(Move, Board)], player: Color, depth: u32, previous: Option<i32>, window: i32) -> SmVec<i32> {
+		let mut delta = i32::max(window, 1);
+		let (mut alpha, mut beta) = match previous {
+			Some(value) if value.abs() < MATE_BOUND => (value - delta, value + delta),
+			_ => (-INF, INF),
+		};
+		loop {
+			let values = self.pvs_root(root, player, depth, alpha, beta);
+			let best = values.iter().copied().max().unwrap_or(-INF);
+			if self.is_stopped() || values.is_empty() {
+				return values;
+			}
+			delta = delta.saturating_mul(2);
+			if best <= alpha && alpha > -INF {
+				self.fail_lows.fetch_add(1, Relaxed);
+				alpha = widen(best.saturating_sub(delta));
+			} else if best >= beta && beta < INF {
+				self.fail_highs.fetch_add(1, Relaxed);
+				beta = widen(best.saturating_add(delta));
+			} else {
+				return values;
+			}
+			self.researches.fetch_add(1, Relaxed);
+		}
+	}
+
+	/// Legal moves in the order of the search without `SearchOptions::move_ordering`:
+	/// `tt_move` first, then the moves leading to the worst positions for the opponent
+	/// by the leaf evaluation (only at least two plies above the leaves, where it is worth the cost).
+	fn sorted_moves(&self, board: &Board, player: Color, depth: u32, tt_move: Option<Move>) -> SmVec<Move> {
+		let mut moves = board.legal_moves(player);
+		if depth > 1 {
+			moves.sort_by_cached_key(|&mv| (self.leaf_eval)(&board.with_move(mv), player));
+		}
+		if let Some(i) = moves.iter().position(|mv| Some(*mv) == tt_move) {
+			moves[..=i].rotate_right(1);
+		}
+		moves
+	}
+
+	/// Has the position with Zobrist `key` occurred before, in the game or on the `line` leading to it?
+	fn is_repetition(&self, key: u64, line: Option<&Line>) -> bool {
+		let mut line = line;
+		while let Some(l) = line {
+			if l.key == key {
+				return true;
+			}
+			line = l.parent;
+		}
+		self.root == Some(key) || self.history.contains(&key)
+	}
+
+	/// Value for `player` once the captures have been played out (https://www.chessprogramming.org/Quiescence_Search).
+	///
+	/// The player may "stand pat": decline to capture and take the static evaluation, which is then a lower bound.
+	/// Captures are searched in order of static exchange evaluation, captures that lose material not at all.
+	/// In check, there is no standing pat: all evasions are searched, and without any the player is mate (at `ply`).
+	///
+	/// Like `alphabeta`, stops when out of budget, returning nonsense, except in the first iteration of `iterative_deepening`.
+	fn quiescence(&self, board: &Board, player: Color, alpha: i32, beta: i32, ply: u32) -> i32 {
+		let in_check = board.is_check(player);
+		let stand_pat = match in_check {
+			true => -INF + ply as i32,
+			false => (self.leaf_eval)(board, player),
+		};
+		if stand_pat >= beta {
+			return stand_pat;
+		}
+		let moves = match in_check {
+			true => board.legal_moves(player),
+			false => captures(board, player),
+		};
+
+		let mut best_value = stand_pat;
+		let mut alpha = i32::max(alpha, stand_pat);
+		for mv in moves {
+			if !in_check && stand_pat + (material_gain(board, mv) + DELTA_MARGIN) * self.options.pawn_value < alpha {
+				continue;
+			}
+			if !in_check && board.see(mv) < 0 {
+				continue;
+			}
+
+			let nodes = self.nodes.fetch_add(1, Relaxed) + 1;
+			if !self.first_iteration.load(Relaxed) && self.out_of_budget(nodes) {
+				return 0;
+			}
+			let value = -self.quiescence(&board.with_move(mv), player.opposite(), -beta, -alpha, ply + 1);
+			best_value = i32::max(best_value, value);
+			alpha = i32::max(alpha, value);
+			if alpha >= beta {
+				break;
+			}
+		}
+		best_value
+	}
+}
+
+/// An aspiration window bound, or the full window once it reaches mate values.
+fn widen(bound: i32) -> i32 {
+	match bound {
+		b if b > MATE_BOUND => INF,
+		b if b < -MATE_BOUND => -INF,
+		b => b,
+	}
+}
+
+/// The moves of a node, in the order they are searched.
+/// Only ever on the stack of one node, so the size of the picker does not matter.
+#[allow(clippy::large_enum_variant)]
+enum Moves<'p> {
+	Staged(MovePicker<'p>),
+	Sorted(smallvec::IntoIter<[Move; 32]>),
+}
+
+impl Iterator for Moves<'_> {
+	type Item = Move;
+
+	fn next(&mut self) -> Option<Move> {
+		match self {
+			Moves::Staged(picker) => picker.next(),
+			Moves::Sorted(moves) => moves.next(),
+		}
+	}
+}
+
+/// Legal captures and queen promotions, best static exchange evaluation (`Board::see`) first,
+/// and for the same evaluation, least valuable attacker first.
+pub fn captures(board: &Board, player: Color) -> SmVec<Move> {
+	let opponent = board.all_pieces(player.opposite());
+	let mut captures = board
+		.legal_moves(player)
+		.into_iter()
+		.filter(|mv| opponent & (1 << mv.to.index()) != 0 || is_en_passant(board, *mv) || mv.promotion.is_some_and(|p| p.is_queen()))
+		.collect::<SmVec<_>>();
+	captures.sort_by_cached_key(|&mv| -(16 * board.see(mv) - mv.piece.value().abs()));
+	captures
+}
+
+/// Pawns won by playing `mv` (not counting any recapture).
+fn material_gain(board: &Board, mv: Move) -> i32 {
+	let victim = match is_en_passant(board, mv) {
+		true => 1,
+		false => board.at(mv.to).value().abs(),
+	};
+	let promotion = mv.promotion.map(|p| p.value().abs() - 1).unwrap_or(0);
+	victim + promotion
+}
+
+fn is_en_passant(board: &Board, mv: Move) -> bool {
+	mv.piece.is_pawn() && Some(mv.to) == board.en_passant() && mv.from.col() != mv.to.col()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Iterative deepening with a sequential search below the root.
+	fn iterate<T: TTable>(search: &Search<impl Fn(&Board, Color) -> i32, T>, board: &Board, player: Color, max_depth: u32) -> SmVec<(Move, i32)> {
+		search.iterative_deepening(board, player, max_depth, |root, depth| {
+			root.iter()
+				.map(|(_, board)| -search.alphabeta(board, player.opposite(), -INF, INF, depth, 1).1)
+				.collect()
+		})
+	}
+
+	#[test]
+	fn depth_limit() {
+		let board = Board::from_str(
+			r"
+		Q . . p p p . k
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . K
+		",
+		)
+		.unwrap();
+
+		for max_depth in 1..=4 {
+			let search = Search::new(&material, &NoTable);
+			let have = iterate(&search, &board, White, max_depth);
+			let moves = board.legal_moves(White);
+			assert_eq!(have.iter().map(|(mv, _)| *mv).collect::<SmVec<_>>(), moves);
+			for (mv, value) in have {
+				let want = -alphabeta(&board.with_move(mv), Black, &material, max_depth - 1);
+				assert_eq!(value, want, "{} depth {}", mv, max_depth);
+			}
+		}
+	}
+
+	#[test]
+	fn node_limit() {
+		let board = Board::from_fen(STARTING_FEN).unwrap();
+		let search = Search::with_limits(&material, &NoTable, &SearchLimits::nodes(10_000));
+		let values = iterate(&search, &board, White, MAX_DEPTH);
+		assert_eq!(values.len(), 20);
+		assert!(search.is_stopped());
+		assert!(search.nodes() < 11_000, "{}", search.nodes());
+	}
+
+	#[test]
+	fn time_limit() {
+		let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+		let tt = TranspositionTable::new(1);
+		let search = Search::with_limits(&material, &tt, &SearchLimits::time(Duration::from_millis(100)));
+		let start = Instant::now();
+		let values = iterate(&search, &board, White, MAX_DEPTH);
+		assert_eq!(values.len(), 48);
+		assert!(start.elapsed() < Duration::from_secs(1), "{:?}", start.elapsed());
+	}
+
+	#[test]
+	fn finds_mate() {
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. R . . . . . .
+		R . . . K . . .
+		",
+		)
+		.unwrap();
+
+		// mate in 2 (Rb7, then Ra8#): found without searching any deeper.
+		let tt = TranspositionTable::new(1);
+		let search = Search::new(&material, &tt);
+		let values = iterate(&search, &board, White, MAX_DEPTH);
+		assert_eq!(best_value(&values), Some(INF - 3));
+		assert!(!search.is_stopped());
+	}
+
+	#[test]
+	fn stalemate() {
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . K .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . Q . .
+		",
+		)
+		.unwrap();
+
+		// Qf7 stalemates, a draw instead of being a queen up. (Qf8 mates.)
+		let search = Search::new(&material, &NoTable);
+		let values = iterate(&search, &board, White, 2);
+		let value = |mv: &str| values.iter().find(|(m, _)| m.to_string() == mv).unwrap().1;
+		assert_eq!(value("f1f7"), DRAW);
+		assert_eq!(value("f1f6"), 9);
+		assert_eq!(value("f1f8"), INF - 1);
+	}
+
+	#[test]
+	fn repetition() {
+		// Black, a queen down, can repeat the starting position: a draw.
+		let mut position = Position::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+		for san in ["Nf3", "Nf6", "Ng1"] {
+			position.play(position.parse_san(san).unwrap());
+		}
+		for max_depth in 1..=3 {
+			let search = Search::new(&material, &NoTable).with_history(&position);
+			let values = iterate(&search, position.board(), Black, max_depth);
+			let (best, value) = values.iter().max_by_key(|(_, v)| *v).unwrap();
+			assert_eq!((best.to_string().as_str(), *value), ("f6g8", DRAW), "depth {}", max_depth);
+		}
+
+		// without the game history, the repetition is not seen.
+		let search = Search::new(&material, &NoTable);
+		assert_eq!(best_value(&iterate(&search, position.board(), Black, 1)), Some(-9));
+	}
+
+	#[test]
+	fn quiescence() {
+		let board = Board::from_str(
+			r"
+		. . . . . . k .
+		. . . . . p . .
+		. . . . p . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . Q . . .
+		. . . . . . . .
+		. . . . . . . K
+		",
+		)
+		.unwrap();
+
+		// without quiescence, white greedily takes a pawn at the horizon.
+		let plain = Search::new(&material, &NoTable);
+		assert_eq!(plain.alphabeta(&board, White, -INF, INF, 1, 0).1, 8);
+
+		// with quiescence, white sees the recapture even at depth 0.
+		let search = Search::new(&material, &NoTable).with_options(SearchOptions::new(1).with_quiescence());
+		for depth in 0..=1 {
+			assert_eq!(search.alphabeta(&board, White, -INF, INF, depth, 0).1, 7, "depth {}", depth);
+		}
+	}
+
+	#[test]
+	fn quiescence_in_check() {
+		// white is two pawns up, but mate: no standing pat in check.
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. P P P P P . .
+		. . . . . . . .
+		. . . . . . P P
+		r . . . . . . K
+		",
+		)
+		.unwrap();
+		let search = Search::new(&material, &NoTable).with_options(SearchOptions::new(1).with_quiescence());
+		assert_eq!(search.alphabeta(&board, White, -INF, INF, 0, 0).1, -INF);
+		assert_eq!(search.alphabeta(&board, White, -INF, INF, 0, 3).1, -INF + 3);
+
+		// with an escape, the value is that after the evasion.
+		let mut board = board;
+		board.set(pos(1, 7), Square::Empty);
+		assert_eq!(search.alphabeta(&board, White, -INF, INF, 0, 0).1, 1);
+	}
+
+	#[test]
+	fn quiescence_node_limit() {
+		let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+		let options = SearchOptions::new(1).with_quiescence();
+		let unlimited = Search::new(&material, &NoTable).with_options(options);
+		unlimited.alphabeta(&board, White, -INF, INF, 1, 0);
+		assert!(unlimited.nodes() > 150, "{}", unlimited.nodes());
+
+		// the 48 nodes at the horizon are within the limit, the captures below it are not.
+		let limited = Search::with_limits(&material, &NoTable, &SearchLimits::nodes(100)).with_options(options);
+		limited.alphabeta(&board, White, -INF, INF, 1, 0);
+		assert!(limited.is_stopped());
+		assert!(limited.nodes() <= 100, "{}", limited.nodes());
+
+		// but the first iteration completes: its values are those of the full quiescence search.
+		let want = iterate(&unlimited, &board, White, 1);
+		let limited = Search::with_limits(&material, &NoTable, &SearchLimits::nodes(100)).with_options(options);
+		assert_eq!(iterate(&limited, &board, White, MAX_DEPTH), want);
+		assert!(limited.nodes() > 150, "{}", limited.nodes());
+	}
+
+	#[test]
+	fn mvv_lva() {
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . . .
+		. . q . n . . .
+		. . . P . . . .
+		. . . . . . . .
+		. . Q . . . . .
+		. . . . . . . K
+		",
+		)
+		.unwrap();
+
+		let captures = captures(&board, White).iter().map(|mv| mv.to_string()).collect::<Vec<_>>();
+		assert_eq!(captures, ["d4c5", "c2c5", "d4e5"]);
+	}
+
+	#[test]
+	fn losing_captures_last() {
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . . .
+		. p . . . . . p
+		. . . . . . r .
+		. . N . . . . .
+		. . . . . . . .
+		. . . Q . . . K
+		",
+		)
+		.unwrap();
+
+		// the rook is worth more, but defended.
+		let captures = captures(&board, White).iter().map(|mv| mv.to_string()).collect::<Vec<_>>();
+		assert_eq!(captures, ["c3b5", "d1g4"]);
+	}
+
+	#[test]
+	fn move_ordering() {
+		// the same values with fewer nodes than ordering by leaf evaluation.
+		let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+		let run = |move_ordering| {
+			let tt = TranspositionTable::new(1);
+			let options = SearchOptions::new(1).with_move_ordering(move_ordering);
+			let search = Search::new(&material, &tt).with_options(options);
+			(iterate(&search, &board, White, 3), search.nodes())
+		};
+		let (sorted, sorted_nodes) = run(false);
+		let (staged, staged_nodes) = run(true);
+		assert_eq!(best_value(&staged), best_value(&sorted));
+		assert!(staged_nodes < sorted_nodes, "{} >= {}", staged_nodes, sorted_nodes);
+	}
+
+	#[test]
+	fn pruning() {
+		let kinds = [
+			Pruning {
+				null_move: true,
+				..Pruning::none()
+			},
+			Pruning {
+				lmr: Some(Lmr::new()),
+				..Pruning::none()
+			},
+			Pruning {
+				futility: true,
+				..Pruning::none()
+			},
+			Pruning {
+				reverse_futility: true,
+				..Pruning::none()
+			},
+			Pruning::all(),
+		];
+		let run = |board: &Board, pruning, depth| {
+			let tt = TranspositionTable::new(1);
+			let search = Search::new(&material, &tt).with_options(SearchOptions::new(1).with_pruning(pruning));
+			(best_value(&iterate(&search, board, White, depth)), search.nodes())
+		};
+
+		// mate in 2 (Rb7, then Ra8#) is still found.
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		R R . . K . . .
+		",
+		)
+		.unwrap();
+		for pruning in kinds {
+			assert_eq!(run(&board, pruning, 4).0, Some(INF - 3), "{:?}", pruning);
+		}
+
+		// fewer nodes (null-move pruning needs at least 3 plies left below a node with a finite beta).
+		let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+		let (_, nodes) = run(&board, Pruning::none(), 5);
+		for pruning in kinds {
+			let (_, pruned) = run(&board, pruning, 5);
+			assert!(pruned < nodes, "{:?}: {} >= {}", pruning, pruned, nodes);
+		}
+	}
+
+	#[test]
+	fn no_null_move_with_pawns_only() {
+		// whoever is to move here loses the pawn or lets the other king in: passing would be better.
+		let board = Board::from_fen("8/8/8/2k5/2P5/2K5/8/8 w - - 0 1").unwrap();
+		let run = |pruning| {
+			let search = Search::new(&material, &NoTable).with_options(SearchOptions::new(1).with_pruning(pruning));
+			let values = iterate(&search, &board, White, 5);
+			(values, search.nodes())
+		};
+		let null_move = Pruning {
+			null_move: true,
+			..Pruning::none()
+		};
+		assert_eq!(run(null_move), run(Pruning::none()));
+	}
+
+	#[test]
+	fn score() {
+		assert_eq!(Score::new(250, 1000).to_string(), "cp 25");
+		assert_eq!(Score::new(-3, 1).to_string(), "cp -300");
+		assert_eq!(Score::new(INF - 1, 1), Score::Mate(1));
+		assert_eq!(Score::new(INF - 3, 1000), Score::Mate(2));
+		assert_eq!(Score::new(-INF + 2, 1), Score::Mate(-1));
+		assert_eq!(Score::new(-INF + 4, 1).to_string(), "mate -2");
+	}
+
+	#[test]
+	fn principal_variation() {
+		let board = Board::from_str(
+			r"
+		. . . . . . . k
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. . . . . . . .
+		. R . . . . . .
+		R . . . K . . .
+		",
+		)
+		.unwrap();
+
+		let tt = TranspositionTable::new(1);
+		let search = Search::new(&material, &tt);
+		let mut reported = vec![];
+		let values = search.iterative_deepening_with_progress(
+			&board,
+			White,
+			MAX_DEPTH,
+			|root, depth| {
+				root.iter()
+					.map(|(_, board)| -search.alphabeta(board, Black, -INF, INF, depth, 1).1)
+					.collect()
+			},
+			|depth, values| reported.push((depth, values[0])),
+		);
+		// the mated position must be searched (not evaluated) too: mate in 2 moves needs 4 plies.
+		assert_eq!(reported.iter().map(|(depth, _)| *depth).collect::<Vec<_>>(), [1, 2, 3, 4]);
+		let (best, value) = reported[3].1;
+		assert_eq!(best_value(&values), Some(value));
+		assert_eq!(Score::new(value, 1), Score::Mate(2));
+
+		let pv = search.principal_variation(&board, White, best, 4);
+		assert_eq!(pv.len(), 3);
+		let mut board = board;
+		let mut player = White;
+		for mv in pv {
+			assert!(board.legal_moves(player).contains(&mv));
+			board = board.with_move(mv);
+			player = player.opposite();
+		}
+		assert!(board.is_check(Black) && board.legal_moves(Black).is_empty());
+	}
+}
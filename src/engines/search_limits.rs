@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+/// When to stop searching. The search stops at whichever limit is hit first.
+/// Without any limits, it stops at `MAX_DEPTH`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+	/// Maximum depth in plies, counting the move to be played.
+	pub depth: Option<u32>,
+	/// Wall-clock time.
+	pub time: Option<Duration>,
+	/// Maximum number of positions visited.
+	pub nodes: Option<u64>,
+}
+
+/// Search depth when no other limit applies.
+pub const MAX_DEPTH: u32 = 64;
+
+impl SearchLimits {
+	/// No limits (other than `MAX_DEPTH`).
+	pub fn none() -> Self {
+		Self::default()
+	}
+
+	pub fn depth(depth: u32) -> Self {
+		Self::none().with_depth(depth)
+	}
+
+	pub fn time(time: Duration) -> Self {
+		Self::none().with_time(time)
+	}
+
+	pub fn nodes(nodes: u64) -> Self {
+		Self::none().with_nodes(nodes)
+	}
+
+	pub fn with_depth(self, depth: u32) -> Self {
+		Self { depth: Some(depth), ..self }
+	}
+
+	pub fn with_time(self, time: Duration) -> Self {
+		Self { time: Some(time), ..self }
+	}
+
+	pub fn with_nodes(self, nodes: u64) -> Self {
+		Self { nodes: Some(nodes), ..self }
+	}
+
+	/// The limits of both `self` and `other`: stop at whichever is hit first.
+	pub fn both(&self, other: &SearchLimits) -> Self {
+		fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+			match (a, b) {
+				(Some(a), Some(b)) => Some(a.min(b)),
+				(a, b) => a.or(b),
+			}
+		}
+		Self {
+			depth: min(self.depth, other.depth),
+			time: min(self.time, other.time),
+			nodes: min(self.nodes, other.nodes),
+		}
+	}
+
+	/// Depth limit, or `MAX_DEPTH` if none.
+	pub fn max_depth(&self) -> u32 {
+		self.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn both() {
+		let a = SearchLimits::depth(5).with_time(Duration::from_secs(2));
+		let b = SearchLimits::depth(3).with_nodes(1000);
+		assert_eq!(
+			a.both(&b),
+			SearchLimits {
+				depth: Some(3),
+				time: Some(Duration::from_secs(2)),
+				nodes: Some(1000),
+			}
+		);
+		assert_eq!(a.both(&SearchLimits::none()), a);
+		assert_eq!(SearchLimits::none().both(&SearchLimits::none()).max_depth(), MAX_DEPTH);
+	}
+}
@@ -0,0 +1,344 @@
+use super::internal::*;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Search results by Zobrist key, so that positions reached by different move orders are searched only once.
+pub trait TTable {
+	fn probe(&self, key: u64) -> Option<TTEntry>;
+	fn store(&self, key: u64, entry: TTEntry);
+}
+
+/// What a search found out about a position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TTEntry {
+	/// Remaining depth that was searched.
+	pub depth: u32,
+	pub bound: Bound,
+	/// Value for the side to move. Mate values are relative to this position, see `value_to_tt`.
+	pub value: i32,
+	pub best_move: Option<Move>,
+}
+
+/// How a value (of a `TTEntry`, or one of the `SearchResult::root_moves`) relates to the true value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+	Exact,
+	/// The true value is at least this (the search failed high).
+	Lower,
+	/// The true value is at most this (the search failed low).
+	Upper,
+}
+
+impl TTEntry {
+	/// Can this entry stand in for a search of `depth` with window `alpha..beta`?
+	pub fn cutoff(&self, depth: u32, alpha: i32, beta: i32) -> bool {
+		self.depth >= depth
+			&& match self.bound {
+				Bound::Exact => true,
+				Bound::Lower => self.value >= beta,
+				Bound::Upper => self.value <= alpha,
+			}
+	}
+}
+
+/// Values beyond this (either sign) mean mate.
+pub const MATE_BOUND: i32 = INF - 1000;
+
+/// Mate values count plies from the root.
+/// Stored in the table, they count plies from the position itself,
+/// so that they stay valid when the position is reached at another ply.
+pub fn value_to_tt(value: i32, ply: u32) -> i32 {
+	match value {
+		v if v > MATE_BOUND => v + ply as i32,
+		v if v < -MATE_BOUND => v - ply as i32,
+		v => v,
+	}
+}
+
+/// Inverse of `value_to_tt`.
+pub fn value_from_tt(value: i32, ply: u32) -> i32 {
+	match value {
+		v if v > MATE_BOUND => v - ply as i32,
+		v if v < -MATE_BOUND => v + ply as i32,
+		v => v,
+	}
+}
+
+/// Single-threaded transposition table.
+/// Fixed size, a power of two number of entries. When two positions map to the same slot, the newest wins,
+/// unless the older is the same position searched deeper.
+pub struct TranspositionTable {
+	slots: Vec<Cell<[u64; 2]>>,
+	mask: u64,
+}
+
+impl TranspositionTable {
+	/// Table taking up (at most) `mb` megabytes.
+	pub fn new(mb: usize) -> Self {
+		let len = num_slots(mb);
+		Self {
+			slots: (0..len).map(|_| Cell::new([0, 0])).collect(),
+			mask: len as u64 - 1,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.slots.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.slots.is_empty()
+	}
+
+	pub fn clear(&self) {
+		self.slots.iter().for_each(|slot| slot.set([0, 0]));
+	}
+}
+
+impl TTable for TranspositionTable {
+	#[inline]
+	fn probe(&self, key: u64) -> Option<TTEntry> {
+		let [stored_key, data] = self.slots[(key & self.mask) as usize].get();
+		match stored_key == key && data != 0 {
+			true => Some(unpack(data)),
+			false => None,
+		}
+	}
+
+	#[inline]
+	fn store(&self, key: u64, entry: TTEntry) {
+		let slot = &self.slots[(key & self.mask) as usize];
+		let [stored_key, data] = slot.get();
+		if replace(stored_key, data, key, &entry) {
+			slot.set([key, pack(&entry)]);
+		}
+	}
+}
+
+/// Transposition table that can be shared between threads, without locks.
+///
+/// Each slot holds the key xor-ed with the data, and the data.
+/// A slot torn by two threads writing at the same time then no longer matches the key,
+/// and is ignored (https://www.chessprogramming.org/Shared_Hash_Table#Lockless).
+pub struct SharedTranspositionTable {
+	slots: Vec<[AtomicU64; 2]>,
+	mask: u64,
+}
+
+impl SharedTranspositionTable {
+	/// Table taking up (at most) `mb` megabytes.
+	pub fn new(mb: usize) -> Self {
+		let len = num_slots(mb);
+		Self {
+			slots: (0..len).map(|_| [AtomicU64::new(0), AtomicU64::new(0)]).collect(),
+			mask: len as u64 - 1,
+		}
+	}
+
+	pub fn len(&self) -> usize {
+		self.slots.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.slots.is_empty()
+	}
+
+	pub fn clear(&self) {
+		for [key, data] in &self.slots {
+			key.store(0, Relaxed);
+			data.store(0, Relaxed);
+		}
+	}
+}
+
+impl TTable for SharedTranspositionTable {
+	#[inline]
+	fn probe(&self, key: u64) -> Option<TTEntry> {
+		let [stored_key, stored_data] = &self.slots[(key & self.mask) as usize];
+		let data = stored_data.load(Relaxed);
+		match stored_key.load(Relaxed) ^ data == key && data != 0 {
+			true => Some(unpack(data)),
+			false => None,
+		}
+	}
+
+	#[inline]
+	fn store(&self, key: u64, entry: TTEntry) {
+		let [stored_key, stored_data] = &self.slots[(key & self.mask) as usize];
+		let data = stored_data.load(Relaxed);
+		if replace(stored_key.load(Relaxed) ^ data, data, key, &entry) {
+			let data = pack(&entry);
+			stored_key.store(key ^ data, Relaxed);
+			stored_data.store(data, Relaxed);
+		}
+	}
+}
+
+/// No table at all: searches without memory.
+pub struct NoTable;
+
+impl TTable for NoTable {
+	#[inline]
+	fn probe(&self, _key: u64) -> Option<TTEntry> {
+		None
+	}
+
+	#[inline]
+	fn store(&self, _key: u64, _entry: TTEntry) {}
+}
+
+/// Largest power of two number of 16-byte slots that fits in `mb` megabytes (at least one).
+pub(super) fn num_slots(mb: usize) -> usize {
+	let bytes = mb << 20;
+	let slots = usize::max(1, bytes / 16);
+	1 << (usize::BITS - 1 - slots.leading_zeros())
+}
+
+/// Replace the entry in a slot, unless it holds the same position searched deeper.
+#[inline]
+fn replace(stored_key: u64, stored_data: u64, key: u64, entry: &TTEntry) -> bool {
+	stored_key != key || stored_data == 0 || unpack(stored_data).depth <= entry.depth
+}
+
+// Data layout (bits): value 0..32, depth 32..40, bound 40..42, move 42..63, valid 63.
+// The valid bit makes sure that no entry packs to 0, which marks an empty slot.
+
+fn pack(entry: &TTEntry) -> u64 {
+	let bound = match entry.bound {
+		Bound::Exact => 0,
+		Bound::Lower => 1,
+		Bound::Upper => 2,
+	};
+	let depth = u32::min(entry.depth, 255) as u64;
+	(entry.value as u32 as u64) | depth << 32 | bound << 40 | pack_move(entry.best_move) << 42 | 1 << 63
+}
+
+fn unpack(data: u64) -> TTEntry {
+	TTEntry {
+		value: data as u32 as i32,
+		depth: ((data >> 32) & 0xff) as u32,
+		bound: match (data >> 40) & 0b11 {
+			0 => Bound::Exact,
+			1 => Bound::Lower,
+			_ => Bound::Upper,
+		},
+		best_move: unpack_move((data >> 42) & 0x1f_ffff),
+	}
+}
+
+/// 21 bits: piece 0..4, from 4..10, to 10..16, promotion 16..20, present 20.
+pub(super) fn pack_move(mv: Option<Move>) -> u64 {
+	match mv {
+		None => 0,
+		Some(mv) => {
+			let promotion = mv.promotion.unwrap_or(Square::Empty);
+			(mv.piece.index() as u64) | (mv.from.index() as u64) << 4 | (mv.to.index() as u64) << 10 | (promotion.index() as u64) << 16 | 1 << 20
+		}
+	}
+}
+
+pub(super) fn unpack_move(bits: u64) -> Option<Move> {
+	if bits & (1 << 20) == 0 {
+		return None;
+	}
+	let piece = Square::ALL_SQUARES[(bits & 0xf) as usize];
+	let from = Pos::from_index(((bits >> 4) & 0x3f) as usize);
+	let to = Pos::from_index(((bits >> 10) & 0x3f) as usize);
+	Some(match Square::ALL_SQUARES[((bits >> 16) & 0xf) as usize] {
+		Square::Empty => Move::new(piece, from, to),
+		promotion => Move::with_promotion(piece, from, to, promotion),
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn entries() -> Vec<TTEntry> {
+		let mv = |s| Some(Move::from_str(s).unwrap());
+		vec![
+			TTEntry {
+				depth: 0,
+				bound: Bound::Exact,
+				value: 0,
+				best_move: None,
+			},
+			TTEntry {
+				depth: 3,
+				bound: Bound::Lower,
+				value: -INF,
+				best_move: mv("Pe2e4"),
+			},
+			TTEntry {
+				depth: 255,
+				bound: Bound::Upper,
+				value: INF - 3,
+				best_move: mv("pa2b1n"),
+			},
+			TTEntry {
+				depth: 12,
+				bound: Bound::Exact,
+				value: -1234,
+				best_move: mv("Ke1g1"),
+			},
+		]
+	}
+
+	#[test]
+	fn pack_unpack() {
+		for entry in entries() {
+			assert_eq!(unpack(pack(&entry)), entry);
+		}
+	}
+
+	#[test]
+	fn probe_store() {
+		let local = TranspositionTable::new(1);
+		let shared = SharedTranspositionTable::new(1);
+		assert_eq!(local.len(), 1 << 16);
+		assert_eq!(shared.len(), 1 << 16);
+		let tables: [&dyn TTable; 2] = [&local, &shared];
+
+		for table in tables {
+			let key = 0x_1234_5678_9abc_def0;
+			let [shallow, deep, ..] = [entries()[1], entries()[2]];
+			assert_eq!(table.probe(key), None);
+			table.store(key, deep);
+			assert_eq!(table.probe(key), Some(deep));
+			assert_eq!(table.probe(key ^ 1 << 40), None); // same slot, other key
+
+			// a shallower search does not replace a deeper one of the same position...
+			table.store(key, shallow);
+			assert_eq!(table.probe(key), Some(deep));
+
+			// ...but another position does.
+			table.store(key ^ 1 << 40, shallow);
+			assert_eq!(table.probe(key), None);
+			assert_eq!(table.probe(key ^ 1 << 40), Some(shallow));
+		}
+
+		local.clear();
+		shared.clear();
+		assert_eq!(local.probe(0x_1234_5678_9abc_def0 ^ 1 << 40), None);
+		assert_eq!(shared.probe(0x_1234_5678_9abc_def0 ^ 1 << 40), None);
+	}
+
+	#[test]
+	fn mate_distance() {
+		// mate 5 plies from the root, found at ply 2: 3 plies from there.
+		let value = INF - 5;
+		assert_eq!(value_to_tt(value, 2), INF - 3);
+		assert_eq!(value_from_tt(value_to_tt(value, 2), 2), value);
+		// the same position reached at ply 4: mate 7 plies from the root.
+		assert_eq!(value_from_tt(value_to_tt(value, 2), 4), INF - 7);
+		assert_eq!(value_from_tt(value_to_tt(-value, 2), 4), -(INF - 7));
+		assert_eq!(value_to_tt(1234, 7), 1234);
+	}
+
+	#[test]
+	fn num_slots() {
+		assert_eq!(super::num_slots(0), 1);
+		assert_eq!(super::num_slots(1), 1 << 16);
+		assert_eq!(super::num_slots(3), 1 << 17);
+	}
+}
@@ -3,17 +3,17 @@ use super::internal::*;
 pub fn heuristic1(board: &Board, player: Color) -> i32 {
 	let attck = board.attack_vectors();
 
-	-1_000_000 * is_check(board, &attck, player)
+	-1_000_000 * check(board, &attck, player)
 		+ 1000 * material(board, player)
 		+ 3 * protection(board, &attck, player)
 		+ 2 * threat(board, &attck, player)
-		+ 1 * mobility(&attck, player)
+		+ mobility(&attck, player)
 }
 
 pub fn heuristic2(board: &Board, player: Color) -> i32 {
 	let attck = board.attack_vectors();
 
-	-1_000_000 * is_check(board, &attck, player) + 1000 * material(board, player)
+	-1_000_000 * check(board, &attck, player) + 1000 * material(board, player)
 }
 
 pub fn material(board: &Board, player: Color) -> i32 {
@@ -24,8 +24,31 @@ pub fn material(board: &Board, player: Color) -> i32 {
 		* player.sign()
 }
 
+/// Material and piece placement in centipawns, blending middlegame and endgame
+/// piece-square tables by the phase of the game (see `Board::pst`).
+pub fn tapered(board: &Board, player: Color) -> i32 {
+	board.pst().taper(board.phase()) * player.sign()
+}
+
+/// `tapered`, plus the pawn structure and king safety (where given).
+pub fn tapered_with(board: &Board, player: Color, pawns: Option<&PawnStructure>, king: Option<&KingSafety>) -> i32 {
+	let mut value = board.pst();
+	if let Some(pawns) = pawns {
+		value += pawns.eval(board);
+	}
+	if let Some(king) = king {
+		value += king.eval(board, &board.attack_vectors());
+	}
+	value.taper(board.phase()) * player.sign()
+}
+
+/// 1 if `player` is in check, -1 if the opponent is, else 0.
+fn check(board: &Board, attck: &AttacVector, player: Color) -> i32 {
+	is_check(board, attck, player) - is_check(board, attck, player.opposite())
+}
+
 fn is_check(board: &Board, attck: &AttacVector, player: Color) -> i32 {
-	((board.bits(player.king()) & attck.all[player.opposite().index()]) == 0) as i32
+	((board.bits(player.king()) & attck.all[player.opposite().index()]) != 0) as i32
 }
 
 pub fn protection(board: &Board, attck: &AttacVector, player: Color) -> i32 {
@@ -83,3 +106,73 @@ pub fn king_distance(board: &Board, player: Color) -> i32 {
 pub fn zero(_board: &Board, _player: Color) -> i32 {
 	0
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn tapered_fen(fen: &str) -> i32 {
+		let position = Position::from_fen(fen).unwrap();
+		tapered(position.board(), position.player())
+	}
+
+	#[test]
+	fn heuristics_symmetric() {
+		for fen in [
+			STARTING_FEN,
+			"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+			"4k3/8/8/8/8/8/P7/4K3 w - - 0 1",
+			"rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", // white is mate
+		] {
+			let board = Board::from_fen(fen).unwrap();
+			for eval in [heuristic1, heuristic2] {
+				assert_eq!(eval(&board, White), -eval(&board, Black), "{}", fen);
+			}
+		}
+		assert_eq!(heuristic1(&Board::starting_position(), White), 0);
+		assert_eq!(heuristic2(&Board::starting_position(), White), 0);
+
+		// being in check is bad.
+		let board = Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+		assert!(heuristic2(&board, White) < -900_000);
+	}
+
+	#[test]
+	fn tapered_eval() {
+		assert_eq!(tapered(&Board::starting_position(), White), 0);
+		assert_eq!(
+			tapered_fen("4k3/8/8/8/8/8/P7/4K3 w - - 0 1"),
+			-tapered_fen("4k3/8/8/8/8/8/P7/4K3 b - - 0 1")
+		);
+		assert!(tapered_fen("4k3/8/8/8/8/8/P7/4K3 w - - 0 1") > 100);
+
+		// endgame: the king goes to the center, passed pawns run.
+		assert!(tapered_fen("4k3/8/8/8/4K3/8/P7/8 w - - 0 1") > tapered_fen("4k3/8/8/8/8/8/P7/K7 w - - 0 1"));
+		assert!(tapered_fen("4k3/8/P7/8/8/8/8/4K3 w - - 0 1") > tapered_fen("4k3/8/8/8/8/P7/8/4K3 w - - 0 1"));
+
+		// middlegame: the king stays castled.
+		let castled = "r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQ1RK1 w - - 6 6";
+		let center = "r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPPKPPP/R1BQ3R w - - 6 6";
+		assert!(tapered_fen(castled) > tapered_fen(center));
+	}
+
+	#[test]
+	fn tapered_with_terms() {
+		let pawns = PawnStructure::new(PawnWeights::default(), 1);
+		let king = KingSafety::default();
+		for fen in [
+			"4k3/8/8/8/8/8/P7/4K3 w - - 0 1",
+			"r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPPKPPP/R1BQ3R b - - 6 6",
+		] {
+			let position = Position::from_fen(fen).unwrap();
+			let (board, player) = (position.board(), position.player());
+			assert_eq!(tapered_with(board, player, None, None), tapered(board, player));
+			let pawn_terms = pawns.eval(board).taper(board.phase()) * player.sign();
+			assert!((tapered_with(board, player, Some(&pawns), None) - tapered(board, player) - pawn_terms).abs() <= 1);
+		}
+
+		// the uncastled king is worse off.
+		let center = Position::from_fen("r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPPKPPP/R1BQ3R w - - 6 6").unwrap();
+		assert!(tapered_with(center.board(), White, None, Some(&king)) < tapered(center.board(), White));
+	}
+}
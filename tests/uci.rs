@@ -0,0 +1,168 @@
+//! Scripted UCI sessions, piped into the `uci` binary.
+
+use std::io::prelude::*;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Run the uci binary on `script`, one command per line, sleeping where a line says `sleep <ms>`.
+/// Returns all output lines.
+fn session(script: &str) -> Vec<String> {
+	let mut uci = Command::new(env!("CARGO_BIN_EXE_uci"))
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.spawn()
+		.unwrap();
+
+	let mut stdin = uci.stdin.take().unwrap();
+	for line in script.lines().map(str::trim).filter(|line| !line.is_empty()) {
+		match line.strip_prefix("sleep ") {
+			Some(ms) => thread::sleep(Duration::from_millis(ms.parse().unwrap())),
+			None => writeln!(stdin, "{}", line).unwrap(),
+		}
+	}
+	drop(stdin);
+
+	let output = uci.wait_with_output().unwrap();
+	assert!(output.status.success());
+	String::from_utf8(output.stdout).unwrap().lines().map(str::to_owned).collect()
+}
+
+fn best_move(output: &[String]) -> &str {
+	let best = output.iter().filter_map(|line| line.strip_prefix("bestmove ")).collect::<Vec<_>>();
+	assert_eq!(best.len(), 1, "{:#?}", output);
+	best[0]
+}
+
+#[test]
+fn handshake() {
+	let output = session(
+		"
+		uci
+		isready
+		quit
+		",
+	);
+	assert!(output[0].starts_with("id name "));
+	assert!(output.iter().any(|line| line.starts_with("option name Hash type spin")));
+	assert!(output.iter().any(|line| line.starts_with("option name Engine type string")));
+	let uciok = output.iter().position(|line| line == "uciok").unwrap();
+	assert_eq!(output[uciok + 1], "readyok");
+}
+
+#[test]
+fn go_depth() {
+	let output = session(
+		"
+		setoption name Engine value palphabeta(eval=material)
+		position startpos moves e2e4 e7e5 g1f3
+		go depth 3
+		",
+	);
+	let info = output.iter().filter(|line| line.starts_with("info depth")).collect::<Vec<_>>();
+	assert_eq!(info.len(), 3, "{:#?}", output);
+	for (depth, line) in info.iter().enumerate() {
+		let words = line.split_whitespace().collect::<Vec<_>>();
+		assert_eq!(words[2], (depth + 1).to_string());
+		for field in ["score", "nodes", "nps", "time", "pv"] {
+			assert!(words.contains(&field), "{}", line);
+		}
+	}
+	let pv = info[2].split(" pv ").nth(1).unwrap();
+	assert_eq!(pv.split_whitespace().next(), Some(best_move(&output)));
+}
+
+#[test]
+fn mate_in_one() {
+	let output = session(
+		"
+		setoption name Engine value palphabeta(eval=material,qsearch=on)
+		position fen 6k1/5ppp/8/8/8/8/8/R3K3 w Q - 0 1
+		go depth 4
+		",
+	);
+	assert_eq!(best_move(&output), "a1a8");
+	assert!(output.iter().any(|line| line.contains("score mate 1 ")), "{:#?}", output);
+}
+
+#[test]
+fn mated() {
+	let output = session(
+		"
+		position fen R5k1/5ppp/8/8/8/8/8/4K3 b - - 1 1
+		go depth 2
+		",
+	);
+	assert_eq!(best_move(&output), "0000");
+}
+
+#[test]
+fn infinite_until_stop() {
+	let start = Instant::now();
+	let output = session(
+		"
+		setoption name Engine value palphabeta(eval=material)
+		setoption name Hash value 1
+		go infinite
+		sleep 300
+		isready
+		stop
+		quit
+		",
+	);
+	assert!(start.elapsed() >= Duration::from_millis(300));
+	let ready = output.iter().position(|line| line == "readyok").unwrap();
+	let best = output.iter().position(|line| line.starts_with("bestmove")).unwrap();
+	assert!(ready < best, "{:#?}", output);
+	assert_ne!(best_move(&output), "0000");
+}
+
+#[test]
+fn time_and_nodes() {
+	let start = Instant::now();
+	let output = session(
+		"
+		position startpos
+		go movetime 200
+		",
+	);
+	assert!(start.elapsed() < Duration::from_secs(5));
+	assert_ne!(best_move(&output), "0000");
+
+	let output = session(
+		"
+		position startpos
+		go wtime 1000 btime 1000 winc 10 binc 10
+		",
+	);
+	assert!(start.elapsed() < Duration::from_secs(10));
+	assert_ne!(best_move(&output), "0000");
+
+	let output = session(
+		"
+		position startpos moves d2d4
+		go nodes 5000
+		",
+	);
+	assert_ne!(best_move(&output), "0000");
+}
+
+#[test]
+fn errors() {
+	let output = session(
+		"
+		position startpos moves e2e5
+		setoption name Engine value nonsense
+		go depth 1
+		",
+	);
+	assert!(output.iter().any(|line| line == "info string error: illegal move: e2e5"), "{:#?}", output);
+	assert!(
+		output
+			.iter()
+			.any(|line| line.starts_with("info string error: engine 'nonsense': unknown algorithm")),
+		"{:#?}",
+		output
+	);
+	assert_ne!(best_move(&output), "0000");
+}